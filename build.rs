@@ -0,0 +1,82 @@
+//! Generates the D-Bus bindings for BlueZ.
+//!
+//! Client traits for the interfaces BlueZ implements are generated from
+//! `src/bluez.xml` into `gen.rs`, and server-side interface definitions for
+//! the objects blurst exports to BlueZ from `src/bluez_server.xml` into
+//! `gen_server.rs`. Both XML files record the BlueZ release they track.
+//! Without the `codegen` feature, the copies checked in under `src/` are
+//! used, so that building doesn't require `dbus-codegen`.
+//!
+//! With the `codegen` feature, the bindings are generated into `OUT_DIR` and
+//! a warning is printed if the checked-in copies are out of date. Set
+//! `BLURST_UPDATE_GEN=1` to overwrite them instead.
+
+fn main() {
+    #[cfg(feature = "codegen")]
+    codegen::run();
+}
+
+#[cfg(feature = "codegen")]
+mod codegen {
+    use std::env;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::process::Command;
+
+    use dbus_codegen::{generate, ConnectionType, GenOpts};
+
+    const CLIENT_XML: &str = "src/bluez.xml";
+    const SERVER_XML: &str = "src/bluez_server.xml";
+
+    pub fn run() {
+        println!("cargo:rerun-if-changed={}", CLIENT_XML);
+        println!("cargo:rerun-if-changed={}", SERVER_XML);
+        println!("cargo:rerun-if-env-changed=BLURST_UPDATE_GEN");
+
+        let client = GenOpts {
+            methodtype: None,
+            skipprefix: Some("org.bluez".into()),
+            connectiontype: ConnectionType::Blocking,
+            command_line: format!("-i org.bluez -c blocking --file {} -m None", CLIENT_XML),
+            ..GenOpts::default()
+        };
+        let server = GenOpts {
+            methodtype: None,
+            crossroads: true,
+            skipprefix: Some("org.bluez".into()),
+            command_line: format!("-i org.bluez --crossroads --file {} -m None", SERVER_XML),
+            ..GenOpts::default()
+        };
+        write(CLIENT_XML, &client, "gen.rs");
+        write(SERVER_XML, &server, "gen_server.rs");
+    }
+
+    fn write(xml: &str, opts: &GenOpts, name: &str) {
+        let xml =
+            fs::read_to_string(xml).unwrap_or_else(|e| panic!("failed to read {}: {}", xml, e));
+        let out = PathBuf::from(env::var("OUT_DIR").unwrap()).join(name);
+        let code = generate(&xml, opts).expect("failed to generate D-Bus bindings");
+        fs::write(&out, code).unwrap();
+        // Formatting is only needed to compare with the checked-in copy, so
+        // it's fine if rustfmt isn't available
+        let formatted = Command::new(env::var("RUSTFMT").unwrap_or_else(|_| "rustfmt".into()))
+            .args(["--edition", "2018"])
+            .arg(&out)
+            .status()
+            .is_ok_and(|s| s.success());
+
+        let checked_in = Path::new("src").join(name);
+        let generated = fs::read_to_string(&out).unwrap();
+        if fs::read_to_string(&checked_in).ok().as_ref() == Some(&generated) {
+            return;
+        }
+        if env::var_os("BLURST_UPDATE_GEN").is_some() && formatted {
+            fs::write(&checked_in, generated).unwrap();
+        } else {
+            println!(
+                "cargo:warning={} is out of date, rebuild with BLURST_UPDATE_GEN=1 to update it",
+                checked_in.display()
+            );
+        }
+    }
+}
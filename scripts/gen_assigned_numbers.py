@@ -0,0 +1,73 @@
+#!/usr/bin/env python3
+"""Generate src/uuids/assigned.rs from the Bluetooth SIG assigned numbers.
+
+Usage: gen_assigned_numbers.py <sig-public>/assigned_numbers/uuids <bluetooth-numbers-database>/v1
+
+The first argument is the uuids directory of the Bluetooth SIG public
+repository (https://bitbucket.org/bluetooth-SIG/public). The second is the v1
+directory of the Nordic Semiconductor bluetooth-numbers-database
+(https://github.com/NordicSemiconductor/bluetooth-numbers-database), whose
+names take precedence because they are more descriptive (e.g. "Battery
+Service" instead of "Battery"). Member UUIDs only come from the SIG.
+"""
+
+import json
+import os
+import re
+import sys
+
+TABLES = [
+    ("SERVICES", "service_uuids.yaml", "service_uuids.json"),
+    ("CHARACTERISTICS", "characteristic_uuids.yaml", "characteristic_uuids.json"),
+    ("DESCRIPTORS", "descriptors.yaml", "descriptor_uuids.json"),
+    ("MEMBERS", "member_uuids.yaml", None),
+]
+
+
+def parse_sig_yaml(path):
+    """Parse the simple list format used by the SIG, without needing PyYAML."""
+    entries = {}
+    uuid = None
+    with open(path) as f:
+        for line in f:
+            m = re.match(r"\s*-\s*uuid:\s*0x([0-9A-Fa-f]{4})\s*$", line)
+            if m:
+                uuid = int(m.group(1), 16)
+                continue
+            m = re.match(r"\s*name:\s*(.+?)\s*$", line)
+            if m and uuid is not None:
+                entries[uuid] = m.group(1).strip("'\"")
+                uuid = None
+    return entries
+
+
+def parse_nordic_json(path):
+    with open(path) as f:
+        return {
+            int(e["uuid"], 16): e["name"]
+            for e in json.load(f)
+            if len(e["uuid"]) == 4 and e.get("source", "gss") == "gss"
+        }
+
+
+def main():
+    sig_dir, nordic_dir = sys.argv[1:3]
+    out = [
+        "// This code was autogenerated with `scripts/gen_assigned_numbers.py`, from the Bluetooth",
+        "// SIG assigned numbers and the Nordic Semiconductor bluetooth-numbers-database.",
+    ]
+    for name, sig_file, nordic_file in TABLES:
+        entries = parse_sig_yaml(os.path.join(sig_dir, sig_file))
+        if nordic_file is not None:
+            entries.update(parse_nordic_json(os.path.join(nordic_dir, nordic_file)))
+        out.append("")
+        out.append(f"pub static {name}: &[(u16, &str)] = &[")
+        for uuid, desc in sorted(entries.items()):
+            desc = desc.replace("\\", "\\\\").replace('"', '\\"')
+            out.append(f'    (0x{uuid:04X}, "{desc}"),')
+        out.append("];")
+    print("\n".join(out))
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,323 @@
+//! Parsing and encoding of raw advertising data (AD) structures.
+
+use std::collections::HashMap;
+use std::convert::{TryFrom, TryInto};
+
+use uuid::Uuid;
+
+use crate::dbus::RefArgCast;
+use crate::{Device, Error};
+
+#[cfg(test)]
+mod test;
+
+/// AD type codes from the Bluetooth Core Specification Supplement
+pub mod ad_type {
+    pub const FLAGS: u8 = 0x01;
+    pub const INCOMPLETE_UUIDS_16: u8 = 0x02;
+    pub const COMPLETE_UUIDS_16: u8 = 0x03;
+    pub const INCOMPLETE_UUIDS_32: u8 = 0x04;
+    pub const COMPLETE_UUIDS_32: u8 = 0x05;
+    pub const INCOMPLETE_UUIDS_128: u8 = 0x06;
+    pub const COMPLETE_UUIDS_128: u8 = 0x07;
+    pub const SHORTENED_LOCAL_NAME: u8 = 0x08;
+    pub const COMPLETE_LOCAL_NAME: u8 = 0x09;
+    pub const TX_POWER_LEVEL: u8 = 0x0A;
+    pub const SOLICIT_UUIDS_16: u8 = 0x14;
+    pub const SOLICIT_UUIDS_128: u8 = 0x15;
+    pub const SERVICE_DATA_16: u8 = 0x16;
+    pub const APPEARANCE: u8 = 0x19;
+    pub const SOLICIT_UUIDS_32: u8 = 0x1F;
+    pub const SERVICE_DATA_32: u8 = 0x20;
+    pub const SERVICE_DATA_128: u8 = 0x21;
+    pub const URI: u8 = 0x24;
+    pub const LE_SUPPORTED_FEATURES: u8 = 0x27;
+    pub const MANUFACTURER_DATA: u8 = 0xFF;
+}
+
+/// A single AD structure. Structures that can't be decoded, either because
+/// the type is unknown or the data is malformed, are preserved as
+/// `Unknown`, so encoding a parsed structure always reproduces the original
+/// bytes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum AdStructure {
+    Flags(u8),
+    ServiceUuids16 {
+        complete: bool,
+        uuids: Vec<u16>,
+    },
+    ServiceUuids32 {
+        complete: bool,
+        uuids: Vec<u32>,
+    },
+    ServiceUuids128 {
+        complete: bool,
+        uuids: Vec<Uuid>,
+    },
+    LocalName {
+        complete: bool,
+        name: String,
+    },
+    TxPowerLevel(i8),
+    ServiceData16 {
+        uuid: u16,
+        data: Vec<u8>,
+    },
+    ServiceData32 {
+        uuid: u32,
+        data: Vec<u8>,
+    },
+    ServiceData128 {
+        uuid: Uuid,
+        data: Vec<u8>,
+    },
+    ManufacturerData {
+        company_id: u16,
+        data: Vec<u8>,
+    },
+    Appearance(u16),
+    /// URI, with the scheme encoded as a single code point as described in
+    /// the Bluetooth assigned numbers
+    Uri(String),
+    LeSupportedFeatures(Vec<u8>),
+    Unknown {
+        ad_type: u8,
+        data: Vec<u8>,
+    },
+}
+
+/// 128-bit UUIDs are transmitted in little endian byte order
+fn uuid_from_le(buf: &[u8]) -> Uuid {
+    let mut bytes: [u8; 16] = buf.try_into().unwrap();
+    bytes.reverse();
+    Uuid::from_bytes(bytes)
+}
+
+pub(crate) fn uuid_to_le(uuid: &Uuid) -> [u8; 16] {
+    let mut bytes = *uuid.as_bytes();
+    bytes.reverse();
+    bytes
+}
+
+fn chunks<const N: usize>(data: &[u8]) -> Option<impl Iterator<Item = [u8; N]> + '_> {
+    let chunks = data.chunks_exact(N);
+    chunks
+        .remainder()
+        .is_empty()
+        .then(|| chunks.map(|c| c.try_into().unwrap()))
+}
+
+impl AdStructure {
+    /// Decode the data of a single AD structure of the given type.
+    pub fn decode(ad_type: u8, data: &[u8]) -> Self {
+        use self::ad_type::*;
+
+        let decoded = match ad_type {
+            FLAGS if data.len() == 1 => Some(Self::Flags(data[0])),
+            INCOMPLETE_UUIDS_16 | COMPLETE_UUIDS_16 => chunks(data).map(|c| Self::ServiceUuids16 {
+                complete: ad_type == COMPLETE_UUIDS_16,
+                uuids: c.map(u16::from_le_bytes).collect(),
+            }),
+            INCOMPLETE_UUIDS_32 | COMPLETE_UUIDS_32 => chunks(data).map(|c| Self::ServiceUuids32 {
+                complete: ad_type == COMPLETE_UUIDS_32,
+                uuids: c.map(u32::from_le_bytes).collect(),
+            }),
+            INCOMPLETE_UUIDS_128 | COMPLETE_UUIDS_128 => {
+                chunks::<16>(data).map(|c| Self::ServiceUuids128 {
+                    complete: ad_type == COMPLETE_UUIDS_128,
+                    uuids: c.map(|u| uuid_from_le(&u)).collect(),
+                })
+            }
+            SHORTENED_LOCAL_NAME | COMPLETE_LOCAL_NAME => {
+                std::str::from_utf8(data).ok().map(|name| Self::LocalName {
+                    complete: ad_type == COMPLETE_LOCAL_NAME,
+                    name: name.to_owned(),
+                })
+            }
+            TX_POWER_LEVEL if data.len() == 1 => Some(Self::TxPowerLevel(data[0] as i8)),
+            SERVICE_DATA_16 if data.len() >= 2 => Some(Self::ServiceData16 {
+                uuid: u16::from_le_bytes(data[..2].try_into().unwrap()),
+                data: data[2..].to_vec(),
+            }),
+            SERVICE_DATA_32 if data.len() >= 4 => Some(Self::ServiceData32 {
+                uuid: u32::from_le_bytes(data[..4].try_into().unwrap()),
+                data: data[4..].to_vec(),
+            }),
+            SERVICE_DATA_128 if data.len() >= 16 => Some(Self::ServiceData128 {
+                uuid: uuid_from_le(&data[..16]),
+                data: data[16..].to_vec(),
+            }),
+            MANUFACTURER_DATA if data.len() >= 2 => Some(Self::ManufacturerData {
+                company_id: u16::from_le_bytes(data[..2].try_into().unwrap()),
+                data: data[2..].to_vec(),
+            }),
+            APPEARANCE if data.len() == 2 => Some(Self::Appearance(u16::from_le_bytes(
+                data.try_into().unwrap(),
+            ))),
+            URI => std::str::from_utf8(data)
+                .ok()
+                .map(|uri| Self::Uri(uri.to_owned())),
+            LE_SUPPORTED_FEATURES => Some(Self::LeSupportedFeatures(data.to_vec())),
+            _ => None,
+        };
+        decoded.unwrap_or_else(|| Self::Unknown {
+            ad_type,
+            data: data.to_vec(),
+        })
+    }
+
+    /// Parse a sequence of length-prefixed AD structures, as found in
+    /// advertising and scan response packets.
+    pub fn parse(mut buf: &[u8]) -> Result<Vec<Self>, Error> {
+        let mut structures = vec![];
+        while let Some((&len, rest)) = buf.split_first() {
+            // A zero length terminates the significant part of the data
+            if len == 0 {
+                break;
+            }
+            let len = usize::from(len);
+            if rest.len() < len {
+                return Err(Error::InvalidValue("AD structure is truncated".into()));
+            }
+            structures.push(Self::decode(rest[0], &rest[1..len]));
+            buf = &rest[len..];
+        }
+        Ok(structures)
+    }
+
+    /// AD type code of this structure
+    pub fn ad_type(&self) -> u8 {
+        use self::ad_type::*;
+
+        let select = |complete, complete_type, incomplete_type| {
+            if complete {
+                complete_type
+            } else {
+                incomplete_type
+            }
+        };
+        match self {
+            Self::Flags(_) => FLAGS,
+            Self::ServiceUuids16 { complete, .. } => {
+                select(*complete, COMPLETE_UUIDS_16, INCOMPLETE_UUIDS_16)
+            }
+            Self::ServiceUuids32 { complete, .. } => {
+                select(*complete, COMPLETE_UUIDS_32, INCOMPLETE_UUIDS_32)
+            }
+            Self::ServiceUuids128 { complete, .. } => {
+                select(*complete, COMPLETE_UUIDS_128, INCOMPLETE_UUIDS_128)
+            }
+            Self::LocalName { complete, .. } => {
+                select(*complete, COMPLETE_LOCAL_NAME, SHORTENED_LOCAL_NAME)
+            }
+            Self::TxPowerLevel(_) => TX_POWER_LEVEL,
+            Self::ServiceData16 { .. } => SERVICE_DATA_16,
+            Self::ServiceData32 { .. } => SERVICE_DATA_32,
+            Self::ServiceData128 { .. } => SERVICE_DATA_128,
+            Self::ManufacturerData { .. } => MANUFACTURER_DATA,
+            Self::Appearance(_) => APPEARANCE,
+            Self::Uri(_) => URI,
+            Self::LeSupportedFeatures(_) => LE_SUPPORTED_FEATURES,
+            Self::Unknown { ad_type, .. } => *ad_type,
+        }
+    }
+
+    /// Encode the data of this structure, without the length and type.
+    pub fn encode_data(&self) -> Vec<u8> {
+        let mut buf = vec![];
+        match self {
+            Self::Flags(flags) => buf.push(*flags),
+            Self::ServiceUuids16 { uuids, .. } => uuids
+                .iter()
+                .for_each(|u| buf.extend_from_slice(&u.to_le_bytes())),
+            Self::ServiceUuids32 { uuids, .. } => uuids
+                .iter()
+                .for_each(|u| buf.extend_from_slice(&u.to_le_bytes())),
+            Self::ServiceUuids128 { uuids, .. } => uuids
+                .iter()
+                .for_each(|u| buf.extend_from_slice(&uuid_to_le(u))),
+            Self::LocalName { name, .. } => buf.extend_from_slice(name.as_bytes()),
+            Self::TxPowerLevel(power) => buf.push(*power as u8),
+            Self::ServiceData16 { uuid, data } => {
+                buf.extend_from_slice(&uuid.to_le_bytes());
+                buf.extend_from_slice(data);
+            }
+            Self::ServiceData32 { uuid, data } => {
+                buf.extend_from_slice(&uuid.to_le_bytes());
+                buf.extend_from_slice(data);
+            }
+            Self::ServiceData128 { uuid, data } => {
+                buf.extend_from_slice(&uuid_to_le(uuid));
+                buf.extend_from_slice(data);
+            }
+            Self::ManufacturerData { company_id, data } => {
+                buf.extend_from_slice(&company_id.to_le_bytes());
+                buf.extend_from_slice(data);
+            }
+            Self::Appearance(appearance) => buf.extend_from_slice(&appearance.to_le_bytes()),
+            Self::Uri(uri) => buf.extend_from_slice(uri.as_bytes()),
+            Self::LeSupportedFeatures(data) | Self::Unknown { data, .. } => {
+                buf.extend_from_slice(data)
+            }
+        }
+        buf
+    }
+
+    /// Encode this structure, including the length and type. Fails if the
+    /// data is too long to fit in a single structure.
+    pub fn encode(&self) -> Result<Vec<u8>, Error> {
+        let data = self.encode_data();
+        // The length byte covers the type and the data
+        let len = u8::try_from(data.len() + 1).map_err(|_| Error::PayloadTooLarge {
+            len: data.len(),
+            max: usize::from(u8::MAX) - 1,
+        })?;
+        let mut buf = Vec::with_capacity(data.len() + 2);
+        buf.push(len);
+        buf.push(self.ad_type());
+        buf.extend_from_slice(&data);
+        Ok(buf)
+    }
+
+    /// Encode a sequence of structures.
+    pub fn encode_all<'a>(
+        structures: impl IntoIterator<Item = &'a Self>,
+    ) -> Result<Vec<u8>, Error> {
+        let mut buf = vec![];
+        for s in structures {
+            buf.extend(s.encode()?);
+        }
+        Ok(buf)
+    }
+}
+
+impl Device {
+    /// Get the raw AD structures from the most recent advertisement, as
+    /// exposed by BlueZ in the `AdvertisingFlags` and `AdvertisingData`
+    /// properties. BlueZ only exposes AD types that it doesn't handle itself,
+    /// so structures such as the local name or service UUIDs are usually not
+    /// included. Structures are sorted by AD type.
+    pub fn advertising_data(&self) -> Result<Vec<AdStructure>, Error> {
+        let mut structures = vec![];
+        if let Some(flags) = self
+            .properties
+            .get(Self::INTERFACE, "AdvertisingFlags")?
+            .map(|ref_arg| <Vec<u8>>::ref_arg_cast(ref_arg.as_ref()))
+            .transpose()?
+        {
+            // Flags are a single byte, but BlueZ exposes them as an array
+            structures.push(AdStructure::decode(ad_type::FLAGS, &flags));
+        }
+        let mut data: Vec<_> = self
+            .properties
+            .get(Self::INTERFACE, "AdvertisingData")?
+            .map(|ref_arg| <HashMap<u8, Vec<u8>>>::ref_arg_cast(ref_arg.as_ref()))
+            .transpose()?
+            .unwrap_or_default()
+            .into_iter()
+            .collect();
+        data.sort_by_key(|(t, _)| *t);
+        structures.extend(data.into_iter().map(|(t, d)| AdStructure::decode(t, &d)));
+        Ok(structures)
+    }
+}
@@ -0,0 +1,136 @@
+use proptest::collection::vec;
+use proptest::prelude::*;
+
+use super::*;
+
+#[test]
+fn parse_advertisement() {
+    let buf = [
+        0x02, 0x01, 0x06, // Flags
+        0x03, 0x03, 0x0F, 0x18, // Complete 16-bit UUIDs: Battery Service
+        0x05, 0x09, b'T', b'e', b's', b't', // Complete local name
+        0x02, 0x0A, 0xF4, // TX power: -12 dBm
+        0x05, 0xFF, 0x4C, 0x00, 0x01, 0x02, // Manufacturer data
+        0x00, 0x00, // Padding
+    ];
+    assert_eq!(
+        AdStructure::parse(&buf).unwrap(),
+        vec![
+            AdStructure::Flags(0x06),
+            AdStructure::ServiceUuids16 {
+                complete: true,
+                uuids: vec![0x180F],
+            },
+            AdStructure::LocalName {
+                complete: true,
+                name: "Test".into(),
+            },
+            AdStructure::TxPowerLevel(-12),
+            AdStructure::ManufacturerData {
+                company_id: 0x004C,
+                data: vec![0x01, 0x02],
+            },
+        ]
+    );
+}
+
+#[test]
+fn parse_uuid128() {
+    let uuid = Uuid::parse_str("6e400001-b5a3-f393-e0a9-e50e24dcca9e").unwrap();
+    let mut buf = vec![0x11, 0x07];
+    buf.extend(uuid.as_bytes().iter().rev());
+    assert_eq!(
+        AdStructure::parse(&buf).unwrap(),
+        vec![AdStructure::ServiceUuids128 {
+            complete: true,
+            uuids: vec![uuid],
+        }]
+    );
+}
+
+#[test]
+fn parse_malformed() {
+    // 16-bit UUID list with an odd length
+    assert_eq!(
+        AdStructure::decode(ad_type::COMPLETE_UUIDS_16, &[0x0F]),
+        AdStructure::Unknown {
+            ad_type: ad_type::COMPLETE_UUIDS_16,
+            data: vec![0x0F],
+        }
+    );
+    AdStructure::parse(&[0x05, 0x09, b'a']).unwrap_err();
+}
+
+#[test]
+fn encode_too_long() {
+    AdStructure::ManufacturerData {
+        company_id: 0,
+        data: vec![0; 300],
+    }
+    .encode()
+    .unwrap_err();
+}
+
+#[test]
+fn encode_max_length() {
+    // 254 bytes of data plus the type give the largest length byte
+    let encoded = AdStructure::Uri("a".repeat(254)).encode().unwrap();
+    assert_eq!(encoded.len(), 256);
+    assert_eq!(encoded[0], 255);
+    assert_eq!(
+        AdStructure::parse(&encoded).unwrap(),
+        vec![AdStructure::Uri("a".repeat(254))]
+    );
+    match AdStructure::Uri("a".repeat(255)).encode() {
+        Err(Error::PayloadTooLarge { len: 255, max: 254 }) => {}
+        r => panic!("unexpected result: {:?}", r),
+    }
+}
+
+fn ad_structure() -> impl Strategy<Value = AdStructure> {
+    let bytes = || vec(any::<u8>(), 0..24);
+    prop_oneof![
+        any::<u8>().prop_map(AdStructure::Flags),
+        (any::<bool>(), vec(any::<u16>(), 0..8))
+            .prop_map(|(complete, uuids)| AdStructure::ServiceUuids16 { complete, uuids }),
+        (any::<bool>(), vec(any::<u32>(), 0..4))
+            .prop_map(|(complete, uuids)| AdStructure::ServiceUuids32 { complete, uuids }),
+        (
+            any::<bool>(),
+            vec(any::<u128>().prop_map(Uuid::from_u128), 0..2)
+        )
+            .prop_map(|(complete, uuids)| AdStructure::ServiceUuids128 { complete, uuids }),
+        (any::<bool>(), "\\PC{0,20}")
+            .prop_map(|(complete, name)| AdStructure::LocalName { complete, name }),
+        any::<i8>().prop_map(AdStructure::TxPowerLevel),
+        (any::<u16>(), bytes()).prop_map(|(uuid, data)| AdStructure::ServiceData16 { uuid, data }),
+        (any::<u32>(), bytes()).prop_map(|(uuid, data)| AdStructure::ServiceData32 { uuid, data }),
+        (any::<u128>().prop_map(Uuid::from_u128), bytes())
+            .prop_map(|(uuid, data)| AdStructure::ServiceData128 { uuid, data }),
+        (any::<u16>(), bytes())
+            .prop_map(|(company_id, data)| AdStructure::ManufacturerData { company_id, data }),
+        any::<u16>().prop_map(AdStructure::Appearance),
+        "\\PC{0,20}".prop_map(AdStructure::Uri),
+        bytes().prop_map(AdStructure::LeSupportedFeatures),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn structure_round_trip(structures in vec(ad_structure(), 0..8)) {
+        let buf = AdStructure::encode_all(&structures).unwrap();
+        prop_assert_eq!(AdStructure::parse(&buf).unwrap(), structures);
+    }
+
+    #[test]
+    fn bytes_round_trip(raw in vec((1..=255u8, vec(any::<u8>(), 0..30)), 0..8)) {
+        let mut buf = vec![];
+        for (ad_type, data) in &raw {
+            buf.push(data.len() as u8 + 1);
+            buf.push(*ad_type);
+            buf.extend_from_slice(data);
+        }
+        let structures = AdStructure::parse(&buf).unwrap();
+        prop_assert_eq!(AdStructure::encode_all(&structures).unwrap(), buf);
+    }
+}
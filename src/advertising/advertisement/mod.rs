@@ -0,0 +1,602 @@
+//! Publishing LE advertisements through BlueZ.
+
+use std::collections::HashMap;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use dbus::arg::{PropMap, RefArg, Variant};
+use dbus::MethodErr;
+use uuid::Uuid;
+
+use super::ad::{ad_type, uuid_to_le};
+use super::AdStructure;
+use crate::dbus::missing_as_default;
+use crate::gen::LEAdvertisingManager1;
+use crate::gen_server::{register_leadvertisement1, LEAdvertisement1};
+use crate::server::{milliseconds, optional, seconds};
+use crate::{get_optional_property, Adapter, BluetoothUuidExt, Bluez, DBusProxy, Error, ResultExt};
+
+#[cfg(test)]
+mod test;
+
+const MANAGER_INTERFACE: &str = "org.bluez.LEAdvertisingManager1";
+
+/// Maximum advertising data length for legacy advertising, used if BlueZ
+/// doesn't report `MaxAdvLen`
+const LEGACY_MAX_ADV_LEN: u8 = 31;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AdvertisementType {
+    /// Non-connectable advertisement
+    Broadcast,
+    /// Connectable advertisement
+    Peripheral,
+}
+
+impl AdvertisementType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Broadcast => "broadcast",
+            Self::Peripheral => "peripheral",
+        }
+    }
+}
+
+/// Data that BlueZ adds to the advertisement itself.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Include {
+    TxPower,
+    Appearance,
+    LocalName,
+    /// Resolvable Set Identifier of a coordinated set
+    Rsi,
+}
+
+impl Include {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::TxPower => "tx-power",
+            Self::Appearance => "appearance",
+            Self::LocalName => "local-name",
+            Self::Rsi => "rsi",
+        }
+    }
+
+    pub fn from_name(s: &str) -> Option<Self> {
+        match s {
+            "tx-power" => Some(Self::TxPower),
+            "appearance" => Some(Self::Appearance),
+            "local-name" => Some(Self::LocalName),
+            "rsi" => Some(Self::Rsi),
+            _ => None,
+        }
+    }
+}
+
+/// PHY used on the secondary advertising channel, for extended advertising.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SecondaryChannel {
+    Le1M,
+    Le2M,
+    Coded,
+}
+
+impl SecondaryChannel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Le1M => "1M",
+            Self::Le2M => "2M",
+            Self::Coded => "Coded",
+        }
+    }
+
+    pub fn from_name(s: &str) -> Option<Self> {
+        match s {
+            "1M" => Some(Self::Le1M),
+            "2M" => Some(Self::Le2M),
+            "Coded" => Some(Self::Coded),
+            _ => None,
+        }
+    }
+}
+
+/// An LE advertisement, registered with `Adapter::register_advertisement()`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Advertisement {
+    advertisement_type: AdvertisementType,
+    service_uuids: Vec<Uuid>,
+    manufacturer_data: HashMap<u16, Vec<u8>>,
+    solicit_uuids: Vec<Uuid>,
+    service_data: HashMap<Uuid, Vec<u8>>,
+    local_name: Option<String>,
+    appearance: Option<u16>,
+    includes: Vec<Include>,
+    tx_power: Option<i16>,
+    discoverable: Option<bool>,
+    duration: Option<Duration>,
+    timeout: Option<Duration>,
+    min_interval: Option<Duration>,
+    max_interval: Option<Duration>,
+    secondary_channel: Option<SecondaryChannel>,
+}
+
+impl Advertisement {
+    pub fn new(advertisement_type: AdvertisementType) -> Self {
+        Self {
+            advertisement_type,
+            service_uuids: Vec::new(),
+            manufacturer_data: HashMap::new(),
+            solicit_uuids: Vec::new(),
+            service_data: HashMap::new(),
+            local_name: None,
+            appearance: None,
+            includes: Vec::new(),
+            tx_power: None,
+            discoverable: None,
+            duration: None,
+            timeout: None,
+            min_interval: None,
+            max_interval: None,
+            secondary_channel: None,
+        }
+    }
+
+    pub fn service_uuid(mut self, uuid: Uuid) -> Self {
+        self.service_uuids.push(uuid);
+        self
+    }
+
+    pub fn manufacturer_data(mut self, company_id: u16, data: impl Into<Vec<u8>>) -> Self {
+        self.manufacturer_data.insert(company_id, data.into());
+        self
+    }
+
+    pub fn solicit_uuid(mut self, uuid: Uuid) -> Self {
+        self.solicit_uuids.push(uuid);
+        self
+    }
+
+    pub fn service_data(mut self, uuid: Uuid, data: impl Into<Vec<u8>>) -> Self {
+        self.service_data.insert(uuid, data.into());
+        self
+    }
+
+    pub fn local_name(mut self, name: impl Into<String>) -> Self {
+        self.local_name = Some(name.into());
+        self
+    }
+
+    /// Set the GAP appearance value, see the Bluetooth Assigned Numbers.
+    pub fn appearance(mut self, appearance: u16) -> Self {
+        self.appearance = Some(appearance);
+        self
+    }
+
+    pub fn include(mut self, include: Include) -> Self {
+        if !self.includes.contains(&include) {
+            self.includes.push(include);
+        }
+        self
+    }
+
+    /// Request a TX power in dBm. The power actually used may differ, and is
+    /// only advertised with `Include::TxPower`.
+    pub fn tx_power(mut self, tx_power: i16) -> Self {
+        self.tx_power = Some(tx_power);
+        self
+    }
+
+    /// Set whether the general discoverable flag is advertised. By default,
+    /// BlueZ sets it if the adapter is discoverable.
+    pub fn discoverable(mut self, discoverable: bool) -> Self {
+        self.discoverable = Some(discoverable);
+        self
+    }
+
+    /// How long this advertisement is advertised at a time when BlueZ rotates
+    /// between multiple advertisements. BlueZ uses a resolution of one
+    /// second.
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    /// Stop advertising after the timeout, at which point BlueZ releases the
+    /// advertisement. BlueZ uses a resolution of one second.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Set the range for the advertising interval. BlueZ uses a resolution of
+    /// one millisecond.
+    pub fn interval(mut self, min: Duration, max: Duration) -> Self {
+        self.min_interval = Some(min);
+        self.max_interval = Some(max);
+        self
+    }
+
+    /// Use extended advertising with the given secondary channel PHY.
+    pub fn secondary_channel(mut self, channel: SecondaryChannel) -> Self {
+        self.secondary_channel = Some(channel);
+        self
+    }
+
+    /// The AD structures that end up in the advertising data, following the
+    /// same rules as BlueZ. Flags, TX power and appearance, which BlueZ and
+    /// the kernel add themselves, are included with placeholder values. The
+    /// local name is left out, since it is shortened to fit or moved to the
+    /// scan response.
+    pub fn ad_structures(&self) -> Vec<AdStructure> {
+        let mut structures = vec![];
+        if self.advertisement_type == AdvertisementType::Peripheral
+            || self.discoverable == Some(true)
+        {
+            structures.push(AdStructure::Flags(0));
+        }
+        structures.extend(uuid_structures(
+            &self.service_uuids,
+            [
+                ad_type::COMPLETE_UUIDS_16,
+                ad_type::COMPLETE_UUIDS_32,
+                ad_type::COMPLETE_UUIDS_128,
+            ],
+        ));
+        structures.extend(uuid_structures(
+            &self.solicit_uuids,
+            [
+                ad_type::SOLICIT_UUIDS_16,
+                ad_type::SOLICIT_UUIDS_32,
+                ad_type::SOLICIT_UUIDS_128,
+            ],
+        ));
+        let mut manufacturer_data: Vec<_> = self.manufacturer_data.iter().collect();
+        manufacturer_data.sort_by_key(|(id, _)| **id);
+        structures.extend(manufacturer_data.into_iter().map(|(&company_id, data)| {
+            AdStructure::ManufacturerData {
+                company_id,
+                data: data.clone(),
+            }
+        }));
+        let mut service_data: Vec<_> = self.service_data.iter().collect();
+        service_data.sort_by_key(|(uuid, _)| **uuid);
+        structures.extend(service_data.into_iter().map(|(uuid, data)| {
+            let data = data.clone();
+            match (uuid.as_bluetooth_u16(), uuid.as_bluetooth_short()) {
+                (Some(uuid), _) => AdStructure::ServiceData16 { uuid, data },
+                (None, Some(uuid)) => AdStructure::ServiceData32 { uuid, data },
+                (None, None) => AdStructure::ServiceData128 { uuid: *uuid, data },
+            }
+        }));
+        if self.includes.contains(&Include::TxPower) {
+            structures.push(AdStructure::TxPowerLevel(0));
+        }
+        if self.appearance.is_some() || self.includes.contains(&Include::Appearance) {
+            structures.push(AdStructure::Appearance(self.appearance.unwrap_or(0)));
+        }
+        structures
+    }
+
+    /// Length of the advertising data in bytes, see `ad_structures()`.
+    pub fn advertising_data_len(&self) -> usize {
+        self.ad_structures()
+            .iter()
+            .map(|s| s.encode_data().len() + 2)
+            .sum()
+    }
+}
+
+/// Group UUIDs into lists of 16-bit, 32-bit and 128-bit UUIDs, using the
+/// given AD types for each size.
+fn uuid_structures(uuids: &[Uuid], types: [u8; 3]) -> Vec<AdStructure> {
+    let mut lists: [Vec<u8>; 3] = Default::default();
+    for uuid in uuids {
+        match (uuid.as_bluetooth_u16(), uuid.as_bluetooth_short()) {
+            (Some(short), _) => lists[0].extend_from_slice(&short.to_le_bytes()),
+            (None, Some(short)) => lists[1].extend_from_slice(&short.to_le_bytes()),
+            (None, None) => lists[2].extend_from_slice(&uuid_to_le(uuid)),
+        }
+    }
+    types
+        .iter()
+        .zip(lists)
+        .filter(|(_, data)| !data.is_empty())
+        .map(|(&t, data)| AdStructure::decode(t, &data))
+        .collect()
+}
+
+/// Advertising capabilities of an adapter, from `LEAdvertisingManager1`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct AdvertisingCapabilities {
+    /// Number of advertisements currently registered
+    pub active_instances: u8,
+    /// Number of additional advertisements that can be registered
+    pub supported_instances: u8,
+    pub supported_includes: Vec<Include>,
+    pub supported_secondary_channels: Vec<SecondaryChannel>,
+    /// Features such as "CanSetTxPower" or "HardwareOffload"
+    pub supported_features: Vec<String>,
+    /// Maximum length of the advertising data in bytes
+    pub max_adv_len: Option<u8>,
+    /// Maximum length of the scan response data in bytes
+    pub max_scan_response_len: Option<u8>,
+    /// Minimum TX power in dBm
+    pub min_tx_power: Option<i16>,
+    /// Maximum TX power in dBm
+    pub max_tx_power: Option<i16>,
+}
+
+impl AdvertisingCapabilities {
+    fn parse_capabilities(&mut self, capabilities: &PropMap) -> Result<(), Error> {
+        self.max_adv_len = get_optional_property(capabilities, "MaxAdvLen")?;
+        self.max_scan_response_len = get_optional_property(capabilities, "MaxScnRspLen")?;
+        self.min_tx_power = get_optional_property(capabilities, "MinTxPower")?;
+        self.max_tx_power = get_optional_property(capabilities, "MaxTxPower")?;
+        Ok(())
+    }
+
+    /// Check that the advertising data of an advertisement fits. If BlueZ
+    /// doesn't report the maximum length, the legacy advertising limit is
+    /// used, unless the advertisement uses extended advertising.
+    pub fn check(&self, advertisement: &Advertisement) -> Result<(), Error> {
+        let max = match self.max_adv_len {
+            Some(max) => max,
+            None if advertisement.secondary_channel.is_none() => LEGACY_MAX_ADV_LEN,
+            None => return Ok(()),
+        };
+        let len = advertisement.advertising_data_len();
+        if len > usize::from(max) {
+            return Err(Error::PayloadTooLarge {
+                len,
+                max: usize::from(max),
+            });
+        }
+        Ok(())
+    }
+}
+
+fn uuid_strings(uuids: &[Uuid]) -> Vec<String> {
+    uuids.iter().map(Uuid::to_string).collect()
+}
+
+struct AdvertisementObject {
+    advertisement: Advertisement,
+    released: Arc<AtomicBool>,
+}
+
+impl LEAdvertisement1 for AdvertisementObject {
+    fn release(&mut self) -> Result<(), MethodErr> {
+        self.released.store(true, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn type_(&self) -> Result<String, MethodErr> {
+        Ok(self.advertisement.advertisement_type.as_str().to_owned())
+    }
+
+    fn service_uuids(&self) -> Result<Vec<String>, MethodErr> {
+        Ok(uuid_strings(&self.advertisement.service_uuids))
+    }
+
+    fn manufacturer_data(&self) -> Result<HashMap<u16, Variant<Box<dyn RefArg>>>, MethodErr> {
+        Ok(self
+            .advertisement
+            .manufacturer_data
+            .iter()
+            .map(|(&id, data)| (id, Variant(Box::new(data.clone()) as Box<dyn RefArg>)))
+            .collect())
+    }
+
+    fn solicit_uuids(&self) -> Result<Vec<String>, MethodErr> {
+        Ok(uuid_strings(&self.advertisement.solicit_uuids))
+    }
+
+    fn service_data(&self) -> Result<PropMap, MethodErr> {
+        Ok(self
+            .advertisement
+            .service_data
+            .iter()
+            .map(|(uuid, data)| {
+                (
+                    uuid.to_string(),
+                    Variant(Box::new(data.clone()) as Box<dyn RefArg>),
+                )
+            })
+            .collect())
+    }
+
+    fn includes(&self) -> Result<Vec<String>, MethodErr> {
+        Ok(self
+            .advertisement
+            .includes
+            .iter()
+            .map(|i| i.as_str().to_owned())
+            .collect())
+    }
+
+    fn local_name(&self) -> Result<String, MethodErr> {
+        optional(self.advertisement.local_name.clone(), "LocalName")
+    }
+
+    fn appearance(&self) -> Result<u16, MethodErr> {
+        optional(self.advertisement.appearance, "Appearance")
+    }
+
+    fn duration(&self) -> Result<u16, MethodErr> {
+        optional(self.advertisement.duration.map(seconds), "Duration")
+    }
+
+    fn timeout(&self) -> Result<u16, MethodErr> {
+        optional(self.advertisement.timeout.map(seconds), "Timeout")
+    }
+
+    fn discoverable(&self) -> Result<bool, MethodErr> {
+        optional(self.advertisement.discoverable, "Discoverable")
+    }
+
+    fn secondary_channel(&self) -> Result<String, MethodErr> {
+        optional(
+            self.advertisement
+                .secondary_channel
+                .map(|c| c.as_str().to_owned()),
+            "SecondaryChannel",
+        )
+    }
+
+    fn min_interval(&self) -> Result<u32, MethodErr> {
+        optional(
+            self.advertisement.min_interval.map(milliseconds),
+            "MinInterval",
+        )
+    }
+
+    fn max_interval(&self) -> Result<u32, MethodErr> {
+        optional(
+            self.advertisement.max_interval.map(milliseconds),
+            "MaxInterval",
+        )
+    }
+
+    fn tx_power(&self) -> Result<i16, MethodErr> {
+        optional(self.advertisement.tx_power, "TxPower")
+    }
+}
+
+/// An advertisement registered with BlueZ. Dropping the handle unregisters
+/// the advertisement.
+pub struct AdvertisementHandle {
+    bluez: Rc<Bluez>,
+    adapter: DBusProxy,
+    path: dbus::Path<'static>,
+    released: Arc<AtomicBool>,
+}
+
+impl AdvertisementHandle {
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Whether BlueZ has released the advertisement, for example because its
+    /// timeout expired or the adapter was powered off. A released
+    /// advertisement is no longer advertised and has to be registered again.
+    pub fn is_released(&self) -> bool {
+        // Release is handled while processing messages
+        self.bluez.process(Duration::from_millis(0)).ok();
+        self.released.load(Ordering::Relaxed)
+    }
+}
+
+impl Drop for AdvertisementHandle {
+    fn drop(&mut self) {
+        let server = self.bluez.object_server();
+        if !self.released.load(Ordering::Relaxed) {
+            server
+                .call::<_, ()>(
+                    &self.adapter,
+                    MANAGER_INTERFACE,
+                    "UnregisterAdvertisement",
+                    (self.path.clone(),),
+                )
+                .ok();
+        }
+        server.remove::<AdvertisementObject>(&self.path);
+    }
+}
+
+impl Adapter {
+    pub fn advertising_capabilities(&self) -> Result<AdvertisingCapabilities, Error> {
+        let adapter = &self.adapter;
+        let includes = missing_as_default(adapter.supported_includes()).context(
+            adapter,
+            MANAGER_INTERFACE,
+            "SupportedIncludes",
+        )?;
+        let secondary_channels = missing_as_default(adapter.supported_secondary_channels())
+            .context(adapter, MANAGER_INTERFACE, "SupportedSecondaryChannels")?;
+        let mut capabilities = AdvertisingCapabilities {
+            active_instances: adapter.active_instances().context(
+                adapter,
+                MANAGER_INTERFACE,
+                "ActiveInstances",
+            )?,
+            supported_instances: adapter.supported_instances().context(
+                adapter,
+                MANAGER_INTERFACE,
+                "SupportedInstances",
+            )?,
+            supported_includes: includes
+                .iter()
+                .filter_map(|i| Include::from_name(i))
+                .collect(),
+            supported_secondary_channels: secondary_channels
+                .iter()
+                .filter_map(|c| SecondaryChannel::from_name(c))
+                .collect(),
+            supported_features: missing_as_default(adapter.supported_features()).context(
+                adapter,
+                MANAGER_INTERFACE,
+                "SupportedFeatures",
+            )?,
+            ..Default::default()
+        };
+        capabilities.parse_capabilities(
+            &missing_as_default(adapter.supported_capabilities()).context(
+                adapter,
+                MANAGER_INTERFACE,
+                "SupportedCapabilities",
+            )?,
+        )?;
+        Ok(capabilities)
+    }
+
+    /// Export an advertisement and register it with BlueZ, which starts
+    /// advertising it. The advertisement is unregistered when the returned
+    /// handle is dropped.
+    ///
+    /// Fails with `Error::PayloadTooLarge` if the advertising data doesn't
+    /// fit, see `AdvertisingCapabilities::check()`.
+    pub fn register_advertisement(
+        &self,
+        advertisement: Advertisement,
+    ) -> Result<AdvertisementHandle, Error> {
+        self.advertising_capabilities()?.check(&advertisement)?;
+        let server = self.bluez.object_server();
+        let iface = server.interface(
+            "le-advertisement",
+            register_leadvertisement1::<AdvertisementObject>,
+        );
+        let path = server.unique_path("advertisement");
+        let released = Arc::new(AtomicBool::new(false));
+        server.insert(
+            path.clone(),
+            &[iface],
+            AdvertisementObject {
+                advertisement,
+                released: released.clone(),
+            },
+        );
+        let handle = AdvertisementHandle {
+            bluez: self.bluez.clone(),
+            adapter: self.adapter.clone(),
+            path: path.clone(),
+            released,
+        };
+        // If this fails, the advertisement was never registered, so mark it
+        // released to only remove the object when the handle is dropped
+        server
+            .call::<_, ()>(
+                &self.adapter,
+                MANAGER_INTERFACE,
+                "RegisterAdvertisement",
+                (path, PropMap::new()),
+            )
+            .context(&self.adapter, MANAGER_INTERFACE, "RegisterAdvertisement")
+            .map_err(|e| {
+                handle.released.store(true, Ordering::Relaxed);
+                e
+            })?;
+        Ok(handle)
+    }
+}
@@ -0,0 +1,105 @@
+use super::*;
+
+#[test]
+fn builder_includes() {
+    let advertisement = Advertisement::new(AdvertisementType::Peripheral)
+        .include(Include::TxPower)
+        .include(Include::LocalName)
+        .include(Include::TxPower);
+    assert_eq!(
+        advertisement.includes,
+        vec![Include::TxPower, Include::LocalName]
+    );
+}
+
+#[test]
+fn names() {
+    for include in [
+        Include::TxPower,
+        Include::Appearance,
+        Include::LocalName,
+        Include::Rsi,
+    ] {
+        assert_eq!(Include::from_name(include.as_str()), Some(include));
+    }
+    for channel in [
+        SecondaryChannel::Le1M,
+        SecondaryChannel::Le2M,
+        SecondaryChannel::Coded,
+    ] {
+        assert_eq!(SecondaryChannel::from_name(channel.as_str()), Some(channel));
+    }
+    assert_eq!(Include::from_name("bogus"), None);
+}
+
+#[test]
+fn duration_resolution() {
+    assert_eq!(seconds(Duration::from_millis(2500)), 2);
+    assert_eq!(seconds(Duration::from_secs(100_000)), u16::MAX);
+    assert_eq!(milliseconds(Duration::from_micros(20_500)), 20);
+}
+
+#[test]
+fn advertising_data_len() {
+    let advertisement = Advertisement::new(AdvertisementType::Peripheral)
+        .service_uuid(crate::uuid16(0x180F))
+        .service_uuid(crate::uuid16(0x180A))
+        .service_uuid(Uuid::from_u128(0x12345678_1234_5678_1234_567812345678))
+        .manufacturer_data(0x004C, vec![1, 2, 3])
+        .include(Include::TxPower)
+        .local_name("ignored");
+    assert_eq!(
+        advertisement.ad_structures(),
+        vec![
+            AdStructure::Flags(0),
+            AdStructure::ServiceUuids16 {
+                complete: true,
+                uuids: vec![0x180F, 0x180A],
+            },
+            AdStructure::ServiceUuids128 {
+                complete: true,
+                uuids: vec![Uuid::from_u128(0x12345678_1234_5678_1234_567812345678)],
+            },
+            AdStructure::ManufacturerData {
+                company_id: 0x004C,
+                data: vec![1, 2, 3],
+            },
+            AdStructure::TxPowerLevel(0),
+        ]
+    );
+    assert_eq!(advertisement.advertising_data_len(), 3 + 6 + 18 + 7 + 3);
+
+    let broadcast = Advertisement::new(AdvertisementType::Broadcast)
+        .service_data(crate::uuid16(0xFCD2), vec![0x40]);
+    assert_eq!(broadcast.advertising_data_len(), 5);
+}
+
+#[test]
+fn check_capabilities() {
+    let advertisement =
+        Advertisement::new(AdvertisementType::Broadcast).manufacturer_data(0xFFFF, vec![0; 27]);
+    assert_eq!(advertisement.advertising_data_len(), 31);
+
+    let mut capabilities = AdvertisingCapabilities::default();
+    assert!(capabilities.check(&advertisement).is_ok());
+    let too_long = advertisement.clone().appearance(0x0340);
+    match capabilities.check(&too_long) {
+        Err(Error::PayloadTooLarge { len: 35, max: 31 }) => {}
+        r => panic!("unexpected result: {:?}", r),
+    }
+    // No limit is known for extended advertising
+    assert!(capabilities
+        .check(&too_long.clone().secondary_channel(SecondaryChannel::Le1M))
+        .is_ok());
+
+    let mut properties = PropMap::new();
+    properties.insert("MaxAdvLen".into(), Variant(Box::new(251u8)));
+    properties.insert("MinTxPower".into(), Variant(Box::new(-34i16)));
+    properties.insert("MaxTxPower".into(), Variant(Box::new(10i16)));
+    capabilities.parse_capabilities(&properties).unwrap();
+    assert_eq!(capabilities.max_adv_len, Some(251));
+    assert_eq!(capabilities.max_scan_response_len, None);
+    assert_eq!(capabilities.min_tx_power, Some(-34));
+    assert_eq!(capabilities.max_tx_power, Some(10));
+    assert!(capabilities.check(&too_long).is_ok());
+}
@@ -0,0 +1,252 @@
+//! Decoders for iBeacon, Eddystone and AltBeacon advertisements.
+
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::time::Duration;
+
+use uuid::Uuid;
+
+use crate::{uuid16, Device, Error};
+
+#[cfg(test)]
+mod test;
+
+/// Apple company identifier, used for iBeacon manufacturer data
+pub const APPLE_COMPANY_ID: u16 = 0x004C;
+/// Service data UUID used by Eddystone
+pub const EDDYSTONE_UUID: Uuid = uuid16(0xFEAA);
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Beacon {
+    IBeacon(IBeacon),
+    Eddystone(Eddystone),
+    AltBeacon(AltBeacon),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct IBeacon {
+    pub uuid: Uuid,
+    pub major: u16,
+    pub minor: u16,
+    /// Calibrated RSSI at 1 meter, in dBm
+    pub measured_power: i8,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AltBeacon {
+    pub company_id: u16,
+    pub beacon_id: [u8; 20],
+    /// Average RSSI at 1 meter, in dBm
+    pub reference_rssi: i8,
+    pub reserved: u8,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Eddystone {
+    Uid {
+        /// Calibrated TX power at 0 meters, in dBm
+        tx_power: i8,
+        namespace: [u8; 10],
+        instance: [u8; 6],
+    },
+    Url {
+        /// Calibrated TX power at 0 meters, in dBm
+        tx_power: i8,
+        url: String,
+    },
+    Tlm(EddystoneTlm),
+    Eid {
+        /// Calibrated TX power at 0 meters, in dBm
+        tx_power: i8,
+        eid: [u8; 8],
+    },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum EddystoneTlm {
+    Plain {
+        /// Battery voltage in millivolts, if supported
+        battery_voltage: Option<u16>,
+        /// Beacon temperature in degrees Celsius, if supported
+        temperature: Option<f32>,
+        /// Number of advertisements sent since power-on or reboot
+        advertisement_count: u32,
+        /// Time since power-on or reboot
+        uptime: Duration,
+    },
+    /// Encrypted TLM frame, which can only be decoded with the beacon's
+    /// identity key
+    Encrypted { data: [u8; 12], salt: u16, mic: u16 },
+}
+
+fn invalid(what: &str, len: usize) -> Error {
+    Error::InvalidValue(format!("{} has invalid length {}", what, len))
+}
+
+fn array<const N: usize>(buf: &[u8]) -> [u8; N] {
+    buf[..N].try_into().unwrap()
+}
+
+/// Decode an iBeacon from manufacturer data. Returns `Ok(None)` if the data is
+/// not an iBeacon.
+pub fn decode_ibeacon(company_id: u16, data: &[u8]) -> Result<Option<IBeacon>, Error> {
+    if company_id != APPLE_COMPANY_ID || !data.starts_with(&[0x02, 0x15]) {
+        return Ok(None);
+    }
+    if data.len() != 23 {
+        return Err(invalid("iBeacon", data.len()));
+    }
+    Ok(Some(IBeacon {
+        uuid: Uuid::from_bytes(array(&data[2..])),
+        major: u16::from_be_bytes(array(&data[18..])),
+        minor: u16::from_be_bytes(array(&data[20..])),
+        measured_power: data[22] as i8,
+    }))
+}
+
+/// Decode an AltBeacon from manufacturer data. Returns `Ok(None)` if the data
+/// is not an AltBeacon.
+pub fn decode_altbeacon(company_id: u16, data: &[u8]) -> Result<Option<AltBeacon>, Error> {
+    if !data.starts_with(&[0xBE, 0xAC]) {
+        return Ok(None);
+    }
+    if data.len() != 24 {
+        return Err(invalid("AltBeacon", data.len()));
+    }
+    Ok(Some(AltBeacon {
+        company_id,
+        beacon_id: array(&data[2..]),
+        reference_rssi: data[22] as i8,
+        reserved: data[23],
+    }))
+}
+
+const EDDYSTONE_URL_SCHEMES: [&str; 4] = ["http://www.", "https://www.", "http://", "https://"];
+const EDDYSTONE_URL_EXPANSIONS: [&str; 14] = [
+    ".com/", ".org/", ".edu/", ".net/", ".info/", ".biz/", ".gov/", ".com", ".org", ".edu", ".net",
+    ".info", ".biz", ".gov",
+];
+
+/// Decode an Eddystone frame from the service data for `EDDYSTONE_UUID`.
+/// Returns `Ok(None)` for frame types that aren't known to this crate.
+pub fn decode_eddystone(data: &[u8]) -> Result<Option<Eddystone>, Error> {
+    let frame_type = *data.first().ok_or_else(|| invalid("Eddystone frame", 0))?;
+    let check_len = |what, min| {
+        if data.len() < min {
+            Err(invalid(what, data.len()))
+        } else {
+            Ok(())
+        }
+    };
+    match frame_type {
+        0x00 => {
+            // Two reserved bytes at the end are optional
+            check_len("Eddystone-UID", 18)?;
+            Ok(Some(Eddystone::Uid {
+                tx_power: data[1] as i8,
+                namespace: array(&data[2..]),
+                instance: array(&data[12..]),
+            }))
+        }
+        0x10 => {
+            check_len("Eddystone-URL", 3)?;
+            let mut url = EDDYSTONE_URL_SCHEMES
+                .get(usize::from(data[2]))
+                .ok_or_else(|| {
+                    Error::InvalidValue(format!("invalid Eddystone-URL scheme: {}", data[2]))
+                })?
+                .to_string();
+            for &b in &data[3..] {
+                match EDDYSTONE_URL_EXPANSIONS.get(usize::from(b)) {
+                    Some(expansion) => url.push_str(expansion),
+                    None if (0x21..0x7F).contains(&b) => url.push(char::from(b)),
+                    None => {
+                        return Err(Error::InvalidValue(format!(
+                            "invalid Eddystone-URL character: {:#04x}",
+                            b
+                        )))
+                    }
+                }
+            }
+            Ok(Some(Eddystone::Url {
+                tx_power: data[1] as i8,
+                url,
+            }))
+        }
+        0x20 => {
+            check_len("Eddystone-TLM", 2)?;
+            match data[1] {
+                0x00 => {
+                    check_len("Eddystone-TLM", 14)?;
+                    let battery_voltage = u16::from_be_bytes(array(&data[2..]));
+                    let temperature = i16::from_be_bytes(array(&data[4..]));
+                    let uptime = u32::from_be_bytes(array(&data[10..]));
+                    Ok(Some(Eddystone::Tlm(EddystoneTlm::Plain {
+                        battery_voltage: (battery_voltage != 0).then_some(battery_voltage),
+                        // Signed 8.8 fixed point, with 0x8000 meaning unsupported
+                        temperature: (temperature != i16::MIN)
+                            .then(|| f32::from(temperature) / 256.0),
+                        advertisement_count: u32::from_be_bytes(array(&data[6..])),
+                        // Uptime has a resolution of 0.1 seconds
+                        uptime: Duration::from_millis(u64::from(uptime) * 100),
+                    })))
+                }
+                0x01 => {
+                    check_len("Eddystone-eTLM", 18)?;
+                    Ok(Some(Eddystone::Tlm(EddystoneTlm::Encrypted {
+                        data: array(&data[2..]),
+                        salt: u16::from_be_bytes(array(&data[14..])),
+                        mic: u16::from_be_bytes(array(&data[16..])),
+                    })))
+                }
+                // Unknown TLM version
+                _ => Ok(None),
+            }
+        }
+        0x30 => {
+            check_len("Eddystone-EID", 10)?;
+            Ok(Some(Eddystone::Eid {
+                tx_power: data[1] as i8,
+                eid: array(&data[2..]),
+            }))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Decode a beacon from the service data and manufacturer data of an
+/// advertisement. Returns `Ok(None)` if the advertisement doesn't contain a
+/// known beacon format.
+pub fn decode(
+    service_data: &HashMap<Uuid, Vec<u8>>,
+    manufacturer_data: &HashMap<u16, Vec<u8>>,
+) -> Result<Option<Beacon>, Error> {
+    if let Some(data) = service_data.get(&EDDYSTONE_UUID) {
+        if let Some(eddystone) = decode_eddystone(data)? {
+            return Ok(Some(Beacon::Eddystone(eddystone)));
+        }
+    }
+    // Check the manufacturer data in a fixed order, so the result doesn't
+    // depend on the order of the map
+    let mut company_ids: Vec<_> = manufacturer_data.keys().copied().collect();
+    company_ids.sort_unstable();
+    for company_id in company_ids {
+        let data = &manufacturer_data[&company_id];
+        if let Some(beacon) = decode_ibeacon(company_id, data)? {
+            return Ok(Some(Beacon::IBeacon(beacon)));
+        }
+        if let Some(beacon) = decode_altbeacon(company_id, data)? {
+            return Ok(Some(Beacon::AltBeacon(beacon)));
+        }
+    }
+    Ok(None)
+}
+
+impl Device {
+    /// Decode a beacon from the most recent advertisement of this device.
+    /// Returns `Ok(None)` if the device isn't advertising a known beacon
+    /// format.
+    pub fn beacon(&self) -> Result<Option<Beacon>, Error> {
+        decode(&self.service_data()?, &self.manufacturer_data()?)
+    }
+}
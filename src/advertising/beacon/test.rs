@@ -0,0 +1,118 @@
+use super::*;
+
+#[test]
+fn ibeacon() {
+    let mut data = vec![0x02, 0x15];
+    data.extend_from_slice(
+        Uuid::parse_str("e2c56db5-dffb-48d2-b060-d0f5a71096e0")
+            .unwrap()
+            .as_bytes(),
+    );
+    data.extend_from_slice(&[0x00, 0x01, 0x00, 0x02, 0xC5]);
+    assert_eq!(
+        decode_ibeacon(APPLE_COMPANY_ID, &data).unwrap(),
+        Some(IBeacon {
+            uuid: Uuid::parse_str("e2c56db5-dffb-48d2-b060-d0f5a71096e0").unwrap(),
+            major: 1,
+            minor: 2,
+            measured_power: -59,
+        })
+    );
+    assert_eq!(decode_ibeacon(0x0059, &data).unwrap(), None);
+    decode_ibeacon(APPLE_COMPANY_ID, &data[..20]).unwrap_err();
+}
+
+#[test]
+fn altbeacon() {
+    let mut data = vec![0xBE, 0xAC];
+    data.extend(1..=20);
+    data.extend_from_slice(&[0xBC, 0x00]);
+    let beacon = decode_altbeacon(0x0118, &data).unwrap().unwrap();
+    assert_eq!(beacon.company_id, 0x0118);
+    assert_eq!(beacon.beacon_id[0], 1);
+    assert_eq!(beacon.reference_rssi, -68);
+}
+
+#[test]
+fn eddystone_uid() {
+    let data = [
+        0x00, 0xEE, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 0, 0,
+    ];
+    assert_eq!(
+        decode_eddystone(&data).unwrap(),
+        Some(Eddystone::Uid {
+            tx_power: -18,
+            namespace: [0, 1, 2, 3, 4, 5, 6, 7, 8, 9],
+            instance: [10, 11, 12, 13, 14, 15],
+        })
+    );
+}
+
+#[test]
+fn eddystone_url() {
+    let mut data = vec![0x10, 0xEB, 0x01];
+    data.extend_from_slice(b"example");
+    data.push(0x07);
+    assert_eq!(
+        decode_eddystone(&data).unwrap(),
+        Some(Eddystone::Url {
+            tx_power: -21,
+            url: "https://www.example.com".into(),
+        })
+    );
+}
+
+#[test]
+fn eddystone_tlm() {
+    let data = [
+        0x20, 0x00, 0x0B, 0xB8, 0x18, 0x80, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x64,
+    ];
+    assert_eq!(
+        decode_eddystone(&data).unwrap(),
+        Some(Eddystone::Tlm(EddystoneTlm::Plain {
+            battery_voltage: Some(3000),
+            temperature: Some(24.5),
+            advertisement_count: 256,
+            uptime: Duration::from_secs(10),
+        }))
+    );
+}
+
+#[test]
+fn decode_service_data() {
+    let mut service_data = HashMap::new();
+    service_data.insert(EDDYSTONE_UUID, vec![0x30, 0x00, 1, 2, 3, 4, 5, 6, 7, 8]);
+    assert_eq!(
+        decode(&service_data, &HashMap::new()).unwrap(),
+        Some(Beacon::Eddystone(Eddystone::Eid {
+            tx_power: 0,
+            eid: [1, 2, 3, 4, 5, 6, 7, 8],
+        }))
+    );
+    assert_eq!(decode(&HashMap::new(), &HashMap::new()).unwrap(), None);
+}
+
+#[test]
+fn eddystone_unknown_frame() {
+    assert_eq!(decode_eddystone(&[0x50, 0x00, 0x01]).unwrap(), None);
+    assert_eq!(decode_eddystone(&[0x20, 0x02, 0x00]).unwrap(), None);
+    decode_eddystone(&[]).unwrap_err();
+}
+
+#[test]
+fn decode_manufacturer_data_order() {
+    let mut altbeacon = vec![0xBE, 0xAC];
+    altbeacon.extend(1..=20);
+    altbeacon.extend_from_slice(&[0xBC, 0x00]);
+    let mut manufacturer_data = HashMap::new();
+    for company_id in (0x0100..0x0110).rev() {
+        manufacturer_data.insert(company_id, altbeacon.clone());
+    }
+    // Falls through to the manufacturer data for unknown Eddystone frames
+    let mut service_data = HashMap::new();
+    service_data.insert(EDDYSTONE_UUID, vec![0x50]);
+    match decode(&service_data, &manufacturer_data).unwrap() {
+        Some(Beacon::AltBeacon(beacon)) => assert_eq!(beacon.company_id, 0x0100),
+        b => panic!("unexpected beacon: {:?}", b),
+    }
+}
@@ -0,0 +1,420 @@
+//! Decoder for BTHome v2 sensor advertisements (https://bthome.io).
+
+use std::convert::TryInto;
+use std::time::Duration;
+
+use aes::Aes128;
+use ccm::aead::generic_array::GenericArray;
+use ccm::aead::{Aead, KeyInit};
+use ccm::consts::{U13, U4};
+use ccm::Ccm;
+use uuid::Uuid;
+
+use crate::util::{parse_address, parse_key};
+use crate::{uuid16, Adapter, Device, Error, Timeouts};
+
+#[cfg(test)]
+mod test;
+
+const BTHOME_UUID16: u16 = 0xFCD2;
+/// Service data UUID used by BTHome
+pub const BTHOME_UUID: Uuid = uuid16(BTHOME_UUID16);
+
+/// AES-128 key used to encrypt BTHome advertisements
+pub type BindKey = [u8; 16];
+
+type BthomeCcm = Ccm<Aes128, U4, U13>;
+
+const DEVICE_INFO_ENCRYPTED: u8 = 1 << 0;
+const DEVICE_INFO_TRIGGER_BASED: u8 = 1 << 2;
+const DEVICE_INFO_VERSION_SHIFT: u8 = 5;
+const VERSION: u8 = 2;
+
+/// Parse a bind key from its hexadecimal representation.
+pub fn parse_bind_key(hex: &str) -> Result<BindKey, Error> {
+    parse_key(hex, "BTHome bind key")
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ButtonEvent {
+    None,
+    Press,
+    DoublePress,
+    TriplePress,
+    LongPress,
+    LongDoublePress,
+    LongTriplePress,
+    HoldPress,
+    Unknown(u8),
+}
+
+impl From<u8> for ButtonEvent {
+    fn from(v: u8) -> Self {
+        match v {
+            0x00 => Self::None,
+            0x01 => Self::Press,
+            0x02 => Self::DoublePress,
+            0x03 => Self::TriplePress,
+            0x04 => Self::LongPress,
+            0x05 => Self::LongDoublePress,
+            0x06 => Self::LongTriplePress,
+            0x80 => Self::HoldPress,
+            v => Self::Unknown(v),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DimmerEvent {
+    None,
+    RotateLeft(u8),
+    RotateRight(u8),
+    Unknown(u8, u8),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    /// Numeric value, with the scaling factor already applied
+    Number(f64),
+    Binary(bool),
+    Button(ButtonEvent),
+    Dimmer(DimmerEvent),
+    Text(String),
+    Raw(Vec<u8>),
+    Timestamp(Duration),
+    /// Firmware version, most significant component first
+    Version(Vec<u8>),
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Format {
+    Unsigned(usize),
+    Signed(usize),
+    Binary,
+    Button,
+    Dimmer,
+    Text,
+    Raw,
+    Timestamp,
+    Version(usize),
+}
+
+struct ObjectType {
+    id: u8,
+    name: &'static str,
+    format: Format,
+    factor: f64,
+    unit: Option<&'static str>,
+}
+
+const fn object(
+    id: u8,
+    name: &'static str,
+    format: Format,
+    factor: f64,
+    unit: Option<&'static str>,
+) -> ObjectType {
+    ObjectType {
+        id,
+        name,
+        format,
+        factor,
+        unit,
+    }
+}
+
+const fn binary(id: u8, name: &'static str) -> ObjectType {
+    object(id, name, Format::Binary, 1.0, None)
+}
+
+use Format::{Signed as S, Unsigned as U};
+
+/// Object types defined by BTHome v2, sorted by ID
+#[rustfmt::skip]
+static OBJECT_TYPES: &[ObjectType] = &[
+    object(0x00, "packet id", U(1), 1.0, None),
+    object(0x01, "battery", U(1), 1.0, Some("%")),
+    object(0x02, "temperature", S(2), 0.01, Some("°C")),
+    object(0x03, "humidity", U(2), 0.01, Some("%")),
+    object(0x04, "pressure", U(3), 0.01, Some("hPa")),
+    object(0x05, "illuminance", U(3), 0.01, Some("lux")),
+    object(0x06, "mass", U(2), 0.01, Some("kg")),
+    object(0x07, "mass", U(2), 0.01, Some("lb")),
+    object(0x08, "dewpoint", S(2), 0.01, Some("°C")),
+    object(0x09, "count", U(1), 1.0, None),
+    object(0x0A, "energy", U(3), 0.001, Some("kWh")),
+    object(0x0B, "power", U(3), 0.01, Some("W")),
+    object(0x0C, "voltage", U(2), 0.001, Some("V")),
+    object(0x0D, "pm2.5", U(2), 1.0, Some("µg/m³")),
+    object(0x0E, "pm10", U(2), 1.0, Some("µg/m³")),
+    binary(0x0F, "generic boolean"),
+    binary(0x10, "power"),
+    binary(0x11, "opening"),
+    object(0x12, "co2", U(2), 1.0, Some("ppm")),
+    object(0x13, "tvoc", U(2), 1.0, Some("µg/m³")),
+    object(0x14, "moisture", U(2), 0.01, Some("%")),
+    binary(0x15, "battery"),
+    binary(0x16, "battery charging"),
+    binary(0x17, "carbon monoxide"),
+    binary(0x18, "cold"),
+    binary(0x19, "connectivity"),
+    binary(0x1A, "door"),
+    binary(0x1B, "garage door"),
+    binary(0x1C, "gas"),
+    binary(0x1D, "heat"),
+    binary(0x1E, "light"),
+    binary(0x1F, "lock"),
+    binary(0x20, "moisture"),
+    binary(0x21, "motion"),
+    binary(0x22, "moving"),
+    binary(0x23, "occupancy"),
+    binary(0x24, "plug"),
+    binary(0x25, "presence"),
+    binary(0x26, "problem"),
+    binary(0x27, "running"),
+    binary(0x28, "safety"),
+    binary(0x29, "smoke"),
+    binary(0x2A, "sound"),
+    binary(0x2B, "tamper"),
+    binary(0x2C, "vibration"),
+    binary(0x2D, "window"),
+    object(0x2E, "humidity", U(1), 1.0, Some("%")),
+    object(0x2F, "moisture", U(1), 1.0, Some("%")),
+    object(0x3A, "button", Format::Button, 1.0, None),
+    object(0x3C, "dimmer", Format::Dimmer, 1.0, None),
+    object(0x3D, "count", U(2), 1.0, None),
+    object(0x3E, "count", U(4), 1.0, None),
+    object(0x3F, "rotation", S(2), 0.1, Some("°")),
+    object(0x40, "distance", U(2), 1.0, Some("mm")),
+    object(0x41, "distance", U(2), 0.1, Some("m")),
+    object(0x42, "duration", U(3), 0.001, Some("s")),
+    object(0x43, "current", U(2), 0.001, Some("A")),
+    object(0x44, "speed", U(2), 0.01, Some("m/s")),
+    object(0x45, "temperature", S(2), 0.1, Some("°C")),
+    object(0x46, "uv index", U(1), 0.1, None),
+    object(0x47, "volume", U(2), 0.1, Some("L")),
+    object(0x48, "volume", U(2), 1.0, Some("mL")),
+    object(0x49, "volume flow rate", U(2), 0.001, Some("m³/h")),
+    object(0x4A, "voltage", U(2), 0.1, Some("V")),
+    object(0x4B, "gas", U(3), 0.001, Some("m³")),
+    object(0x4C, "gas", U(4), 0.001, Some("m³")),
+    object(0x4D, "energy", U(4), 0.001, Some("kWh")),
+    object(0x4E, "volume", U(4), 0.001, Some("L")),
+    object(0x4F, "water", U(4), 0.001, Some("L")),
+    object(0x50, "timestamp", Format::Timestamp, 1.0, None),
+    object(0x51, "acceleration", U(2), 0.001, Some("m/s²")),
+    object(0x52, "gyroscope", U(2), 0.001, Some("°/s")),
+    object(0x53, "text", Format::Text, 1.0, None),
+    object(0x54, "raw", Format::Raw, 1.0, None),
+    object(0x55, "volume storage", U(4), 0.001, Some("L")),
+    object(0x56, "conductivity", U(2), 1.0, Some("µS/cm")),
+    object(0x57, "temperature", S(1), 1.0, Some("°C")),
+    object(0x58, "temperature", S(1), 0.35, Some("°C")),
+    object(0x59, "count", S(1), 1.0, None),
+    object(0x5A, "count", S(2), 1.0, None),
+    object(0x5B, "count", S(4), 1.0, None),
+    object(0x5C, "power", S(4), 0.01, Some("W")),
+    object(0x5D, "current", S(2), 0.001, Some("A")),
+    object(0x5E, "direction", U(2), 0.01, Some("°")),
+    object(0x5F, "precipitation", U(2), 0.1, Some("mm")),
+    object(0x60, "channel", U(1), 1.0, None),
+    object(0x61, "rotational speed", U(2), 1.0, Some("rpm")),
+    object(0xF0, "device type id", U(2), 1.0, None),
+    object(0xF1, "firmware version", Format::Version(4), 1.0, None),
+    object(0xF2, "firmware version", Format::Version(3), 1.0, None),
+];
+
+/// A single measurement or event from a BTHome packet.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Measurement {
+    pub id: u8,
+    pub name: &'static str,
+    pub unit: Option<&'static str>,
+    pub value: Value,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Packet {
+    pub encrypted: bool,
+    /// Whether the device only advertises when triggered, rather than at a
+    /// regular interval
+    pub trigger_based: bool,
+    /// Counter used to encrypt the packet, which can be used for replay
+    /// protection
+    pub counter: Option<u32>,
+    pub measurements: Vec<Measurement>,
+}
+
+fn unsigned(buf: &[u8]) -> u64 {
+    buf.iter()
+        .rev()
+        .fold(0, |acc, b| (acc << 8) | u64::from(*b))
+}
+
+fn signed(buf: &[u8]) -> i64 {
+    let shift = 64 - 8 * buf.len();
+    ((unsigned(buf) << shift) as i64) >> shift
+}
+
+/// Take `len` bytes from the start of `buf`.
+fn take<'a>(buf: &mut &'a [u8], len: usize, id: u8) -> Result<&'a [u8], Error> {
+    if buf.len() < len {
+        return Err(Error::InvalidValue(format!(
+            "BTHome object {:#04x} is truncated",
+            id
+        )));
+    }
+    let (data, rest) = buf.split_at(len);
+    *buf = rest;
+    Ok(data)
+}
+
+fn decode_objects(mut buf: &[u8]) -> Result<Vec<Measurement>, Error> {
+    let mut measurements = vec![];
+    while let Some((&id, rest)) = buf.split_first() {
+        buf = rest;
+        let object_type = OBJECT_TYPES
+            .binary_search_by_key(&id, |t| t.id)
+            .map(|i| &OBJECT_TYPES[i])
+            // Objects have no length prefix, so we can't continue past one we
+            // don't know
+            .map_err(|_| Error::InvalidValue(format!("unknown BTHome object: {:#04x}", id)))?;
+        let value = match object_type.format {
+            Format::Unsigned(len) => {
+                Value::Number(unsigned(take(&mut buf, len, id)?) as f64 * object_type.factor)
+            }
+            Format::Signed(len) => {
+                Value::Number(signed(take(&mut buf, len, id)?) as f64 * object_type.factor)
+            }
+            Format::Binary => Value::Binary(take(&mut buf, 1, id)?[0] != 0),
+            Format::Button => Value::Button(take(&mut buf, 1, id)?[0].into()),
+            Format::Dimmer => {
+                let data = take(&mut buf, 2, id)?;
+                Value::Dimmer(match data[0] {
+                    0x00 => DimmerEvent::None,
+                    0x01 => DimmerEvent::RotateLeft(data[1]),
+                    0x02 => DimmerEvent::RotateRight(data[1]),
+                    e => DimmerEvent::Unknown(e, data[1]),
+                })
+            }
+            Format::Text => {
+                let len = take(&mut buf, 1, id)?[0];
+                let data = take(&mut buf, usize::from(len), id)?;
+                Value::Text(String::from_utf8_lossy(data).into_owned())
+            }
+            Format::Raw => {
+                let len = take(&mut buf, 1, id)?[0];
+                Value::Raw(take(&mut buf, usize::from(len), id)?.to_vec())
+            }
+            Format::Timestamp => {
+                Value::Timestamp(Duration::from_secs(unsigned(take(&mut buf, 4, id)?)))
+            }
+            Format::Version(len) => {
+                Value::Version(take(&mut buf, len, id)?.iter().rev().copied().collect())
+            }
+        };
+        measurements.push(Measurement {
+            id,
+            name: object_type.name,
+            unit: object_type.unit,
+            value,
+        });
+    }
+    Ok(measurements)
+}
+
+/// Decode BTHome v2 service data. `address` is the address of the advertising
+/// device as reported by BlueZ, which is needed to decrypt encrypted packets
+/// along with the bind key.
+pub fn decode(data: &[u8], address: &str, key: Option<&BindKey>) -> Result<Packet, Error> {
+    let (&device_info, payload) = data
+        .split_first()
+        .ok_or_else(|| Error::InvalidValue("BTHome packet is empty".into()))?;
+    let version = device_info >> DEVICE_INFO_VERSION_SHIFT;
+    if version != VERSION {
+        return Err(Error::InvalidValue(format!(
+            "unsupported BTHome version: {}",
+            version
+        )));
+    }
+    let encrypted = device_info & DEVICE_INFO_ENCRYPTED != 0;
+    let trigger_based = device_info & DEVICE_INFO_TRIGGER_BASED != 0;
+
+    if !encrypted {
+        return Ok(Packet {
+            encrypted,
+            trigger_based,
+            counter: None,
+            measurements: decode_objects(payload)?,
+        });
+    }
+
+    let key = key.ok_or_else(|| {
+        Error::InvalidValue("BTHome packet is encrypted, but no bind key was provided".into())
+    })?;
+    // Encrypted payload is followed by a 4 byte counter and a 4 byte MIC
+    if payload.len() < 8 {
+        return Err(Error::InvalidValue(
+            "encrypted BTHome packet is truncated".into(),
+        ));
+    }
+    let (ciphertext, trailer) = payload.split_at(payload.len() - 8);
+    let (counter, mic) = trailer.split_at(4);
+
+    let mut nonce = Vec::with_capacity(13);
+    nonce.extend_from_slice(&parse_address(address)?);
+    nonce.extend_from_slice(&BTHOME_UUID16.to_le_bytes());
+    nonce.push(device_info);
+    nonce.extend_from_slice(counter);
+
+    let mut sealed = ciphertext.to_vec();
+    sealed.extend_from_slice(mic);
+    let plaintext = BthomeCcm::new(GenericArray::from_slice(key))
+        .decrypt(GenericArray::from_slice(&nonce), sealed.as_slice())
+        .map_err(|_| Error::InvalidValue("failed to decrypt BTHome packet".into()))?;
+
+    Ok(Packet {
+        encrypted,
+        trigger_based,
+        counter: Some(u32::from_le_bytes(counter.try_into().unwrap())),
+        measurements: decode_objects(&plaintext)?,
+    })
+}
+
+impl Device {
+    /// Decode the BTHome service data from the most recent advertisement of
+    /// this device. Returns `Ok(None)` if the device isn't advertising BTHome
+    /// data.
+    pub fn bthome(&self, key: Option<&BindKey>) -> Result<Option<Packet>, Error> {
+        self.service_data()?
+            .get(&BTHOME_UUID)
+            .map(|data| decode(data, &self.address()?, key))
+            .transpose()
+    }
+}
+
+impl Adapter {
+    /// Find all devices that have advertised BTHome service data.
+    pub fn find_bthome_devices(&self) -> Result<Vec<Device>, Error> {
+        self.find_bthome_devices_with_timeouts(&self.timeouts)
+    }
+
+    pub fn find_bthome_devices_with_timeouts(
+        &self,
+        timeouts: &Timeouts,
+    ) -> Result<Vec<Device>, Error> {
+        let uuid = BTHOME_UUID.to_string();
+        self.find_devices_with_timeouts(
+            |p| {
+                Ok(p.get("ServiceData")
+                    .and_then(|d| d.0.as_iter())
+                    .is_some_and(|i| {
+                        // Keys and values are interleaved
+                        i.step_by(2)
+                            .any(|k| k.as_str().is_some_and(|k| k.eq_ignore_ascii_case(&uuid)))
+                    }))
+            },
+            timeouts,
+        )
+    }
+}
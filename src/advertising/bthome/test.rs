@@ -0,0 +1,82 @@
+use super::*;
+
+const ADDRESS: &str = "54:48:E6:8F:80:A5";
+
+#[test]
+fn unencrypted() {
+    // Temperature 25.06 °C, humidity 50.55 %, battery 97 %
+    let data = [0x40, 0x02, 0xCA, 0x09, 0x03, 0xBF, 0x13, 0x01, 0x61];
+    let packet = decode(&data, ADDRESS, None).unwrap();
+    assert!(!packet.encrypted);
+    assert_eq!(packet.counter, None);
+    let values: Vec<_> = packet
+        .measurements
+        .iter()
+        .map(|m| (m.name, m.unit, m.value.clone()))
+        .collect();
+    assert_eq!(
+        values,
+        vec![
+            ("temperature", Some("°C"), Value::Number(2506.0 * 0.01)),
+            ("humidity", Some("%"), Value::Number(5055.0 * 0.01)),
+            ("battery", Some("%"), Value::Number(97.0)),
+        ]
+    );
+}
+
+#[test]
+fn signed_and_events() {
+    // Temperature -1.5 °C, button long press, dimmer rotated left 3 steps
+    let data = [0x44, 0x45, 0xF1, 0xFF, 0x3A, 0x04, 0x3C, 0x01, 0x03];
+    let packet = decode(&data, ADDRESS, None).unwrap();
+    assert!(packet.trigger_based);
+    assert_eq!(packet.measurements[0].value, Value::Number(-15.0 * 0.1));
+    assert_eq!(
+        packet.measurements[1].value,
+        Value::Button(ButtonEvent::LongPress)
+    );
+    assert_eq!(
+        packet.measurements[2].value,
+        Value::Dimmer(DimmerEvent::RotateLeft(3))
+    );
+}
+
+#[test]
+fn unknown_object() {
+    decode(&[0x40, 0xEE, 0x00], ADDRESS, None).unwrap_err();
+}
+
+#[test]
+fn wrong_version() {
+    decode(&[0x20, 0x01, 0x61], ADDRESS, None).unwrap_err();
+}
+
+#[test]
+fn encrypted() {
+    let key = parse_bind_key("231d39c1d7cc1ab1aee224cd096db932").unwrap();
+    let device_info = 0x41;
+    let counter = [0x33, 0x22, 0x11, 0x00];
+    let plaintext = [0x02, 0xCA, 0x09, 0x03, 0xBF, 0x13];
+
+    let mut nonce = vec![0x54, 0x48, 0xE6, 0x8F, 0x80, 0xA5, 0xD2, 0xFC, device_info];
+    nonce.extend_from_slice(&counter);
+    let sealed = BthomeCcm::new(GenericArray::from_slice(&key))
+        .encrypt(GenericArray::from_slice(&nonce), &plaintext[..])
+        .unwrap();
+    let (ciphertext, mic) = sealed.split_at(plaintext.len());
+
+    let mut data = vec![device_info];
+    data.extend_from_slice(ciphertext);
+    data.extend_from_slice(&counter);
+    data.extend_from_slice(mic);
+
+    let packet = decode(&data, ADDRESS, Some(&key)).unwrap();
+    assert!(packet.encrypted);
+    assert_eq!(packet.counter, Some(0x00112233));
+    assert_eq!(packet.measurements.len(), 2);
+
+    decode(&data, ADDRESS, None).unwrap_err();
+    let wrong_key = [0; 16];
+    decode(&data, ADDRESS, Some(&wrong_key)).unwrap_err();
+    decode(&data, "54:48:E6:8F:80:A6", Some(&key)).unwrap_err();
+}
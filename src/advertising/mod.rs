@@ -0,0 +1,14 @@
+//! Decoding of advertisement payloads, and publishing local advertisements.
+
+pub mod ad;
+pub mod advertisement;
+pub mod beacon;
+pub mod bthome;
+pub mod monitor;
+
+pub use ad::AdStructure;
+pub use advertisement::{
+    Advertisement, AdvertisementHandle, AdvertisementType, AdvertisingCapabilities, Include,
+    SecondaryChannel,
+};
+pub use monitor::{AdvertisementMonitor, MonitorEvent, MonitorHandle, Pattern, SamplingPeriod};
@@ -0,0 +1,326 @@
+//! Advertisement monitors, which let BlueZ and the controller filter
+//! advertisements without active discovery.
+
+use std::rc::Rc;
+use std::time::Duration;
+
+use dbus::MethodErr;
+
+use crate::gen::AdvertisementMonitorManager1;
+use crate::gen_server::{register_advertisement_monitor1, AdvertisementMonitor1};
+use crate::server::{optional, seconds, EventQueue};
+use crate::{Adapter, Bluez, DBusProxy, Device, Error, ResultExt, Timeouts};
+
+#[cfg(test)]
+mod test;
+
+const MANAGER_INTERFACE: &str = "org.bluez.AdvertisementMonitorManager1";
+
+/// The only monitor type currently supported by BlueZ
+const OR_PATTERNS: &str = "or_patterns";
+
+/// A pattern that matches advertisements containing an AD structure of the
+/// given type, whose data contains `content` at offset `start`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pattern {
+    pub start: u8,
+    pub ad_type: u8,
+    pub content: Vec<u8>,
+}
+
+impl Pattern {
+    pub fn new(ad_type: u8, start: u8, content: impl Into<Vec<u8>>) -> Self {
+        Self {
+            start,
+            ad_type,
+            content: content.into(),
+        }
+    }
+}
+
+/// How often advertisements from a device in range are reported.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SamplingPeriod {
+    /// Report every advertisement
+    All,
+    /// Report only the first advertisement after the device is found
+    First,
+    /// Report at most once per period, with a resolution of 100 ms
+    Every(Duration),
+}
+
+impl SamplingPeriod {
+    fn to_units(self) -> u16 {
+        match self {
+            Self::All => 0,
+            Self::First => 255,
+            Self::Every(period) => (period.as_millis() / 100).clamp(1, 254) as u16,
+        }
+    }
+}
+
+/// An `or_patterns` advertisement monitor, which reports devices whose
+/// advertisements match any of its patterns.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AdvertisementMonitor {
+    patterns: Vec<Pattern>,
+    rssi_high: Option<(i16, Duration)>,
+    rssi_low: Option<(i16, Duration)>,
+    sampling_period: Option<SamplingPeriod>,
+}
+
+impl AdvertisementMonitor {
+    pub fn new() -> Self {
+        Self {
+            patterns: Vec::new(),
+            rssi_high: None,
+            rssi_low: None,
+            sampling_period: None,
+        }
+    }
+
+    pub fn pattern(mut self, pattern: Pattern) -> Self {
+        self.patterns.push(pattern);
+        self
+    }
+
+    /// A device is found once its RSSI stays at or above `threshold` dBm for
+    /// `timeout`. BlueZ uses a resolution of one second.
+    pub fn rssi_high(mut self, threshold: i16, timeout: Duration) -> Self {
+        self.rssi_high = Some((threshold, timeout));
+        self
+    }
+
+    /// A device is lost once its RSSI stays below `threshold` dBm for
+    /// `timeout`. BlueZ uses a resolution of one second.
+    pub fn rssi_low(mut self, threshold: i16, timeout: Duration) -> Self {
+        self.rssi_low = Some((threshold, timeout));
+        self
+    }
+
+    pub fn sampling_period(mut self, period: SamplingPeriod) -> Self {
+        self.sampling_period = Some(period);
+        self
+    }
+}
+
+impl Default for AdvertisementMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Call from BlueZ to the monitor object, which only carries the object path
+/// of the device.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum RawEvent {
+    Activated,
+    Released,
+    DeviceFound(dbus::Path<'static>),
+    DeviceLost(dbus::Path<'static>),
+}
+
+pub enum MonitorEvent {
+    /// BlueZ started monitoring
+    Activated,
+    /// BlueZ stopped monitoring, for example because the adapter was removed
+    Released,
+    /// A device matching the monitor came into range
+    DeviceFound(Device),
+    /// A device that was found before went out of range
+    DeviceLost(Device),
+}
+
+struct MonitorObject {
+    monitor: AdvertisementMonitor,
+    events: EventQueue<RawEvent>,
+}
+
+impl AdvertisementMonitor1 for MonitorObject {
+    fn release(&mut self) -> Result<(), MethodErr> {
+        self.events.push(RawEvent::Released);
+        Ok(())
+    }
+
+    fn activate(&mut self) -> Result<(), MethodErr> {
+        self.events.push(RawEvent::Activated);
+        Ok(())
+    }
+
+    fn device_found(&mut self, device: dbus::Path<'static>) -> Result<(), MethodErr> {
+        self.events.push(RawEvent::DeviceFound(device));
+        Ok(())
+    }
+
+    fn device_lost(&mut self, device: dbus::Path<'static>) -> Result<(), MethodErr> {
+        self.events.push(RawEvent::DeviceLost(device));
+        Ok(())
+    }
+
+    fn type_(&self) -> Result<String, MethodErr> {
+        Ok(OR_PATTERNS.to_owned())
+    }
+
+    fn rssihigh_threshold(&self) -> Result<i16, MethodErr> {
+        optional(self.monitor.rssi_high.map(|r| r.0), "RSSIHighThreshold")
+    }
+
+    fn rssihigh_timeout(&self) -> Result<u16, MethodErr> {
+        optional(
+            self.monitor.rssi_high.map(|r| seconds(r.1)),
+            "RSSIHighTimeout",
+        )
+    }
+
+    fn rssilow_threshold(&self) -> Result<i16, MethodErr> {
+        optional(self.monitor.rssi_low.map(|r| r.0), "RSSILowThreshold")
+    }
+
+    fn rssilow_timeout(&self) -> Result<u16, MethodErr> {
+        optional(
+            self.monitor.rssi_low.map(|r| seconds(r.1)),
+            "RSSILowTimeout",
+        )
+    }
+
+    fn rssisampling_period(&self) -> Result<u16, MethodErr> {
+        optional(
+            self.monitor.sampling_period.map(SamplingPeriod::to_units),
+            "RSSISamplingPeriod",
+        )
+    }
+
+    fn patterns(&self) -> Result<Vec<(u8, u8, Vec<u8>)>, MethodErr> {
+        Ok(self
+            .monitor
+            .patterns
+            .iter()
+            .map(|p| (p.start, p.ad_type, p.content.clone()))
+            .collect())
+    }
+}
+
+/// An advertisement monitor registered with BlueZ. Dropping the handle
+/// unregisters the monitor.
+pub struct MonitorHandle {
+    bluez: Rc<Bluez>,
+    adapter: DBusProxy,
+    root: dbus::Path<'static>,
+    path: dbus::Path<'static>,
+    events: EventQueue<RawEvent>,
+    timeouts: Timeouts,
+    registered: bool,
+}
+
+impl MonitorHandle {
+    /// Wait for the next event from BlueZ, returning `Ok(None)` if the
+    /// timeout expires first. Events that arrived since the last call are
+    /// returned immediately.
+    pub fn wait_event(&self, timeout: Duration) -> Result<Option<MonitorEvent>, Error> {
+        let event = match self.events.wait(&self.bluez, timeout)? {
+            Some(event) => event,
+            None => return Ok(None),
+        };
+        let device = |path| Device::from_path(&self.bluez, path, &self.timeouts);
+        Ok(Some(match event {
+            RawEvent::Activated => MonitorEvent::Activated,
+            RawEvent::Released => MonitorEvent::Released,
+            RawEvent::DeviceFound(path) => MonitorEvent::DeviceFound(device(path)?),
+            RawEvent::DeviceLost(path) => MonitorEvent::DeviceLost(device(path)?),
+        }))
+    }
+}
+
+impl Drop for MonitorHandle {
+    fn drop(&mut self) {
+        let server = self.bluez.object_server();
+        if self.registered {
+            server
+                .call::<_, ()>(
+                    &self.adapter,
+                    MANAGER_INTERFACE,
+                    "UnregisterMonitor",
+                    (self.root.clone(),),
+                )
+                .ok();
+        }
+        server.remove::<MonitorObject>(&self.path);
+        server.remove::<()>(&self.root);
+    }
+}
+
+impl Adapter {
+    /// Monitor types supported by BlueZ, e.g. "or_patterns".
+    pub fn supported_monitor_types(&self) -> Result<Vec<String>, Error> {
+        self.adapter.supported_monitor_types().context(
+            &self.adapter,
+            MANAGER_INTERFACE,
+            "SupportedMonitorTypes",
+        )
+    }
+
+    /// Monitor features supported by the controller, e.g.
+    /// "controller-patterns" if pattern matching is offloaded.
+    pub fn supported_monitor_features(&self) -> Result<Vec<String>, Error> {
+        self.adapter.supported_features().context(
+            &self.adapter,
+            MANAGER_INTERFACE,
+            "SupportedFeatures",
+        )
+    }
+
+    /// Export an advertisement monitor and register it with BlueZ. Devices
+    /// matching the monitor are reported through `MonitorHandle::wait_event()`
+    /// without starting discovery. The monitor is unregistered when the
+    /// returned handle is dropped.
+    pub fn register_monitor(&self, monitor: AdvertisementMonitor) -> Result<MonitorHandle, Error> {
+        if monitor.patterns.is_empty() {
+            return Err(Error::InvalidValue(
+                "advertisement monitor has no patterns".into(),
+            ));
+        }
+        if !self
+            .supported_monitor_types()?
+            .iter()
+            .any(|t| t == OR_PATTERNS)
+        {
+            return Err(Error::InvalidValue(format!(
+                "advertisement monitor type {} is not supported",
+                OR_PATTERNS
+            )));
+        }
+
+        let server = self.bluez.object_server();
+        let iface = server.interface(
+            "advertisement-monitor",
+            register_advertisement_monitor1::<MonitorObject>,
+        );
+        let root = server.unique_path("monitor");
+        let path: dbus::Path<'static> = format!("{}/monitor0", root).into();
+        let events = EventQueue::new();
+        server.add_object_manager(root.clone());
+        server.insert(
+            path.clone(),
+            &[iface],
+            MonitorObject {
+                monitor,
+                events: events.clone(),
+            },
+        );
+        let mut handle = MonitorHandle {
+            bluez: self.bluez.clone(),
+            adapter: self.adapter.clone(),
+            root: root.clone(),
+            path,
+            events,
+            timeouts: self.timeouts,
+            registered: false,
+        };
+        // If this fails, dropping the handle removes the objects again
+        server
+            .call::<_, ()>(&self.adapter, MANAGER_INTERFACE, "RegisterMonitor", (root,))
+            .context(&self.adapter, MANAGER_INTERFACE, "RegisterMonitor")?;
+        handle.registered = true;
+        Ok(handle)
+    }
+}
@@ -0,0 +1,39 @@
+use super::*;
+
+#[test]
+fn sampling_period_units() {
+    assert_eq!(SamplingPeriod::All.to_units(), 0);
+    assert_eq!(SamplingPeriod::First.to_units(), 255);
+    assert_eq!(
+        SamplingPeriod::Every(Duration::from_millis(1500)).to_units(),
+        15
+    );
+    // Out of range periods are clamped rather than turning into All or First
+    assert_eq!(
+        SamplingPeriod::Every(Duration::from_millis(10)).to_units(),
+        1
+    );
+    assert_eq!(
+        SamplingPeriod::Every(Duration::from_secs(60)).to_units(),
+        254
+    );
+}
+
+#[test]
+fn monitor_builder() {
+    let monitor = AdvertisementMonitor::new()
+        .pattern(Pattern::new(0xFF, 0, vec![0x4C, 0x00]))
+        .rssi_high(-60, Duration::from_secs(2))
+        .rssi_low(-80, Duration::from_secs(5));
+    assert_eq!(
+        monitor.patterns,
+        vec![Pattern {
+            start: 0,
+            ad_type: 0xFF,
+            content: vec![0x4C, 0x00],
+        }]
+    );
+    assert_eq!(monitor.rssi_high, Some((-60, Duration::from_secs(2))));
+    assert_eq!(monitor.rssi_low, Some((-80, Duration::from_secs(5))));
+    assert_eq!(monitor.sampling_period, None);
+}
@@ -0,0 +1,352 @@
+//! Watching battery levels reported by BlueZ, and providing battery levels
+//! for devices that report them through a protocol BlueZ doesn't understand.
+
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, VecDeque};
+use std::rc::Rc;
+use std::time::Duration;
+
+use dbus::arg::{PropMap, Variant};
+use dbus::blocking::stdintf::org_freedesktop_dbus::{
+    ObjectManagerInterfacesRemoved, PropertiesPropertiesChanged,
+};
+use dbus::MethodErr;
+
+use crate::gen_server::{register_battery_provider1, BatteryProvider1};
+use crate::util::wait_for;
+use crate::{
+    get_optional_property, get_property, Adapter, Battery, Bluez, DBusProxy, Device, Error,
+    ResultExt,
+};
+
+#[cfg(test)]
+mod test;
+
+const INTERFACE: &str = "org.bluez.BatteryProvider1";
+const MANAGER_INTERFACE: &str = "org.bluez.BatteryProviderManager1";
+
+#[derive(Default)]
+struct ChangesState {
+    percentages: VecDeque<u8>,
+    removed: bool,
+}
+
+/// Battery level updates from BlueZ, created by `Battery::changes()`.
+///
+/// As an iterator, it blocks until the next update and ends once BlueZ
+/// removes the battery, for example because the device disconnected.
+pub struct BatteryChanges {
+    battery: DBusProxy,
+    manager: DBusProxy,
+    state: Rc<RefCell<ChangesState>>,
+    changed_token: dbus::channel::Token,
+    removed_token: dbus::channel::Token,
+}
+
+impl BatteryChanges {
+    pub(crate) fn new(battery: &DBusProxy) -> Result<Self, Error> {
+        let state = Rc::new(RefCell::new(ChangesState::default()));
+        let changed_token = {
+            let state = state.clone();
+            battery.match_signal(move |h: PropertiesPropertiesChanged, _: &_, _: &_| {
+                if h.interface_name == Battery::INTERFACE {
+                    if let Ok(Some(percentage)) =
+                        get_optional_property::<u8>(&h.changed_properties, "Percentage")
+                    {
+                        state.borrow_mut().percentages.push_back(percentage);
+                    }
+                }
+                true
+            })?
+        };
+        // BlueZ announces removed objects on its object manager at the root
+        let manager = DBusProxy {
+            path: "/".into(),
+            ..battery.clone()
+        };
+        let removed_token = {
+            let state = state.clone();
+            let path = battery.path.clone();
+            manager.match_signal(move |h: ObjectManagerInterfacesRemoved, _: &_, _: &_| {
+                if h.object == path && h.interfaces.iter().any(|i| i == Battery::INTERFACE) {
+                    state.borrow_mut().removed = true;
+                }
+                true
+            })
+        };
+        let removed_token = match removed_token {
+            Ok(token) => token,
+            Err(e) => {
+                battery.match_stop(changed_token, true).ok();
+                return Err(e.into());
+            }
+        };
+        Ok(Self {
+            battery: battery.clone(),
+            manager,
+            state,
+            changed_token,
+            removed_token,
+        })
+    }
+
+    /// Wait for the next battery level, returning `Ok(None)` if the timeout
+    /// expires first or the battery was removed. Updates that arrived since
+    /// the last call are returned immediately.
+    pub fn wait(&self, timeout: Duration) -> Result<Option<u8>, Error> {
+        let next = wait_for(
+            timeout,
+            |t| Ok(self.battery.connection.process(t)?),
+            || {
+                let mut state = self.state.borrow_mut();
+                match state.percentages.pop_front() {
+                    Some(percentage) => Some(Some(percentage)),
+                    None if state.removed => Some(None),
+                    None => None,
+                }
+            },
+        )?;
+        Ok(next.flatten())
+    }
+
+    /// Whether BlueZ removed the battery, after which no more updates arrive.
+    pub fn is_removed(&self) -> bool {
+        self.state.borrow().removed
+    }
+}
+
+impl Iterator for BatteryChanges {
+    type Item = Result<u8, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match self.wait(Duration::from_secs(60)) {
+                Ok(Some(percentage)) => return Some(Ok(percentage)),
+                Ok(None) if self.is_removed() => return None,
+                Ok(None) => {}
+                Err(e) => return Some(Err(e)),
+            }
+        }
+    }
+}
+
+impl Drop for BatteryChanges {
+    fn drop(&mut self) {
+        self.battery.match_stop(self.changed_token, true).ok();
+        self.manager.match_stop(self.removed_token, true).ok();
+    }
+}
+
+struct BatteryObject {
+    device: dbus::Path<'static>,
+    percentage: u8,
+    source: Option<String>,
+}
+
+impl BatteryObject {
+    fn properties(&self) -> PropMap {
+        let mut properties = PropMap::new();
+        properties.insert("Device".into(), Variant(Box::new(self.device.clone())));
+        properties.insert("Percentage".into(), Variant(Box::new(self.percentage)));
+        if let Some(source) = &self.source {
+            properties.insert("Source".into(), Variant(Box::new(source.clone())));
+        }
+        properties
+    }
+}
+
+impl BatteryProvider1 for BatteryObject {
+    fn device(&self) -> Result<dbus::Path<'static>, MethodErr> {
+        Ok(self.device.clone())
+    }
+
+    fn percentage(&self) -> Result<u8, MethodErr> {
+        Ok(self.percentage)
+    }
+
+    /// Left out of `GetAll` if not set.
+    fn source(&self) -> Result<String, MethodErr> {
+        self.source
+            .clone()
+            .ok_or_else(|| MethodErr::no_property("Source"))
+    }
+}
+
+/// A battery provider registered with BlueZ. Batteries added to the provider
+/// show up as `org.bluez.Battery1` on their device. Dropping the provider
+/// unregisters it, which removes all of its batteries from BlueZ.
+pub struct BatteryProvider {
+    bluez: Rc<Bluez>,
+    adapter: DBusProxy,
+    root: dbus::Path<'static>,
+    next_id: Cell<u32>,
+    registered: bool,
+}
+
+impl BatteryProvider {
+    /// Provide the battery level of a device. BlueZ ignores the battery if
+    /// the device already has a battery level from another source, such as
+    /// the GATT Battery Service. The battery is removed when the returned
+    /// handle is dropped.
+    pub fn add_battery(
+        &self,
+        device: &Device,
+        percentage: u8,
+        source: Option<&str>,
+    ) -> Result<ProvidedBattery, Error> {
+        check_percentage(percentage)?;
+        let server = self.bluez.object_server();
+        let iface = server.interface(
+            "battery-provider",
+            register_battery_provider1::<BatteryObject>,
+        );
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+        let path: dbus::Path<'static> = format!("{}/battery{}", self.root, id).into();
+        let battery = BatteryObject {
+            device: device.device.path.clone(),
+            percentage,
+            source: source.map(str::to_owned),
+        };
+        let mut interfaces = HashMap::new();
+        interfaces.insert(INTERFACE.to_owned(), battery.properties());
+        server.insert(path.clone(), &[iface], battery);
+        // BlueZ learns about batteries added after registration from the
+        // ObjectManager signals
+        server.emit_interfaces_added(&self.root, path.clone(), interfaces)?;
+        Ok(ProvidedBattery {
+            bluez: self.bluez.clone(),
+            root: self.root.clone(),
+            path,
+        })
+    }
+}
+
+impl Drop for BatteryProvider {
+    fn drop(&mut self) {
+        let server = self.bluez.object_server();
+        if self.registered {
+            server
+                .call::<_, ()>(
+                    &self.adapter,
+                    MANAGER_INTERFACE,
+                    "UnregisterBatteryProvider",
+                    (self.root.clone(),),
+                )
+                .ok();
+        }
+        server.remove::<()>(&self.root);
+    }
+}
+
+/// A battery exported by a `BatteryProvider`.
+pub struct ProvidedBattery {
+    bluez: Rc<Bluez>,
+    root: dbus::Path<'static>,
+    path: dbus::Path<'static>,
+}
+
+impl ProvidedBattery {
+    pub fn percentage(&self) -> u8 {
+        self.bluez
+            .object_server()
+            .with_data(&self.path, |b: &mut BatteryObject| b.percentage)
+            .unwrap_or(0)
+    }
+
+    /// Update the battery level, which BlueZ forwards to `Battery1`.
+    pub fn set_percentage(&self, percentage: u8) -> Result<(), Error> {
+        check_percentage(percentage)?;
+        self.bluez
+            .object_server()
+            .with_data(&self.path, |b: &mut BatteryObject| {
+                b.percentage = percentage
+            });
+        let mut changed_properties = PropMap::new();
+        changed_properties.insert("Percentage".into(), Variant(Box::new(percentage)));
+        self.bluez.object_server().emit_properties_changed(
+            &self.path,
+            INTERFACE,
+            changed_properties,
+        )
+    }
+}
+
+impl Drop for ProvidedBattery {
+    fn drop(&mut self) {
+        let server = self.bluez.object_server();
+        server.remove::<BatteryObject>(&self.path);
+        server
+            .emit_interfaces_removed(&self.root, self.path.clone(), vec![INTERFACE.to_owned()])
+            .ok();
+    }
+}
+
+fn check_percentage(percentage: u8) -> Result<(), Error> {
+    if percentage > 100 {
+        return Err(Error::InvalidValue(format!(
+            "battery percentage out of range: {}",
+            percentage
+        )));
+    }
+    Ok(())
+}
+
+impl Adapter {
+    /// All devices of this adapter with a battery known to BlueZ, together
+    /// with their battery level.
+    pub fn batteries(&self) -> Result<Vec<(Device, u8)>, Error> {
+        let prefix = format!("{}/", self.adapter.path);
+        let mut batteries = Vec::new();
+        let mut error = None;
+        self.bluez.objects.find_map_object(
+            |path, interfaces| {
+                if !path.starts_with(&prefix) {
+                    return None;
+                }
+                let battery = interfaces.get(Battery::INTERFACE)?;
+                let result = get_property(battery, Battery::INTERFACE, "Percentage").and_then(
+                    |percentage| {
+                        let device = Device::from_path(
+                            &self.bluez,
+                            path.clone().into_static(),
+                            &self.timeouts,
+                        )?;
+                        batteries.push((device, percentage));
+                        Ok(())
+                    },
+                );
+                error = result.err();
+                // Stop at the first error
+                error.as_ref().map(|_| ())
+            },
+            Duration::from_millis(0),
+        )?;
+        error.map_or(Ok(batteries), Err)
+    }
+
+    /// Export a battery provider and register it with BlueZ.
+    pub fn register_battery_provider(&self) -> Result<BatteryProvider, Error> {
+        let server = self.bluez.object_server();
+        let root = server.unique_path("battery_provider");
+        server.add_object_manager(root.clone());
+        let mut provider = BatteryProvider {
+            bluez: self.bluez.clone(),
+            adapter: self.adapter.clone(),
+            root: root.clone(),
+            next_id: Cell::new(0),
+            registered: false,
+        };
+        // If this fails, dropping the provider removes the object again
+        server
+            .call::<_, ()>(
+                &self.adapter,
+                MANAGER_INTERFACE,
+                "RegisterBatteryProvider",
+                (root,),
+            )
+            .context(&self.adapter, MANAGER_INTERFACE, "RegisterBatteryProvider")?;
+        provider.registered = true;
+        Ok(provider)
+    }
+}
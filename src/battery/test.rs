@@ -0,0 +1,27 @@
+use super::*;
+
+#[test]
+fn battery_properties() {
+    let mut battery = BatteryObject {
+        device: "/org/bluez/hci0/dev_00_11_22_33_44_55".into(),
+        percentage: 42,
+        source: None,
+    };
+    let properties = battery.properties();
+    assert_eq!(properties.len(), 2);
+    assert_eq!(properties["Percentage"].0.as_u64(), Some(42));
+    assert_eq!(
+        properties["Device"].0.as_str(),
+        Some("/org/bluez/hci0/dev_00_11_22_33_44_55")
+    );
+
+    battery.source = Some("HFP".into());
+    assert_eq!(battery.properties()["Source"].0.as_str(), Some("HFP"));
+}
+
+#[test]
+fn percentage_range() {
+    assert!(check_percentage(0).is_ok());
+    assert!(check_percentage(100).is_ok());
+    assert!(matches!(check_percentage(101), Err(Error::InvalidValue(_))));
+}
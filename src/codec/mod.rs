@@ -0,0 +1,546 @@
+//! Typed access to characteristics, with codecs for common characteristics
+//! defined by the Bluetooth SIG.
+
+use std::convert::{TryFrom, TryInto};
+use std::marker::PhantomData;
+use std::time::Duration;
+
+use uuid::Uuid;
+
+use crate::{uuid16, Error, GattCharacteristic, GattService, NotifyReader, Timeouts};
+
+#[cfg(test)]
+mod test;
+
+/// Conversion between the raw value of a characteristic and a Rust type.
+pub trait GattCodec: Sized {
+    /// UUID of the characteristic this codec is for
+    const UUID: Uuid;
+
+    fn decode(buf: &[u8]) -> Result<Self, Error>;
+
+    fn encode(&self) -> Vec<u8>;
+}
+
+/// Characteristic whose value is converted using a codec.
+pub struct Characteristic<T: GattCodec> {
+    characteristic: GattCharacteristic,
+    phantom: PhantomData<T>,
+}
+
+impl<T: GattCodec> Characteristic<T> {
+    pub fn new(characteristic: GattCharacteristic) -> Self {
+        Self {
+            characteristic,
+            phantom: PhantomData,
+        }
+    }
+
+    pub fn inner(&self) -> &GattCharacteristic {
+        &self.characteristic
+    }
+
+    pub fn into_inner(self) -> GattCharacteristic {
+        self.characteristic
+    }
+
+    pub fn read(&self) -> Result<T, Error> {
+        T::decode(&self.characteristic.read_value()?)
+    }
+
+    pub fn write(&self, value: &T) -> Result<(), Error> {
+        self.characteristic.write_value(value.encode())
+    }
+
+    pub fn start_notify(&self) -> Result<(), Error> {
+        self.characteristic.start_notify()
+    }
+
+    pub fn stop_notify(&self) -> Result<(), Error> {
+        self.characteristic.stop_notify()
+    }
+
+    /// Acquire a notification file descriptor and decode each notification.
+    pub fn notifications(&self) -> Result<Notifications<T>, Error> {
+        Ok(Notifications {
+            reader: self.characteristic.acquire_notify()?,
+            phantom: PhantomData,
+        })
+    }
+}
+
+/// Iterator over decoded notifications.
+pub struct Notifications<T: GattCodec> {
+    reader: NotifyReader,
+    phantom: PhantomData<T>,
+}
+
+impl<T: GattCodec> Notifications<T> {
+    pub fn reader(&self) -> &NotifyReader {
+        &self.reader
+    }
+}
+
+impl<T: GattCodec> Iterator for Notifications<T> {
+    type Item = Result<T, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.reader
+            .next()
+            .map(|r| r.and_then(|buf| T::decode(&buf)))
+    }
+}
+
+impl GattService {
+    /// Find the characteristic for a codec in this service.
+    pub fn find_typed_characteristic<T: GattCodec>(
+        &self,
+    ) -> Result<Option<Characteristic<T>>, Error> {
+        self.find_typed_characteristic_with_timeouts(&self.timeouts)
+    }
+
+    pub fn find_typed_characteristic_with_timeouts<T: GattCodec>(
+        &self,
+        timeouts: &Timeouts,
+    ) -> Result<Option<Characteristic<T>>, Error> {
+        Ok(self
+            .find_characteristic_by_uuid_with_timeouts(&T::UUID, timeouts)?
+            .map(Characteristic::new))
+    }
+}
+
+/// Simple cursor for decoding little endian fields.
+struct Reader<'a> {
+    buf: &'a [u8],
+    name: &'static str,
+}
+
+impl<'a> Reader<'a> {
+    fn new(buf: &'a [u8], name: &'static str) -> Self {
+        Self { buf, name }
+    }
+
+    fn bytes<const N: usize>(&mut self) -> Result<[u8; N], Error> {
+        if self.buf.len() < N {
+            return Err(Error::InvalidValue(format!("{} is truncated", self.name)));
+        }
+        let (bytes, rest) = self.buf.split_at(N);
+        self.buf = rest;
+        Ok(bytes.try_into().unwrap())
+    }
+
+    fn u8(&mut self) -> Result<u8, Error> {
+        Ok(self.bytes::<1>()?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, Error> {
+        Ok(u16::from_le_bytes(self.bytes()?))
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        Ok(u32::from_le_bytes(self.bytes()?))
+    }
+
+    fn is_empty(&self) -> bool {
+        self.buf.is_empty()
+    }
+}
+
+/// Battery Level (0x2A19), as a percentage.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BatteryLevel(pub u8);
+
+impl GattCodec for BatteryLevel {
+    const UUID: Uuid = uuid16(0x2A19);
+
+    fn decode(buf: &[u8]) -> Result<Self, Error> {
+        Ok(Self(Reader::new(buf, "Battery Level").u8()?))
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        vec![self.0]
+    }
+}
+
+/// Heart Rate Measurement (0x2A37).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HeartRateMeasurement {
+    /// Heart rate in beats per minute
+    pub heart_rate: u16,
+    /// Whether the sensor is in contact with the skin, if the sensor supports
+    /// contact detection
+    pub sensor_contact: Option<bool>,
+    /// Energy expended in kilojoules since the last reset
+    pub energy_expended: Option<u16>,
+    /// RR-intervals in units of 1/1024 second
+    pub rr_intervals: Vec<u16>,
+}
+
+impl HeartRateMeasurement {
+    const FLAG_RATE_U16: u8 = 1 << 0;
+    const FLAG_CONTACT_DETECTED: u8 = 1 << 1;
+    const FLAG_CONTACT_SUPPORTED: u8 = 1 << 2;
+    const FLAG_ENERGY_EXPENDED: u8 = 1 << 3;
+    const FLAG_RR_INTERVALS: u8 = 1 << 4;
+
+    pub fn rr_interval_durations(&self) -> impl Iterator<Item = Duration> + '_ {
+        self.rr_intervals
+            .iter()
+            .map(|rr| Duration::from_micros(u64::from(*rr) * 1_000_000 / 1024))
+    }
+}
+
+impl GattCodec for HeartRateMeasurement {
+    const UUID: Uuid = uuid16(0x2A37);
+
+    fn decode(buf: &[u8]) -> Result<Self, Error> {
+        let mut r = Reader::new(buf, "Heart Rate Measurement");
+        let flags = r.u8()?;
+        let heart_rate = if flags & Self::FLAG_RATE_U16 != 0 {
+            r.u16()?
+        } else {
+            u16::from(r.u8()?)
+        };
+        let sensor_contact = (flags & Self::FLAG_CONTACT_SUPPORTED != 0)
+            .then_some(flags & Self::FLAG_CONTACT_DETECTED != 0);
+        let energy_expended = if flags & Self::FLAG_ENERGY_EXPENDED != 0 {
+            Some(r.u16()?)
+        } else {
+            None
+        };
+        let mut rr_intervals = vec![];
+        if flags & Self::FLAG_RR_INTERVALS != 0 {
+            while !r.is_empty() {
+                rr_intervals.push(r.u16()?);
+            }
+        }
+        Ok(Self {
+            heart_rate,
+            sensor_contact,
+            energy_expended,
+            rr_intervals,
+        })
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut flags = 0;
+        let mut buf = vec![0];
+        if let Ok(rate) = u8::try_from(self.heart_rate) {
+            buf.push(rate);
+        } else {
+            flags |= Self::FLAG_RATE_U16;
+            buf.extend_from_slice(&self.heart_rate.to_le_bytes());
+        }
+        if let Some(contact) = self.sensor_contact {
+            flags |= Self::FLAG_CONTACT_SUPPORTED;
+            if contact {
+                flags |= Self::FLAG_CONTACT_DETECTED;
+            }
+        }
+        if let Some(energy) = self.energy_expended {
+            flags |= Self::FLAG_ENERGY_EXPENDED;
+            buf.extend_from_slice(&energy.to_le_bytes());
+        }
+        if !self.rr_intervals.is_empty() {
+            flags |= Self::FLAG_RR_INTERVALS;
+            self.rr_intervals
+                .iter()
+                .for_each(|rr| buf.extend_from_slice(&rr.to_le_bytes()));
+        }
+        buf[0] = flags;
+        buf
+    }
+}
+
+/// Body Sensor Location (0x2A38).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BodySensorLocation {
+    Other,
+    Chest,
+    Wrist,
+    Finger,
+    Hand,
+    EarLobe,
+    Foot,
+    Reserved(u8),
+}
+
+impl GattCodec for BodySensorLocation {
+    const UUID: Uuid = uuid16(0x2A38);
+
+    fn decode(buf: &[u8]) -> Result<Self, Error> {
+        Ok(match Reader::new(buf, "Body Sensor Location").u8()? {
+            0 => Self::Other,
+            1 => Self::Chest,
+            2 => Self::Wrist,
+            3 => Self::Finger,
+            4 => Self::Hand,
+            5 => Self::EarLobe,
+            6 => Self::Foot,
+            v => Self::Reserved(v),
+        })
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        vec![match self {
+            Self::Other => 0,
+            Self::Chest => 1,
+            Self::Wrist => 2,
+            Self::Finger => 3,
+            Self::Hand => 4,
+            Self::EarLobe => 5,
+            Self::Foot => 6,
+            Self::Reserved(v) => *v,
+        }]
+    }
+}
+
+/// Date Time (0x2A08). Zero in any field means the value is not known.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DateTime {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hours: u8,
+    pub minutes: u8,
+    pub seconds: u8,
+}
+
+impl DateTime {
+    fn read(r: &mut Reader) -> Result<Self, Error> {
+        Ok(Self {
+            year: r.u16()?,
+            month: r.u8()?,
+            day: r.u8()?,
+            hours: r.u8()?,
+            minutes: r.u8()?,
+            seconds: r.u8()?,
+        })
+    }
+
+    fn write(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.year.to_le_bytes());
+        buf.extend_from_slice(&[self.month, self.day, self.hours, self.minutes, self.seconds]);
+    }
+}
+
+impl GattCodec for DateTime {
+    const UUID: Uuid = uuid16(0x2A08);
+
+    fn decode(buf: &[u8]) -> Result<Self, Error> {
+        Self::read(&mut Reader::new(buf, "Date Time"))
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut buf = vec![];
+        self.write(&mut buf);
+        buf
+    }
+}
+
+/// Special values of the IEEE 11073-20601 32-bit FLOAT type
+const FLOAT_NAN: u32 = 0x007F_FFFF;
+const FLOAT_NRES: u32 = 0x0080_0000;
+const FLOAT_POSITIVE_INFINITY: u32 = 0x007F_FFFE;
+const FLOAT_NEGATIVE_INFINITY: u32 = 0x0080_0002;
+const FLOAT_MANTISSA_MAX: i32 = 0x007F_FFFD;
+
+/// Decode an IEEE 11073-20601 32-bit FLOAT, which consists of a signed 8-bit
+/// base 10 exponent and a signed 24-bit mantissa.
+pub fn decode_ieee11073_float(raw: u32) -> f64 {
+    let mantissa = raw & 0x00FF_FFFF;
+    match mantissa {
+        FLOAT_POSITIVE_INFINITY => f64::INFINITY,
+        FLOAT_NEGATIVE_INFINITY => f64::NEG_INFINITY,
+        FLOAT_NAN | FLOAT_NRES | 0x0080_0001 => f64::NAN,
+        _ => {
+            // Sign extend the 24-bit mantissa
+            let mantissa = ((mantissa << 8) as i32) >> 8;
+            let exponent = (raw >> 24) as i8;
+            f64::from(mantissa) * 10f64.powi(i32::from(exponent))
+        }
+    }
+}
+
+/// Encode an IEEE 11073-20601 32-bit FLOAT, using as much precision as fits in
+/// the mantissa.
+pub fn encode_ieee11073_float(value: f64) -> u32 {
+    if value.is_nan() {
+        return FLOAT_NAN;
+    } else if value == f64::INFINITY {
+        return FLOAT_POSITIVE_INFINITY;
+    } else if value == f64::NEG_INFINITY {
+        return FLOAT_NEGATIVE_INFINITY;
+    }
+
+    // Find the smallest exponent that fits the value in the mantissa
+    let mut exponent: i32 = -8;
+    let mut mantissa = (value * 10f64.powi(-exponent)).round();
+    while mantissa.abs() > f64::from(FLOAT_MANTISSA_MAX) {
+        if exponent == i32::from(i8::MAX) {
+            return if value > 0.0 {
+                FLOAT_POSITIVE_INFINITY
+            } else {
+                FLOAT_NEGATIVE_INFINITY
+            };
+        }
+        exponent += 1;
+        mantissa = (value * 10f64.powi(-exponent)).round();
+    }
+    let mut mantissa = mantissa as i32;
+    // Normalize by removing trailing zeros
+    while mantissa != 0 && mantissa % 10 == 0 && exponent < i32::from(i8::MAX) {
+        mantissa /= 10;
+        exponent += 1;
+    }
+    if mantissa == 0 {
+        exponent = 0;
+    }
+    ((exponent as u32) << 24) | (mantissa as u32 & 0x00FF_FFFF)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TemperatureUnit {
+    Celsius,
+    Fahrenheit,
+}
+
+/// Temperature Measurement (0x2A1C).
+#[derive(Clone, Debug, PartialEq)]
+pub struct TemperatureMeasurement {
+    pub temperature: f64,
+    pub unit: TemperatureUnit,
+    pub timestamp: Option<DateTime>,
+    /// Location of the measurement, as defined by the Temperature Type
+    /// characteristic (0x2A1D)
+    pub temperature_type: Option<u8>,
+}
+
+impl TemperatureMeasurement {
+    const FLAG_FAHRENHEIT: u8 = 1 << 0;
+    const FLAG_TIMESTAMP: u8 = 1 << 1;
+    const FLAG_TEMPERATURE_TYPE: u8 = 1 << 2;
+}
+
+impl GattCodec for TemperatureMeasurement {
+    const UUID: Uuid = uuid16(0x2A1C);
+
+    fn decode(buf: &[u8]) -> Result<Self, Error> {
+        let mut r = Reader::new(buf, "Temperature Measurement");
+        let flags = r.u8()?;
+        let temperature = decode_ieee11073_float(r.u32()?);
+        let unit = if flags & Self::FLAG_FAHRENHEIT != 0 {
+            TemperatureUnit::Fahrenheit
+        } else {
+            TemperatureUnit::Celsius
+        };
+        let timestamp = if flags & Self::FLAG_TIMESTAMP != 0 {
+            Some(DateTime::read(&mut r)?)
+        } else {
+            None
+        };
+        let temperature_type = if flags & Self::FLAG_TEMPERATURE_TYPE != 0 {
+            Some(r.u8()?)
+        } else {
+            None
+        };
+        Ok(Self {
+            temperature,
+            unit,
+            timestamp,
+            temperature_type,
+        })
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut flags = 0;
+        if self.unit == TemperatureUnit::Fahrenheit {
+            flags |= Self::FLAG_FAHRENHEIT;
+        }
+        let mut buf = vec![0];
+        buf.extend_from_slice(&encode_ieee11073_float(self.temperature).to_le_bytes());
+        if let Some(timestamp) = &self.timestamp {
+            flags |= Self::FLAG_TIMESTAMP;
+            timestamp.write(&mut buf);
+        }
+        if let Some(temperature_type) = self.temperature_type {
+            flags |= Self::FLAG_TEMPERATURE_TYPE;
+            buf.push(temperature_type);
+        }
+        buf[0] = flags;
+        buf
+    }
+}
+
+/// Cumulative wheel revolutions and the time of the last wheel event.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WheelRevolutionData {
+    pub cumulative_revolutions: u32,
+    /// Time of the last event in units of 1/1024 second, rolls over every 64
+    /// seconds
+    pub last_event_time: u16,
+}
+
+/// Cumulative crank revolutions and the time of the last crank event.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CrankRevolutionData {
+    pub cumulative_revolutions: u16,
+    /// Time of the last event in units of 1/1024 second, rolls over every 64
+    /// seconds
+    pub last_event_time: u16,
+}
+
+/// CSC (Cycling Speed and Cadence) Measurement (0x2A5B).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CscMeasurement {
+    pub wheel: Option<WheelRevolutionData>,
+    pub crank: Option<CrankRevolutionData>,
+}
+
+impl CscMeasurement {
+    const FLAG_WHEEL: u8 = 1 << 0;
+    const FLAG_CRANK: u8 = 1 << 1;
+}
+
+impl GattCodec for CscMeasurement {
+    const UUID: Uuid = uuid16(0x2A5B);
+
+    fn decode(buf: &[u8]) -> Result<Self, Error> {
+        let mut r = Reader::new(buf, "CSC Measurement");
+        let flags = r.u8()?;
+        let wheel = if flags & Self::FLAG_WHEEL != 0 {
+            Some(WheelRevolutionData {
+                cumulative_revolutions: r.u32()?,
+                last_event_time: r.u16()?,
+            })
+        } else {
+            None
+        };
+        let crank = if flags & Self::FLAG_CRANK != 0 {
+            Some(CrankRevolutionData {
+                cumulative_revolutions: r.u16()?,
+                last_event_time: r.u16()?,
+            })
+        } else {
+            None
+        };
+        Ok(Self { wheel, crank })
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut flags = 0;
+        let mut buf = vec![0];
+        if let Some(wheel) = &self.wheel {
+            flags |= Self::FLAG_WHEEL;
+            buf.extend_from_slice(&wheel.cumulative_revolutions.to_le_bytes());
+            buf.extend_from_slice(&wheel.last_event_time.to_le_bytes());
+        }
+        if let Some(crank) = &self.crank {
+            flags |= Self::FLAG_CRANK;
+            buf.extend_from_slice(&crank.cumulative_revolutions.to_le_bytes());
+            buf.extend_from_slice(&crank.last_event_time.to_le_bytes());
+        }
+        buf[0] = flags;
+        buf
+    }
+}
@@ -0,0 +1,110 @@
+use super::*;
+
+#[test]
+fn heart_rate_u8_with_rr() {
+    let hrm = HeartRateMeasurement::decode(&[0x16, 72, 0x00, 0x04, 0x10, 0x04]).unwrap();
+    assert_eq!(
+        hrm,
+        HeartRateMeasurement {
+            heart_rate: 72,
+            sensor_contact: Some(true),
+            energy_expended: None,
+            rr_intervals: vec![1024, 1040],
+        }
+    );
+    assert_eq!(
+        hrm.rr_interval_durations().next(),
+        Some(Duration::from_secs(1))
+    );
+    assert_eq!(hrm.encode(), vec![0x16, 72, 0x00, 0x04, 0x10, 0x04]);
+}
+
+#[test]
+fn heart_rate_u16_energy() {
+    let hrm = HeartRateMeasurement {
+        heart_rate: 300,
+        sensor_contact: None,
+        energy_expended: Some(1000),
+        rr_intervals: vec![],
+    };
+    let buf = hrm.encode();
+    assert_eq!(buf, vec![0x09, 0x2C, 0x01, 0xE8, 0x03]);
+    assert_eq!(HeartRateMeasurement::decode(&buf).unwrap(), hrm);
+}
+
+#[test]
+fn heart_rate_truncated() {
+    HeartRateMeasurement::decode(&[0x01, 72]).unwrap_err();
+}
+
+#[test]
+fn ieee11073_float() {
+    // 36.4 = 364 * 10^-1
+    assert_eq!(decode_ieee11073_float(0xFF00_016C), 36.4);
+    assert_eq!(encode_ieee11073_float(36.4), 0xFF00_016C);
+    assert_eq!(decode_ieee11073_float(0x00FF_FFFF), -1.0);
+    assert!(decode_ieee11073_float(FLOAT_NAN).is_nan());
+    assert_eq!(
+        decode_ieee11073_float(FLOAT_POSITIVE_INFINITY),
+        f64::INFINITY
+    );
+    assert_eq!(encode_ieee11073_float(0.0), 0);
+    assert_eq!(encode_ieee11073_float(-1.5), 0xFFFF_FFF1);
+}
+
+#[test]
+fn temperature_measurement() {
+    let buf = [
+        0x06, 0x6C, 0x01, 0x00, 0xFF, 0xE7, 0x07, 0x0A, 0x13, 0x0C, 0x1E, 0x00, 0x02,
+    ];
+    let measurement = TemperatureMeasurement::decode(&buf).unwrap();
+    assert_eq!(measurement.temperature, 36.4);
+    assert_eq!(measurement.unit, TemperatureUnit::Celsius);
+    assert_eq!(
+        measurement.timestamp,
+        Some(DateTime {
+            year: 2023,
+            month: 10,
+            day: 19,
+            hours: 12,
+            minutes: 30,
+            seconds: 0,
+        })
+    );
+    assert_eq!(measurement.temperature_type, Some(2));
+    assert_eq!(measurement.encode(), buf);
+}
+
+#[test]
+fn csc_measurement() {
+    let buf = [
+        0x03, 0x10, 0x00, 0x00, 0x00, 0x00, 0x04, 0x05, 0x00, 0x00, 0x08,
+    ];
+    let measurement = CscMeasurement::decode(&buf).unwrap();
+    assert_eq!(
+        measurement,
+        CscMeasurement {
+            wheel: Some(WheelRevolutionData {
+                cumulative_revolutions: 16,
+                last_event_time: 1024,
+            }),
+            crank: Some(CrankRevolutionData {
+                cumulative_revolutions: 5,
+                last_event_time: 2048,
+            }),
+        }
+    );
+    assert_eq!(measurement.encode(), buf);
+}
+
+#[test]
+fn body_sensor_location() {
+    assert_eq!(
+        BodySensorLocation::decode(&[1]).unwrap(),
+        BodySensorLocation::Chest
+    );
+    assert_eq!(
+        BodySensorLocation::decode(&[0x80]).unwrap(),
+        BodySensorLocation::Reserved(0x80)
+    );
+}
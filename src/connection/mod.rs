@@ -0,0 +1,183 @@
+//! Reasons for failed connection attempts and dropped links.
+
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+use std::time::Duration;
+
+use crate::gen::Device1Disconnected;
+use crate::util::Timeout;
+use crate::{DBusProxy, Device, Error};
+
+#[cfg(test)]
+mod test;
+
+/// Reason for a failed `Device1.Connect` call, as encoded by BlueZ in the
+/// error message, e.g. `le-connection-abort-by-local`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConnectFailure {
+    AlreadyConnected,
+    /// The BR/EDR device didn't respond to paging
+    PageTimeout,
+    /// None of the device's profiles are supported locally
+    ProfileUnavailable,
+    SdpSearch,
+    CreateSocket,
+    InvalidArguments,
+    AdapterNotPowered,
+    NotSupported,
+    BadSocket,
+    MemoryAllocation,
+    Busy,
+    ConcurrentConnectionLimit,
+    Timeout,
+    Refused,
+    AbortedByRemote,
+    AbortedByLocal,
+    /// LMP or link layer protocol error
+    ProtocolError,
+    Canceled,
+    KeyMissing,
+    /// The LE link was established, but browsing the GATT database failed
+    GattBrowsing,
+    Unknown,
+}
+
+impl ConnectFailure {
+    /// Parse a BlueZ connection error message. Returns `None` if the message
+    /// isn't a connection error.
+    pub fn parse(message: &str) -> Option<Self> {
+        let reason = message
+            .strip_prefix("br-connection-")
+            .or_else(|| message.strip_prefix("le-connection-"))?;
+        Some(match reason {
+            "already-connected" => Self::AlreadyConnected,
+            "page-timeout" => Self::PageTimeout,
+            "profile-unavailable" => Self::ProfileUnavailable,
+            "sdp-search" => Self::SdpSearch,
+            "create-socket" => Self::CreateSocket,
+            "invalid-argument" | "invalid-arguments" => Self::InvalidArguments,
+            "adapter-not-powered" => Self::AdapterNotPowered,
+            "not-supported" => Self::NotSupported,
+            "bad-socket" => Self::BadSocket,
+            "memory-allocation" => Self::MemoryAllocation,
+            "busy" => Self::Busy,
+            "concurrent-connection-limit" => Self::ConcurrentConnectionLimit,
+            "timeout" => Self::Timeout,
+            "refused" => Self::Refused,
+            "aborted-by-remote" | "abort-by-remote" => Self::AbortedByRemote,
+            "aborted-by-local" | "abort-by-local" => Self::AbortedByLocal,
+            "lmp-protocol-error" | "link-layer-protocol-error" => Self::ProtocolError,
+            "canceled" => Self::Canceled,
+            "key-missing" => Self::KeyMissing,
+            "gatt-browsing" => Self::GattBrowsing,
+            "unknown" => Self::Unknown,
+            _ => return None,
+        })
+    }
+}
+
+impl Error {
+    /// Get the reason for a failed connection attempt, if this error was
+    /// returned by `Device::connect()`.
+    pub fn connect_failure(&self) -> Option<ConnectFailure> {
+        match self {
+            Error::Bluez { cause, .. } => cause.cause.message().and_then(ConnectFailure::parse),
+            _ => None,
+        }
+    }
+}
+
+/// Reason sent by BlueZ in the `Device1.Disconnected` signal.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DisconnectReason {
+    Unknown,
+    /// The link supervision timeout expired, e.g. because the device went out
+    /// of range
+    Timeout,
+    /// Disconnected by the local host
+    Local,
+    /// Disconnected by the remote device
+    Remote,
+    Authentication,
+    /// The local host was suspended
+    Suspend,
+    /// A reason not known to this crate, with its full name
+    Other(String),
+}
+
+impl DisconnectReason {
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "org.bluez.Reason.Unknown" => Self::Unknown,
+            "org.bluez.Reason.Timeout" => Self::Timeout,
+            "org.bluez.Reason.Local" => Self::Local,
+            "org.bluez.Reason.Remote" => Self::Remote,
+            "org.bluez.Reason.Authentication" => Self::Authentication,
+            "org.bluez.Reason.Suspend" => Self::Suspend,
+            name => Self::Other(name.to_owned()),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Disconnected {
+    pub reason: DisconnectReason,
+    /// Human readable description of the reason
+    pub message: String,
+}
+
+impl fmt::Display for Disconnected {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}: {}", self.reason, self.message)
+    }
+}
+
+/// Record `Device1.Disconnected` signals for a device. Only the most recent
+/// signal is kept.
+fn match_disconnected(
+    device: &DBusProxy,
+    disconnected: Rc<RefCell<Option<Disconnected>>>,
+) -> Result<dbus::channel::Token, Error> {
+    Ok(
+        device.match_signal(move |s: Device1Disconnected, _: &_, _: &_| {
+            disconnected.replace(Some(Disconnected {
+                reason: DisconnectReason::from_name(&s.reason),
+                message: s.message,
+            }));
+            true
+        })?,
+    )
+}
+
+impl Device {
+    /// Start recording `Device1.Disconnected` signals, if not already done.
+    pub(crate) fn watch_disconnected(&self) -> Result<(), Error> {
+        if self.disconnected_token.get().is_none() {
+            let token = match_disconnected(&self.device, self.disconnected.clone())?;
+            self.disconnected_token.set(Some(token));
+        }
+        Ok(())
+    }
+
+    /// Block until BlueZ reports that the link to this device dropped, or the
+    /// timeout expires. A disconnection that happened since `connect()` or
+    /// the previous call on this handle is returned immediately. Requires a
+    /// BlueZ version that emits the `Disconnected` signal, older versions will
+    /// always time out and return `Ok(None)`.
+    pub fn wait_disconnected(&self, timeout: Duration) -> Result<Option<Disconnected>, Error> {
+        self.watch_disconnected()?;
+        let timeout = Timeout::start(timeout);
+        loop {
+            while self.properties.wait_change(Duration::from_millis(0))? {}
+            if let Some(disconnected) = self.disconnected.take() {
+                return Ok(Some(disconnected));
+            }
+            if timeout.get() == Duration::from_millis(0)
+                || !self.properties.wait_change(timeout.get())?
+            {
+                return Ok(None);
+            }
+        }
+    }
+}
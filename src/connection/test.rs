@@ -0,0 +1,38 @@
+use super::*;
+
+#[test]
+fn parse_connect_failure() {
+    assert_eq!(
+        ConnectFailure::parse("br-connection-page-timeout"),
+        Some(ConnectFailure::PageTimeout)
+    );
+    assert_eq!(
+        ConnectFailure::parse("le-connection-abort-by-local"),
+        Some(ConnectFailure::AbortedByLocal)
+    );
+    assert_eq!(
+        ConnectFailure::parse("br-connection-profile-unavailable"),
+        Some(ConnectFailure::ProfileUnavailable)
+    );
+    assert_eq!(ConnectFailure::parse("le-connection-new-reason"), None);
+    assert_eq!(ConnectFailure::parse("Operation already in progress"), None);
+}
+
+#[test]
+fn error_connect_failure() {
+    let error: Error =
+        dbus::Error::new_custom("org.bluez.Error.Failed", "le-connection-timeout").into();
+    assert_eq!(error.connect_failure(), Some(ConnectFailure::Timeout));
+}
+
+#[test]
+fn disconnect_reason() {
+    assert_eq!(
+        DisconnectReason::from_name("org.bluez.Reason.Remote"),
+        DisconnectReason::Remote
+    );
+    assert_eq!(
+        DisconnectReason::from_name("org.bluez.Reason.New"),
+        DisconnectReason::Other("org.bluez.Reason.New".into())
+    );
+}
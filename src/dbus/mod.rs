@@ -1,5 +1,6 @@
 use std::cell::RefCell;
 use std::collections::{hash_map, HashMap, HashSet};
+use std::convert::TryFrom;
 use std::fmt;
 use std::fmt::Display;
 use std::hash::Hash;
@@ -72,6 +73,15 @@ pub trait RefArgCast<'a>: Sized {
     }
 }
 
+/// Treat a property that doesn't exist as empty, since some properties are
+/// only available in newer or experimental BlueZ versions.
+pub fn missing_as_default<T: Default>(result: Result<T, dbus::Error>) -> Result<T, dbus::Error> {
+    match result {
+        Err(e) if e.name() == Some("org.freedesktop.DBus.Error.InvalidArgs") => Ok(T::default()),
+        r => r,
+    }
+}
+
 fn cast_error(from: &dyn RefArg, to: &str) -> dbus::Error {
     dbus::Error::new_failed(&format!(
         "Cannot cast from {:?} to {}",
@@ -94,6 +104,14 @@ impl RefArgCast<'_> for String {
     }
 }
 
+impl RefArgCast<'_> for dbus::Path<'static> {
+    fn ref_arg_cast(r: &dyn RefArg) -> Result<Self, dbus::Error> {
+        r.as_str()
+            .and_then(|s| dbus::Path::new(s.to_owned()).ok())
+            .ok_or_else(|| cast_error(r, "Path"))
+    }
+}
+
 impl RefArgCast<'_> for u8 {
     fn ref_arg_cast(r: &dyn RefArg) -> Result<Self, dbus::Error> {
         r.as_u64()
@@ -102,6 +120,30 @@ impl RefArgCast<'_> for u8 {
     }
 }
 
+impl RefArgCast<'_> for u16 {
+    fn ref_arg_cast(r: &dyn RefArg) -> Result<Self, dbus::Error> {
+        r.as_u64()
+            .ok_or_else(|| cast_error(r, "u16"))
+            .and_then(|v| u16::try_from(v).map_err(|_| cast_error(r, "u16")))
+    }
+}
+
+impl RefArgCast<'_> for bool {
+    fn ref_arg_cast(r: &dyn RefArg) -> Result<Self, dbus::Error> {
+        r.as_u64()
+            .ok_or_else(|| cast_error(r, "bool"))
+            .map(|v| v != 0)
+    }
+}
+
+impl RefArgCast<'_> for i16 {
+    fn ref_arg_cast(r: &dyn RefArg) -> Result<Self, dbus::Error> {
+        r.as_i64()
+            .ok_or_else(|| cast_error(r, "i16"))
+            .and_then(|v| i16::try_from(v).map_err(|_| cast_error(r, "i16")))
+    }
+}
+
 impl RefArgCast<'_> for i64 {
     fn ref_arg_cast(r: &dyn RefArg) -> Result<Self, dbus::Error> {
         r.as_i64().ok_or_else(|| cast_error(r, "i64"))
@@ -227,7 +269,7 @@ impl ObjectManagerDatabase {
     }
 
     pub fn process_queue(&mut self, mut f: impl FnMut(&dbus::strings::Path, &Object)) {
-        let queue = std::mem::replace(&mut self.queue, HashMap::new());
+        let queue = std::mem::take(&mut self.queue);
         queue.into_iter().for_each(move |(object, interfaces)| {
             let interfaces = match self.objects.entry(object.clone()) {
                 hash_map::Entry::Occupied(e) => {
@@ -315,6 +357,19 @@ impl<'a, C: Deref<Target = dbus::blocking::LocalConnection>> ObjectManagerCache<
             }
         })
     }
+
+    /// Call a function for every object currently in the cache, after
+    /// processing any pending updates.
+    pub fn for_each_object(
+        &self,
+        mut f: impl FnMut(&dbus::strings::Path, &Object),
+    ) -> Result<(), TypedError> {
+        while self.manager.connection.process(Duration::from_millis(0))? {}
+        let mut database = self.database.borrow_mut();
+        database.process_queue(|_, _| {});
+        database.objects.iter().for_each(|(path, obj)| f(path, obj));
+        Ok(())
+    }
 }
 
 impl<'a, C: Deref<Target = dbus::blocking::LocalConnection>> Drop for ObjectManagerCache<'a, C> {
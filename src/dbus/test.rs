@@ -12,6 +12,27 @@ fn ref_arg_cast_string_to_u64() {
     u64::ref_arg_cast(&val).unwrap_err();
 }
 
+#[test]
+fn ref_arg_cast_u16_out_of_range() {
+    assert_eq!(u16::ref_arg_cast(&0xffffu32).unwrap(), 0xffff);
+    u16::ref_arg_cast(&0x10000u32).unwrap_err();
+}
+
+#[test]
+fn ref_arg_cast_i16_out_of_range() {
+    assert_eq!(i16::ref_arg_cast(&-0x8000i32).unwrap(), -0x8000);
+    assert_eq!(i16::ref_arg_cast(&0x7fffi32).unwrap(), 0x7fff);
+    i16::ref_arg_cast(&0x8000i32).unwrap_err();
+    i16::ref_arg_cast(&-0x8001i32).unwrap_err();
+}
+
+#[test]
+fn ref_arg_cast_path() {
+    let path = dbus::Path::from("/org/bluez/hci0");
+    assert_eq!(dbus::Path::ref_arg_cast(&path).unwrap(), path);
+    dbus::Path::ref_arg_cast(&"not a path".to_owned()).unwrap_err();
+}
+
 #[test]
 fn ref_arg_cast_vec_u8() {
     let ref_arg = vec![0u8, 1u8];
@@ -64,3 +85,15 @@ fn ref_arg_cast_hash_map_variant_to_vec_u8() {
     expected.insert("key".to_owned(), vec![0u8, 1u8]);
     assert_eq!(HashMap::ref_arg_cast(&ref_arg).unwrap(), expected);
 }
+
+#[test]
+fn missing_property() {
+    let missing =
+        dbus::Error::new_custom("org.freedesktop.DBus.Error.InvalidArgs", "No such property");
+    assert_eq!(
+        missing_as_default::<Vec<String>>(Err(missing)).unwrap(),
+        Vec::<String>::new()
+    );
+    let failed = dbus::Error::new_failed("failed");
+    assert!(missing_as_default::<Vec<String>>(Err(failed)).is_err());
+}
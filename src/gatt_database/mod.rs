@@ -0,0 +1,284 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use dbus::arg::PropMap;
+#[cfg(feature = "serde")]
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::dbus::Object;
+use crate::{
+    get_optional_property, get_property, BluetoothUuidExt, Device, Error, GattCharacteristic,
+    GattService, Timeouts,
+};
+
+#[cfg(test)]
+mod test;
+
+const DESCRIPTOR_INTERFACE: &str = "org.bluez.GattDescriptor1";
+
+/// Whether `Device::gatt_database()` reads attribute values.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GattValues {
+    /// Only list the attributes
+    Skip,
+    /// Read the value of every readable characteristic and descriptor from
+    /// the device
+    Read,
+}
+
+/// Snapshot of the GATT database of a device, as seen by BlueZ. Services,
+/// characteristics and descriptors are sorted by handle (or by object path if
+/// BlueZ doesn't publish handles), so dumps of the same firmware compare
+/// equal.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct GattDatabase {
+    pub services: Vec<GattServiceInfo>,
+}
+
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct GattServiceInfo {
+    pub path: String,
+    pub uuid: Uuid,
+    pub primary: bool,
+    pub handle: Option<u16>,
+    /// Object paths of the services included by this service
+    pub includes: Vec<String>,
+    pub characteristics: Vec<GattCharacteristicInfo>,
+}
+
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct GattCharacteristicInfo {
+    pub path: String,
+    pub uuid: Uuid,
+    pub handle: Option<u16>,
+    pub flags: Vec<String>,
+    /// Current value, only present if values were requested and the
+    /// characteristic could be read
+    pub value: Option<Vec<u8>>,
+    pub descriptors: Vec<GattDescriptorInfo>,
+}
+
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct GattDescriptorInfo {
+    pub path: String,
+    pub uuid: Uuid,
+    pub handle: Option<u16>,
+    pub flags: Vec<String>,
+    /// Current value, only present if values were requested and the
+    /// descriptor could be read
+    pub value: Option<Vec<u8>>,
+}
+
+// The Debug output shows UUIDs with their assigned names, which makes dumps
+// much easier to read.
+
+impl fmt::Debug for GattServiceInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("GattServiceInfo")
+            .field("path", &self.path)
+            .field("uuid", &self.uuid.display_name())
+            .field("primary", &self.primary)
+            .field("handle", &self.handle)
+            .field("includes", &self.includes)
+            .field("characteristics", &self.characteristics)
+            .finish()
+    }
+}
+
+impl fmt::Debug for GattCharacteristicInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("GattCharacteristicInfo")
+            .field("path", &self.path)
+            .field("uuid", &self.uuid.display_name())
+            .field("handle", &self.handle)
+            .field("flags", &self.flags)
+            .field("value", &self.value)
+            .field("descriptors", &self.descriptors)
+            .finish()
+    }
+}
+
+impl fmt::Debug for GattDescriptorInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("GattDescriptorInfo")
+            .field("path", &self.path)
+            .field("uuid", &self.uuid.display_name())
+            .field("handle", &self.handle)
+            .field("flags", &self.flags)
+            .field("value", &self.value)
+            .finish()
+    }
+}
+
+/// Sort key that orders attributes by handle, falling back to the object
+/// path, which contains the handle for objects created by BlueZ.
+fn sort_key(handle: Option<u16>, path: &str) -> (Option<u16>, String) {
+    (handle, path.to_owned())
+}
+
+/// Whether an attribute with the given flags can be read, which includes the
+/// flags that require encryption or authentication, such as "encrypt-read"
+/// and "secure-read".
+fn is_readable(flags: &[String]) -> bool {
+    flags.iter().any(|f| f.ends_with("read"))
+}
+
+/// Read the value of an attribute, treating BlueZ errors (such as
+/// `NotPermitted` or `NotAuthorized`) as a missing value.
+fn read_optional(
+    read: impl FnOnce() -> Result<Vec<u8>, dbus::Error>,
+) -> Result<Option<Vec<u8>>, Error> {
+    match read().map_err(Error::from) {
+        Ok(value) => Ok(Some(value)),
+        Err(Error::Bluez { .. }) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// Collects the GATT objects of a device from the object cache, which lists
+/// them in no particular order, and assembles them into a `GattDatabase`.
+#[derive(Default)]
+struct DatabaseBuilder {
+    services: HashMap<String, GattServiceInfo>,
+    /// Characteristics by service path
+    characteristics: HashMap<String, Vec<GattCharacteristicInfo>>,
+    /// Descriptors by characteristic path
+    descriptors: HashMap<String, Vec<GattDescriptorInfo>>,
+}
+
+impl DatabaseBuilder {
+    fn add(&mut self, path: String, interfaces: &Object) -> Result<(), Error> {
+        if let Some(p) = interfaces.get(GattService::INTERFACE) {
+            let uuid = get_property::<&str>(p, GattService::INTERFACE, "UUID")?;
+            self.services.insert(
+                path.clone(),
+                GattServiceInfo {
+                    path,
+                    uuid: Uuid::parse_str(uuid)?,
+                    primary: get_property(p, GattService::INTERFACE, "Primary")?,
+                    handle: get_optional_property(p, "Handle")?,
+                    includes: get_optional_property(p, "Includes")?.unwrap_or_default(),
+                    characteristics: vec![],
+                },
+            );
+        } else if let Some(p) = interfaces.get(GattCharacteristic::INTERFACE) {
+            let interface = GattCharacteristic::INTERFACE;
+            let uuid = get_property::<&str>(p, interface, "UUID")?;
+            let service = get_property::<String>(p, interface, "Service")?;
+            self.characteristics
+                .entry(service)
+                .or_default()
+                .push(GattCharacteristicInfo {
+                    path,
+                    uuid: Uuid::parse_str(uuid)?,
+                    handle: get_optional_property(p, "Handle")?,
+                    flags: get_property(p, interface, "Flags")?,
+                    value: None,
+                    descriptors: vec![],
+                });
+        } else if let Some(p) = interfaces.get(DESCRIPTOR_INTERFACE) {
+            let uuid = get_property::<&str>(p, DESCRIPTOR_INTERFACE, "UUID")?;
+            let characteristic = get_property::<String>(p, DESCRIPTOR_INTERFACE, "Characteristic")?;
+            self.descriptors
+                .entry(characteristic)
+                .or_default()
+                .push(GattDescriptorInfo {
+                    path,
+                    uuid: Uuid::parse_str(uuid)?,
+                    handle: get_optional_property(p, "Handle")?,
+                    flags: get_optional_property(p, "Flags")?.unwrap_or_default(),
+                    value: None,
+                });
+        }
+        Ok(())
+    }
+
+    /// Build the sorted tree. `read_value` is called with the path and
+    /// whether the attribute is a descriptor for every readable attribute.
+    fn build(
+        self,
+        mut read_value: impl FnMut(&str, bool) -> Result<Option<Vec<u8>>, Error>,
+    ) -> Result<GattDatabase, Error> {
+        let Self {
+            services,
+            mut characteristics,
+            mut descriptors,
+        } = self;
+        let mut services = services
+            .into_iter()
+            .map(|(path, mut service)| -> Result<_, Error> {
+                let mut chars = characteristics.remove(&path).unwrap_or_default();
+                for c in chars.iter_mut() {
+                    let mut descs = descriptors.remove(&c.path).unwrap_or_default();
+                    for d in descs.iter_mut() {
+                        if is_readable(&d.flags) {
+                            d.value = read_value(&d.path, true)?;
+                        }
+                    }
+                    descs.sort_by_key(|d| sort_key(d.handle, &d.path));
+                    c.descriptors = descs;
+                    if is_readable(&c.flags) {
+                        c.value = read_value(&c.path, false)?;
+                    }
+                }
+                chars.sort_by_key(|c| sort_key(c.handle, &c.path));
+                service.characteristics = chars;
+                service.includes.sort();
+                Ok(service)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        services.sort_by_key(|s| sort_key(s.handle, &s.path));
+
+        Ok(GattDatabase { services })
+    }
+}
+
+impl Device {
+    /// Build a snapshot of the GATT database of this device from the BlueZ
+    /// object cache. Services must already be resolved, otherwise the database
+    /// may be incomplete.
+    pub fn gatt_database(&self, values: GattValues) -> Result<GattDatabase, Error> {
+        self.gatt_database_with_timeouts(values, &self.timeouts)
+    }
+
+    pub fn gatt_database_with_timeouts(
+        &self,
+        values: GattValues,
+        timeouts: &Timeouts,
+    ) -> Result<GattDatabase, Error> {
+        let device_path = format!("{}/", self.device.path);
+
+        let mut builder = DatabaseBuilder::default();
+        let mut result = Ok(());
+        self.bluez.objects.for_each_object(|path, interfaces| {
+            let path = path.to_string();
+            // Only consider objects that belong to this device
+            if result.is_ok() && path.starts_with(&device_path) {
+                result = builder.add(path, interfaces);
+            }
+        })?;
+        result?;
+
+        builder.build(|path, descriptor| {
+            if values == GattValues::Skip {
+                return Ok(None);
+            }
+            let proxy = self.bluez.with_proxy(path.to_owned(), timeouts.method_call);
+            let interface = if descriptor {
+                DESCRIPTOR_INTERFACE
+            } else {
+                GattCharacteristic::INTERFACE
+            };
+            read_optional(|| {
+                self.bluez
+                    .call_method(&proxy, interface, "ReadValue", (PropMap::new(),))
+                    .map(|(value,)| value)
+            })
+        })
+    }
+}
@@ -0,0 +1,156 @@
+use dbus::arg::{PropMap, RefArg, Variant};
+
+use super::*;
+
+const DEVICE: &str = "/org/bluez/hci0/dev_00_11_22_33_44_55";
+
+fn object(interface: &str, properties: Vec<(&str, Box<dyn RefArg>)>) -> Object {
+    let properties: PropMap = properties
+        .into_iter()
+        .map(|(k, v)| (k.to_owned(), Variant(v)))
+        .collect();
+    let mut object = Object::new();
+    object.insert(interface.to_owned(), properties);
+    object
+}
+
+fn service(uuid: u16, handle: u16) -> Object {
+    object(
+        GattService::INTERFACE,
+        vec![
+            ("UUID", Box::new(crate::uuid16(uuid).to_string())),
+            ("Primary", Box::new(true)),
+            ("Handle", Box::new(handle)),
+        ],
+    )
+}
+
+fn characteristic(service: &str, uuid: u16, handle: u16, flags: &[&str]) -> Object {
+    let flags: Vec<String> = flags.iter().map(|f| f.to_string()).collect();
+    object(
+        GattCharacteristic::INTERFACE,
+        vec![
+            ("UUID", Box::new(crate::uuid16(uuid).to_string())),
+            ("Service", Box::new(dbus::Path::from(service.to_owned()))),
+            ("Handle", Box::new(handle)),
+            ("Flags", Box::new(flags)),
+        ],
+    )
+}
+
+fn descriptor(characteristic: &str, uuid: u16) -> Object {
+    object(
+        DESCRIPTOR_INTERFACE,
+        vec![
+            ("UUID", Box::new(crate::uuid16(uuid).to_string())),
+            (
+                "Characteristic",
+                Box::new(dbus::Path::from(characteristic.to_owned())),
+            ),
+        ],
+    )
+}
+
+#[test]
+fn build_sorted_tree() {
+    let battery = format!("{}/service0010", DEVICE);
+    let gap = format!("{}/service0001", DEVICE);
+    let level = format!("{}/char0011", battery);
+    let name = format!("{}/char0002", gap);
+    let appearance = format!("{}/char0004", gap);
+    let objects = vec![
+        (format!("{}/desc0013", level), descriptor(&level, 0x2902)),
+        (
+            level.clone(),
+            characteristic(&battery, 0x2a19, 0x11, &["read", "notify"]),
+        ),
+        (battery.clone(), service(0x180f, 0x10)),
+        (
+            appearance.clone(),
+            characteristic(&gap, 0x2a01, 0x04, &["encrypt-read"]),
+        ),
+        (name.clone(), characteristic(&gap, 0x2a00, 0x02, &["write"])),
+        (gap.clone(), service(0x1800, 0x01)),
+    ];
+
+    let mut builder = DatabaseBuilder::default();
+    for (path, interfaces) in objects {
+        builder.add(path, &interfaces).unwrap();
+    }
+    let mut read = vec![];
+    let database = builder
+        .build(|path, descriptor| {
+            read.push((path.to_owned(), descriptor));
+            Ok(Some(vec![1]))
+        })
+        .unwrap();
+
+    let paths: Vec<_> = database.services.iter().map(|s| &s.path).collect();
+    assert_eq!(paths, vec![&gap, &battery]);
+    let gap_chars: Vec<_> = database.services[0]
+        .characteristics
+        .iter()
+        .map(|c| (&c.path, c.value.clone()))
+        .collect();
+    assert_eq!(gap_chars, vec![(&name, None), (&appearance, Some(vec![1]))]);
+    let level_info = &database.services[1].characteristics[0];
+    assert_eq!(level_info.uuid, crate::uuid16(0x2a19));
+    assert_eq!(level_info.handle, Some(0x11));
+    assert_eq!(level_info.descriptors.len(), 1);
+    // The descriptor has no flags, so it isn't readable
+    assert_eq!(level_info.descriptors[0].value, None);
+    assert!(format!("{:?}", level_info).contains("uuid: Battery Level (0x2A19)"));
+
+    read.sort();
+    assert_eq!(read, vec![(appearance, false), (level, false)]);
+}
+
+#[test]
+fn readable_flags() {
+    let flags = |f: &[&str]| f.iter().map(|f| f.to_string()).collect::<Vec<_>>();
+    assert!(is_readable(&flags(&["read"])));
+    assert!(is_readable(&flags(&["encrypt-read"])));
+    assert!(is_readable(&flags(&["encrypt-authenticated-read"])));
+    assert!(is_readable(&flags(&["notify", "secure-read"])));
+    assert!(!is_readable(&flags(&["write", "notify"])));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serialize_json() {
+    let service_path = format!("{}/service0010", DEVICE);
+    let level = format!("{}/char0011", service_path);
+    let objects = [
+        (format!("{}/desc0013", level), descriptor(&level, 0x2902)),
+        (
+            level.clone(),
+            characteristic(&service_path, 0x2a19, 0x11, &["read"]),
+        ),
+        (service_path.clone(), service(0x180f, 0x10)),
+    ];
+    let json = |objects: &mut dyn Iterator<Item = &(String, Object)>| {
+        let mut builder = DatabaseBuilder::default();
+        for (path, interfaces) in objects {
+            builder.add(path.clone(), interfaces).unwrap();
+        }
+        let database = builder.build(|_, _| Ok(Some(vec![0x64]))).unwrap();
+        serde_json::to_string(&database).unwrap()
+    };
+
+    let expected = format!(
+        concat!(
+            r#"{{"services":[{{"path":"{service}","#,
+            r#""uuid":"0000180f-0000-1000-8000-00805f9b34fb","primary":true,"handle":16,"#,
+            r#""includes":[],"characteristics":[{{"path":"{level}","#,
+            r#""uuid":"00002a19-0000-1000-8000-00805f9b34fb","handle":17,"flags":["read"],"#,
+            r#""value":[100],"descriptors":[{{"path":"{level}/desc0013","#,
+            r#""uuid":"00002902-0000-1000-8000-00805f9b34fb","handle":null,"flags":[],"#,
+            r#""value":null}}]}}]}}]}}"#
+        ),
+        service = service_path,
+        level = level,
+    );
+    assert_eq!(json(&mut objects.iter()), expected);
+    // The output doesn't depend on the order of the object cache
+    assert_eq!(json(&mut objects.iter().rev()), expected);
+}
@@ -0,0 +1,863 @@
+//! Publishing local GATT services through BlueZ, to act as a peripheral.
+//!
+//! An `Application` is built from `Service`s, `Characteristic`s and
+//! `Descriptor`s, then registered with `Adapter::register_application()`.
+//! Handlers are called while `Bluez::process()` is processing messages, so it
+//! must be called in a loop for as long as the application is registered.
+
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+
+use dbus::arg::{OwnedFd, PropMap, Variant};
+use dbus::blocking::stdintf::org_freedesktop_dbus::PropertiesPropertiesChanged;
+use dbus::blocking::LocalConnection;
+use dbus::message::SignalArgs;
+use dbus::MethodErr;
+use uuid::Uuid;
+
+use crate::gen_server::{
+    register_gatt_characteristic1, register_gatt_descriptor1, register_gatt_service1,
+    GattCharacteristic1, GattDescriptor1, GattService1,
+};
+use crate::io::socket_pair;
+use crate::{get_optional_property, Adapter, Bluez, DBusProxy, Error, ResultExt};
+use crate::{NotifyReader, WriteChannel};
+
+#[cfg(test)]
+mod test;
+
+const CHARACTERISTIC_INTERFACE: &str = "org.bluez.GattCharacteristic1";
+const MANAGER_INTERFACE: &str = "org.bluez.GattManager1";
+
+/// ATT error returned by a handler, which BlueZ sends to the remote device.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum AttError {
+    #[error("operation failed")]
+    Failed,
+    #[error("operation in progress")]
+    InProgress,
+    #[error("operation not permitted")]
+    NotPermitted,
+    #[error("invalid value length")]
+    InvalidValueLength,
+    #[error("invalid offset")]
+    InvalidOffset,
+    #[error("not authorized")]
+    NotAuthorized,
+    #[error("not supported")]
+    NotSupported,
+}
+
+impl AttError {
+    /// The D-Bus error name BlueZ maps to this ATT error.
+    pub fn error_name(&self) -> &'static str {
+        match self {
+            Self::Failed => "org.bluez.Error.Failed",
+            Self::InProgress => "org.bluez.Error.InProgress",
+            Self::NotPermitted => "org.bluez.Error.NotPermitted",
+            Self::InvalidValueLength => "org.bluez.Error.InvalidValueLength",
+            Self::InvalidOffset => "org.bluez.Error.InvalidOffset",
+            Self::NotAuthorized => "org.bluez.Error.NotAuthorized",
+            Self::NotSupported => "org.bluez.Error.NotSupported",
+        }
+    }
+}
+
+impl From<AttError> for MethodErr {
+    fn from(e: AttError) -> Self {
+        (e.error_name(), e.to_string()).into()
+    }
+}
+
+/// Characteristic and descriptor flags. Descriptors only support the read,
+/// write, encrypt, secure and authorize flags.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Flag {
+    Broadcast,
+    Read,
+    WriteWithoutResponse,
+    Write,
+    Notify,
+    Indicate,
+    AuthenticatedSignedWrites,
+    ExtendedProperties,
+    ReliableWrite,
+    WritableAuxiliaries,
+    EncryptRead,
+    EncryptWrite,
+    EncryptNotify,
+    EncryptIndicate,
+    EncryptAuthenticatedRead,
+    EncryptAuthenticatedWrite,
+    EncryptAuthenticatedNotify,
+    EncryptAuthenticatedIndicate,
+    SecureRead,
+    SecureWrite,
+    SecureNotify,
+    SecureIndicate,
+    Authorize,
+}
+
+impl Flag {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Broadcast => "broadcast",
+            Self::Read => "read",
+            Self::WriteWithoutResponse => "write-without-response",
+            Self::Write => "write",
+            Self::Notify => "notify",
+            Self::Indicate => "indicate",
+            Self::AuthenticatedSignedWrites => "authenticated-signed-writes",
+            Self::ExtendedProperties => "extended-properties",
+            Self::ReliableWrite => "reliable-write",
+            Self::WritableAuxiliaries => "writable-auxiliaries",
+            Self::EncryptRead => "encrypt-read",
+            Self::EncryptWrite => "encrypt-write",
+            Self::EncryptNotify => "encrypt-notify",
+            Self::EncryptIndicate => "encrypt-indicate",
+            Self::EncryptAuthenticatedRead => "encrypt-authenticated-read",
+            Self::EncryptAuthenticatedWrite => "encrypt-authenticated-write",
+            Self::EncryptAuthenticatedNotify => "encrypt-authenticated-notify",
+            Self::EncryptAuthenticatedIndicate => "encrypt-authenticated-indicate",
+            Self::SecureRead => "secure-read",
+            Self::SecureWrite => "secure-write",
+            Self::SecureNotify => "secure-notify",
+            Self::SecureIndicate => "secure-indicate",
+            Self::Authorize => "authorize",
+        }
+    }
+}
+
+impl fmt::Display for Flag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WriteType {
+    /// Write without response
+    Command,
+    /// Write with response
+    Request,
+    /// Reliable write, as part of a prepared write sequence
+    Reliable,
+}
+
+/// Details of a request from a remote device, from the options BlueZ passes
+/// to the handlers.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Request {
+    /// Object path of the remote device
+    pub device: Option<String>,
+    /// Offset into the value, for long reads and writes
+    pub offset: u16,
+    /// ATT MTU of the link
+    pub mtu: Option<u16>,
+    /// Link type, e.g. "LE" or "BR/EDR"
+    pub link: Option<String>,
+    /// Type of write, only set for writes
+    pub write_type: Option<WriteType>,
+    /// Set if this is only a request to authorize a prepared write, in which
+    /// case the value must not be written yet
+    pub prepare_authorize: bool,
+}
+
+impl Request {
+    fn parse(options: &PropMap) -> Result<Self, MethodErr> {
+        let parse = || -> Result<Self, Error> {
+            Ok(Self {
+                device: get_optional_property(options, "device")?,
+                offset: get_optional_property(options, "offset")?.unwrap_or(0),
+                mtu: get_optional_property(options, "mtu")?,
+                link: get_optional_property(options, "link")?,
+                write_type: get_optional_property::<&str>(options, "type")?
+                    .map(|t| match t {
+                        "command" => Ok(WriteType::Command),
+                        "request" => Ok(WriteType::Request),
+                        "reliable" => Ok(WriteType::Reliable),
+                        t => Err(Error::InvalidValue(format!("unknown write type: {}", t))),
+                    })
+                    .transpose()?,
+                prepare_authorize: get_optional_property(options, "prepare-authorize")?
+                    .unwrap_or(false),
+            })
+        };
+        parse().map_err(|e| MethodErr::invalid_arg(&e.to_string()))
+    }
+}
+
+type ReadHandler = Box<dyn FnMut(&Request) -> Result<Vec<u8>, AttError> + Send>;
+type WriteHandler = Box<dyn FnMut(Vec<u8>, &Request) -> Result<(), AttError> + Send>;
+type AcquireWriteHandler = Box<dyn FnMut(NotifyReader, &Request) -> Result<(), AttError> + Send>;
+
+/// Create the channel for a socket acquired with `AcquireNotify`. It is
+/// non-blocking, so that a device that doesn't keep up with notifications
+/// can't block the caller of `Notifier::notify()`.
+fn notify_channel(fd: OwnedFd, mtu: u16) -> Result<WriteChannel, Error> {
+    let channel = WriteChannel::new(fd, mtu)?;
+    channel.set_nonblocking(true)?;
+    Ok(channel)
+}
+
+/// Read from a value stored locally, for attributes without a read handler.
+fn read_stored(value: &[u8], request: &Request) -> Result<Vec<u8>, AttError> {
+    value
+        .get(usize::from(request.offset)..)
+        .map(<[u8]>::to_vec)
+        .ok_or(AttError::InvalidOffset)
+}
+
+fn add_flag(flags: &mut Vec<Flag>, flag: Flag) {
+    if !flags.contains(&flag) {
+        flags.push(flag);
+    }
+}
+
+fn flag_strings(flags: &[Flag]) -> Vec<String> {
+    flags.iter().map(|f| f.as_str().to_owned()).collect()
+}
+
+/// A local GATT application, consisting of one or more services.
+#[derive(Default)]
+pub struct Application {
+    services: Vec<Service>,
+}
+
+impl Application {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn service(mut self, service: Service) -> Self {
+        self.services.push(service);
+        self
+    }
+}
+
+pub struct Service {
+    uuid: Uuid,
+    primary: bool,
+    includes: Vec<Uuid>,
+    characteristics: Vec<Characteristic>,
+}
+
+impl Service {
+    pub fn new(uuid: Uuid) -> Self {
+        Self {
+            uuid,
+            primary: true,
+            includes: Vec::new(),
+            characteristics: Vec::new(),
+        }
+    }
+
+    /// Create a secondary service, which is only meant to be included by
+    /// other services with `include()`.
+    pub fn secondary(uuid: Uuid) -> Self {
+        Self {
+            primary: false,
+            ..Self::new(uuid)
+        }
+    }
+
+    /// Include the service with the given UUID, usually a secondary service,
+    /// which must be part of the same application.
+    pub fn include(mut self, uuid: Uuid) -> Self {
+        self.includes.push(uuid);
+        self
+    }
+
+    pub fn characteristic(mut self, characteristic: Characteristic) -> Self {
+        self.characteristics.push(characteristic);
+        self
+    }
+}
+
+pub struct Characteristic {
+    uuid: Uuid,
+    flags: Vec<Flag>,
+    read: Option<ReadHandler>,
+    write: Option<WriteHandler>,
+    acquire_write: Option<AcquireWriteHandler>,
+    acquire_notify: bool,
+    descriptors: Vec<Descriptor>,
+    notifier: Notifier,
+}
+
+impl Characteristic {
+    pub fn new(uuid: Uuid) -> Self {
+        Self {
+            uuid,
+            flags: Vec::new(),
+            read: None,
+            write: None,
+            acquire_write: None,
+            acquire_notify: false,
+            descriptors: Vec::new(),
+            notifier: Notifier::new(),
+        }
+    }
+
+    pub fn flag(mut self, flag: Flag) -> Self {
+        add_flag(&mut self.flags, flag);
+        self
+    }
+
+    /// Set the value returned by reads if there is no read handler, and the
+    /// initial value of the `Value` property.
+    pub fn value(self, value: impl Into<Vec<u8>>) -> Self {
+        self.notifier.state.lock().unwrap().value = value.into();
+        self.flag(Flag::Read)
+    }
+
+    /// Handle reads. The handler is responsible for applying the offset in
+    /// the request.
+    pub fn on_read(
+        mut self,
+        handler: impl FnMut(&Request) -> Result<Vec<u8>, AttError> + Send + 'static,
+    ) -> Self {
+        self.read = Some(Box::new(handler));
+        self.flag(Flag::Read)
+    }
+
+    /// Handle writes with and without response. The handler is responsible
+    /// for applying the offset in the request.
+    pub fn on_write(
+        mut self,
+        handler: impl FnMut(Vec<u8>, &Request) -> Result<(), AttError> + Send + 'static,
+    ) -> Self {
+        self.write = Some(Box::new(handler));
+        self.flag(Flag::Write)
+    }
+
+    /// Allow BlueZ to acquire a socket for writes without response. The
+    /// handler is called with the reader for the socket, which yields one
+    /// packet per write. Writes with response still go to the `on_write()`
+    /// handler.
+    pub fn on_acquire_write(
+        mut self,
+        handler: impl FnMut(NotifyReader, &Request) -> Result<(), AttError> + Send + 'static,
+    ) -> Self {
+        self.acquire_write = Some(Box::new(handler));
+        self.flag(Flag::WriteWithoutResponse)
+    }
+
+    pub fn notify(self) -> Self {
+        self.flag(Flag::Notify)
+    }
+
+    pub fn indicate(self) -> Self {
+        self.flag(Flag::Indicate)
+    }
+
+    /// Allow BlueZ to acquire a socket for notifications, which avoids a
+    /// D-Bus signal per notification.
+    pub fn acquire_notify(mut self) -> Self {
+        self.acquire_notify = true;
+        self.notify()
+    }
+
+    pub fn descriptor(mut self, descriptor: Descriptor) -> Self {
+        self.descriptors.push(descriptor);
+        self
+    }
+
+    /// Get a handle for sending notifications and indications once the
+    /// application is registered.
+    pub fn notifier(&self) -> Notifier {
+        self.notifier.clone()
+    }
+}
+
+pub struct Descriptor {
+    uuid: Uuid,
+    flags: Vec<Flag>,
+    value: Vec<u8>,
+    read: Option<ReadHandler>,
+    write: Option<WriteHandler>,
+}
+
+impl Descriptor {
+    pub fn new(uuid: Uuid) -> Self {
+        Self {
+            uuid,
+            flags: Vec::new(),
+            value: Vec::new(),
+            read: None,
+            write: None,
+        }
+    }
+
+    pub fn flag(mut self, flag: Flag) -> Self {
+        add_flag(&mut self.flags, flag);
+        self
+    }
+
+    /// Set the value returned by reads if there is no read handler.
+    pub fn value(mut self, value: impl Into<Vec<u8>>) -> Self {
+        self.value = value.into();
+        self.flag(Flag::Read)
+    }
+
+    pub fn on_read(
+        mut self,
+        handler: impl FnMut(&Request) -> Result<Vec<u8>, AttError> + Send + 'static,
+    ) -> Self {
+        self.read = Some(Box::new(handler));
+        self.flag(Flag::Read)
+    }
+
+    pub fn on_write(
+        mut self,
+        handler: impl FnMut(Vec<u8>, &Request) -> Result<(), AttError> + Send + 'static,
+    ) -> Self {
+        self.write = Some(Box::new(handler));
+        self.flag(Flag::Write)
+    }
+}
+
+/// State of a characteristic that is shared between the exported object and
+/// its `Notifier`.
+#[derive(Default)]
+struct NotifyState {
+    value: Vec<u8>,
+    notifying: bool,
+    /// Socket acquired by BlueZ with `AcquireNotify`
+    socket: Option<WriteChannel>,
+}
+
+struct NotifyTarget {
+    connection: Rc<LocalConnection>,
+    path: dbus::Path<'static>,
+}
+
+/// Handle for sending notifications or indications of a local characteristic
+/// to subscribed devices.
+#[derive(Clone)]
+pub struct Notifier {
+    state: Arc<Mutex<NotifyState>>,
+    /// Set while the application is registered
+    target: Rc<RefCell<Option<NotifyTarget>>>,
+}
+
+impl Notifier {
+    fn new() -> Self {
+        Self {
+            state: Arc::new(Mutex::new(NotifyState::default())),
+            target: Rc::new(RefCell::new(None)),
+        }
+    }
+
+    /// Whether a device is subscribed, either through `StartNotify` or an
+    /// acquired socket.
+    pub fn is_notifying(&self) -> bool {
+        let state = self.state.lock().unwrap();
+        state.notifying || state.socket.is_some()
+    }
+
+    /// Update the value of the characteristic and notify subscribed devices.
+    /// Returns `Ok(false)` if the notification wasn't sent, in which case
+    /// only the value is updated. That happens if no device is subscribed,
+    /// or if notifications go through an acquired socket and the device
+    /// can't keep up, since this never blocks.
+    pub fn notify(&self, value: &[u8]) -> Result<bool, Error> {
+        let mut state = self.state.lock().unwrap();
+        state.value = value.to_vec();
+        if let Some(socket) = &state.socket {
+            match socket.send(value) {
+                Ok(()) => return Ok(true),
+                // The socket buffer is full, drop the notification
+                Err(Error::Io(e)) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                    return Ok(false)
+                }
+                // BlueZ closes the socket when the device unsubscribes
+                Err(Error::Io(e))
+                    if matches!(
+                        e.kind(),
+                        std::io::ErrorKind::BrokenPipe
+                            | std::io::ErrorKind::ConnectionRefused
+                            | std::io::ErrorKind::ConnectionReset
+                    ) =>
+                {
+                    state.socket = None
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        let target = self.target.borrow();
+        let target = match (&*target, state.notifying) {
+            (Some(target), true) => target,
+            _ => return Ok(false),
+        };
+        let mut changed_properties = PropMap::new();
+        changed_properties.insert("Value".into(), Variant(Box::new(value.to_vec())));
+        let signal = PropertiesPropertiesChanged {
+            interface_name: CHARACTERISTIC_INTERFACE.into(),
+            changed_properties,
+            invalidated_properties: Vec::new(),
+        };
+        target
+            .connection
+            .channel()
+            .send(signal.to_emit_message(&target.path))
+            .map_err(|_| dbus::Error::new_failed("failed to send notification"))?;
+        Ok(true)
+    }
+}
+
+struct ServiceObject {
+    uuid: Uuid,
+    primary: bool,
+    includes: Vec<dbus::Path<'static>>,
+}
+
+struct CharacteristicObject {
+    uuid: Uuid,
+    service: dbus::Path<'static>,
+    flags: Vec<Flag>,
+    read: Option<ReadHandler>,
+    write: Option<WriteHandler>,
+    acquire_write: Option<AcquireWriteHandler>,
+    acquire_notify: bool,
+    state: Arc<Mutex<NotifyState>>,
+}
+
+struct DescriptorObject {
+    uuid: Uuid,
+    characteristic: dbus::Path<'static>,
+    flags: Vec<Flag>,
+    value: Vec<u8>,
+    read: Option<ReadHandler>,
+    write: Option<WriteHandler>,
+}
+
+impl GattService1 for ServiceObject {
+    fn uuid(&self) -> Result<String, MethodErr> {
+        Ok(self.uuid.to_string())
+    }
+
+    fn primary(&self) -> Result<bool, MethodErr> {
+        Ok(self.primary)
+    }
+
+    fn includes(&self) -> Result<Vec<dbus::Path<'static>>, MethodErr> {
+        Ok(self.includes.clone())
+    }
+}
+
+/// Object paths of the services included by each service, given the path of
+/// each service.
+fn include_paths(
+    services: &[Service],
+    paths: &[dbus::Path<'static>],
+) -> Result<Vec<Vec<dbus::Path<'static>>>, Error> {
+    services
+        .iter()
+        .map(|service| {
+            service
+                .includes
+                .iter()
+                .map(|uuid| {
+                    services
+                        .iter()
+                        .position(|s| s.uuid == *uuid)
+                        .map(|i| paths[i].clone())
+                        .ok_or_else(|| {
+                            Error::InvalidValue(format!("included service not found: {}", uuid))
+                        })
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// BlueZ only uses `AcquireWrite` and `AcquireNotify` if the `WriteAcquired`
+/// and `NotifyAcquired` properties exist, so they are left out for
+/// characteristics that don't support them.
+impl GattCharacteristic1 for CharacteristicObject {
+    fn read_value(&mut self, options: PropMap) -> Result<Vec<u8>, MethodErr> {
+        let request = Request::parse(&options)?;
+        Ok(match &mut self.read {
+            Some(read) => read(&request)?,
+            None => read_stored(&self.state.lock().unwrap().value, &request)?,
+        })
+    }
+
+    fn write_value(&mut self, value: Vec<u8>, options: PropMap) -> Result<(), MethodErr> {
+        let request = Request::parse(&options)?;
+        let write = self.write.as_mut().ok_or(AttError::NotSupported)?;
+        Ok(write(value, &request)?)
+    }
+
+    fn acquire_write(&mut self, options: PropMap) -> Result<(OwnedFd, u16), MethodErr> {
+        let acquire_write = self.acquire_write.as_mut().ok_or(AttError::NotSupported)?;
+        let request = Request::parse(&options)?;
+        let mtu = request.mtu.unwrap_or(23);
+        let (local, remote) = socket_pair().map_err(|e| MethodErr::failed(&e))?;
+        let reader = NotifyReader::new(local, mtu).map_err(|e| MethodErr::failed(&e))?;
+        acquire_write(reader, &request)?;
+        Ok((remote, mtu))
+    }
+
+    fn acquire_notify(&mut self, options: PropMap) -> Result<(OwnedFd, u16), MethodErr> {
+        if !self.acquire_notify {
+            return Err(AttError::NotSupported.into());
+        }
+        let request = Request::parse(&options)?;
+        let mtu = request.mtu.unwrap_or(23);
+        let (local, remote) = socket_pair().map_err(|e| MethodErr::failed(&e))?;
+        let channel = notify_channel(local, mtu).map_err(|e| MethodErr::failed(&e))?;
+        let mut state = self.state.lock().unwrap();
+        if state.socket.is_some() {
+            return Err(AttError::InProgress.into());
+        }
+        state.socket = Some(channel);
+        Ok((remote, mtu))
+    }
+
+    fn start_notify(&mut self) -> Result<(), MethodErr> {
+        if !self.flags.contains(&Flag::Notify) && !self.flags.contains(&Flag::Indicate) {
+            return Err(AttError::NotSupported.into());
+        }
+        self.state.lock().unwrap().notifying = true;
+        Ok(())
+    }
+
+    fn stop_notify(&mut self) -> Result<(), MethodErr> {
+        self.state.lock().unwrap().notifying = false;
+        Ok(())
+    }
+
+    fn uuid(&self) -> Result<String, MethodErr> {
+        Ok(self.uuid.to_string())
+    }
+
+    fn service(&self) -> Result<dbus::Path<'static>, MethodErr> {
+        Ok(self.service.clone())
+    }
+
+    fn value(&self) -> Result<Vec<u8>, MethodErr> {
+        Ok(self.state.lock().unwrap().value.clone())
+    }
+
+    fn notifying(&self) -> Result<bool, MethodErr> {
+        Ok(self.state.lock().unwrap().notifying)
+    }
+
+    fn flags(&self) -> Result<Vec<String>, MethodErr> {
+        Ok(flag_strings(&self.flags))
+    }
+
+    /// The reader is handed over to the handler, so whether it is still open
+    /// isn't known. BlueZ only checks that this exists.
+    fn write_acquired(&self) -> Result<bool, MethodErr> {
+        self.acquire_write
+            .as_ref()
+            .map(|_| false)
+            .ok_or_else(|| MethodErr::no_property("WriteAcquired"))
+    }
+
+    fn notify_acquired(&self) -> Result<bool, MethodErr> {
+        if !self.acquire_notify {
+            return Err(MethodErr::no_property("NotifyAcquired"));
+        }
+        Ok(self.state.lock().unwrap().socket.is_some())
+    }
+}
+
+impl GattDescriptor1 for DescriptorObject {
+    fn read_value(&mut self, options: PropMap) -> Result<Vec<u8>, MethodErr> {
+        let request = Request::parse(&options)?;
+        Ok(match &mut self.read {
+            Some(read) => read(&request)?,
+            None => read_stored(&self.value, &request)?,
+        })
+    }
+
+    fn write_value(&mut self, value: Vec<u8>, options: PropMap) -> Result<(), MethodErr> {
+        let request = Request::parse(&options)?;
+        let write = self.write.as_mut().ok_or(AttError::NotSupported)?;
+        Ok(write(value, &request)?)
+    }
+
+    fn uuid(&self) -> Result<String, MethodErr> {
+        Ok(self.uuid.to_string())
+    }
+
+    fn characteristic(&self) -> Result<dbus::Path<'static>, MethodErr> {
+        Ok(self.characteristic.clone())
+    }
+
+    fn flags(&self) -> Result<Vec<String>, MethodErr> {
+        Ok(flag_strings(&self.flags))
+    }
+}
+
+/// A GATT application registered with BlueZ. Dropping the handle unregisters
+/// the application.
+pub struct ApplicationHandle {
+    bluez: Rc<Bluez>,
+    adapter: DBusProxy,
+    root: dbus::Path<'static>,
+    services: Vec<dbus::Path<'static>>,
+    characteristics: Vec<dbus::Path<'static>>,
+    descriptors: Vec<dbus::Path<'static>>,
+    notifiers: Vec<Notifier>,
+    registered: bool,
+}
+
+impl ApplicationHandle {
+    /// Object path of the application root, which implements
+    /// `org.freedesktop.DBus.ObjectManager`.
+    pub fn path(&self) -> &str {
+        &self.root
+    }
+
+    /// Object paths of the exported services, in the order they were added.
+    pub fn service_paths(&self) -> impl Iterator<Item = &str> {
+        self.services.iter().map(|p| &**p)
+    }
+
+    fn remove_objects(&mut self) {
+        let server = self.bluez.object_server();
+        for path in self.descriptors.drain(..) {
+            server.remove::<DescriptorObject>(&path);
+        }
+        for path in self.characteristics.drain(..) {
+            server.remove::<CharacteristicObject>(&path);
+        }
+        for path in self.services.drain(..) {
+            server.remove::<ServiceObject>(&path);
+        }
+        server.remove::<()>(&self.root);
+        for notifier in &self.notifiers {
+            notifier.target.replace(None);
+        }
+    }
+}
+
+impl Drop for ApplicationHandle {
+    fn drop(&mut self) {
+        if self.registered {
+            self.bluez
+                .object_server()
+                .call::<_, ()>(
+                    &self.adapter,
+                    MANAGER_INTERFACE,
+                    "UnregisterApplication",
+                    (self.root.clone(),),
+                )
+                .ok();
+        }
+        self.remove_objects();
+    }
+}
+
+impl Adapter {
+    /// Export a GATT application and register it with BlueZ, which adds its
+    /// services to the local GATT database. The application is unregistered
+    /// when the returned handle is dropped.
+    pub fn register_application(
+        &self,
+        application: Application,
+    ) -> Result<ApplicationHandle, Error> {
+        let server = self.bluez.object_server();
+        let service_iface =
+            server.interface("gatt-service", register_gatt_service1::<ServiceObject>);
+        let characteristic_iface = server.interface(
+            "gatt-characteristic",
+            register_gatt_characteristic1::<CharacteristicObject>,
+        );
+        let descriptor_iface = server.interface(
+            "gatt-descriptor",
+            register_gatt_descriptor1::<DescriptorObject>,
+        );
+
+        let root = server.unique_path("gatt");
+        let service_paths: Vec<dbus::Path<'static>> = (0..application.services.len())
+            .map(|i| format!("{}/service{}", root, i).into())
+            .collect();
+        let includes = include_paths(&application.services, &service_paths)?;
+        let mut handle = ApplicationHandle {
+            bluez: self.bluez.clone(),
+            adapter: self.adapter.clone(),
+            root: root.clone(),
+            services: Vec::new(),
+            characteristics: Vec::new(),
+            descriptors: Vec::new(),
+            notifiers: Vec::new(),
+            registered: false,
+        };
+        server.add_object_manager(root.clone());
+
+        let services = application.services.into_iter().zip(service_paths);
+        for ((service, service_path), includes) in services.zip(includes) {
+            server.insert(
+                service_path.clone(),
+                &[service_iface],
+                ServiceObject {
+                    uuid: service.uuid,
+                    primary: service.primary,
+                    includes,
+                },
+            );
+            handle.services.push(service_path.clone());
+
+            for (j, c) in service.characteristics.into_iter().enumerate() {
+                let path: dbus::Path<'static> = format!("{}/char{}", service_path, j).into();
+                c.notifier.target.replace(Some(NotifyTarget {
+                    connection: server.connection().clone(),
+                    path: path.clone(),
+                }));
+                server.insert(
+                    path.clone(),
+                    &[characteristic_iface],
+                    CharacteristicObject {
+                        uuid: c.uuid,
+                        service: service_path.clone(),
+                        flags: c.flags,
+                        read: c.read,
+                        write: c.write,
+                        acquire_write: c.acquire_write,
+                        acquire_notify: c.acquire_notify,
+                        state: c.notifier.state.clone(),
+                    },
+                );
+                handle.characteristics.push(path.clone());
+                handle.notifiers.push(c.notifier);
+
+                for (k, d) in c.descriptors.into_iter().enumerate() {
+                    let descriptor_path: dbus::Path<'static> = format!("{}/desc{}", path, k).into();
+                    server.insert(
+                        descriptor_path.clone(),
+                        &[descriptor_iface],
+                        DescriptorObject {
+                            uuid: d.uuid,
+                            characteristic: path.clone(),
+                            flags: d.flags,
+                            value: d.value,
+                            read: d.read,
+                            write: d.write,
+                        },
+                    );
+                    handle.descriptors.push(descriptor_path);
+                }
+            }
+        }
+
+        // If this fails, dropping the handle removes the objects again
+        server
+            .call::<_, ()>(
+                &self.adapter,
+                MANAGER_INTERFACE,
+                "RegisterApplication",
+                (root, PropMap::new()),
+            )
+            .context(&self.adapter, MANAGER_INTERFACE, "RegisterApplication")?;
+        handle.registered = true;
+        Ok(handle)
+    }
+}
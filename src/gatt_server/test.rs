@@ -0,0 +1,128 @@
+use dbus::arg::RefArg;
+
+use super::*;
+use crate::uuid16;
+
+fn options(entries: Vec<(&str, Box<dyn RefArg>)>) -> PropMap {
+    entries
+        .into_iter()
+        .map(|(k, v)| (k.to_owned(), Variant(v)))
+        .collect()
+}
+
+#[test]
+fn att_error_reply() {
+    let e = MethodErr::from(AttError::InvalidOffset);
+    assert_eq!(&**e.errorname(), "org.bluez.Error.InvalidOffset");
+    let e = MethodErr::from(AttError::NotPermitted);
+    assert_eq!(&**e.errorname(), "org.bluez.Error.NotPermitted");
+}
+
+#[test]
+fn parse_request() {
+    let request = Request::parse(&options(vec![
+        (
+            "device",
+            Box::new(dbus::Path::from("/org/bluez/hci0/dev_00_11_22_33_44_55")),
+        ),
+        ("offset", Box::new(5u16)),
+        ("mtu", Box::new(185u16)),
+        ("link", Box::new("LE".to_owned())),
+        ("type", Box::new("request".to_owned())),
+    ]))
+    .unwrap();
+    assert_eq!(
+        request,
+        Request {
+            device: Some("/org/bluez/hci0/dev_00_11_22_33_44_55".into()),
+            offset: 5,
+            mtu: Some(185),
+            link: Some("LE".into()),
+            write_type: Some(WriteType::Request),
+            prepare_authorize: false,
+        }
+    );
+    assert_eq!(Request::parse(&PropMap::new()).unwrap(), Request::default());
+    assert!(Request::parse(&options(vec![("type", Box::new("bogus".to_owned()))])).is_err());
+}
+
+#[test]
+fn read_stored_offset() {
+    let request = |offset| Request {
+        offset,
+        ..Request::default()
+    };
+    assert_eq!(read_stored(&[1, 2, 3], &request(1)), Ok(vec![2, 3]));
+    assert_eq!(read_stored(&[1, 2, 3], &request(3)), Ok(vec![]));
+    assert_eq!(
+        read_stored(&[1, 2, 3], &request(4)),
+        Err(AttError::InvalidOffset)
+    );
+}
+
+#[test]
+fn characteristic_flags() {
+    let c = Characteristic::new(uuid16(0x2A19))
+        .value(vec![100])
+        .on_read(|_| Ok(vec![]))
+        .acquire_notify()
+        .flag(Flag::EncryptRead);
+    assert_eq!(
+        flag_strings(&c.flags),
+        vec!["read", "notify", "encrypt-read"]
+    );
+    assert!(c.acquire_notify);
+}
+
+#[test]
+fn notifier_unregistered() {
+    let c = Characteristic::new(uuid16(0x2A19)).notify();
+    let notifier = c.notifier();
+    assert!(!notifier.is_notifying());
+    assert!(!notifier.notify(&[42]).unwrap());
+    assert_eq!(c.notifier.state.lock().unwrap().value, vec![42]);
+}
+
+#[test]
+fn notifier_acquired_socket() {
+    let notifier = Notifier::new();
+    let (local, remote) = socket_pair().unwrap();
+    notifier.state.lock().unwrap().socket = Some(notify_channel(local, 23).unwrap());
+    let reader = NotifyReader::new(remote, 23).unwrap();
+    assert!(notifier.is_notifying());
+    assert!(notifier.notify(&[1, 2]).unwrap());
+    assert_eq!(reader.recv().unwrap(), Some(vec![1, 2]));
+
+    // The device unsubscribed
+    drop(reader);
+    assert!(!notifier.notify(&[3]).unwrap());
+    assert!(!notifier.is_notifying());
+}
+
+#[test]
+fn notifier_full_socket() {
+    let notifier = Notifier::new();
+    let (local, _remote) = socket_pair().unwrap();
+    notifier.state.lock().unwrap().socket = Some(notify_channel(local, 23).unwrap());
+    // Nothing reads from the remote end, so the socket buffer fills up and
+    // notifications are dropped instead of blocking
+    while notifier.notify(&[1; 20]).unwrap() {}
+    assert!(!notifier.notify(&[2]).unwrap());
+    assert!(notifier.is_notifying());
+    assert_eq!(notifier.state.lock().unwrap().value, vec![2]);
+}
+
+#[test]
+fn service_includes() {
+    let services = vec![
+        Service::new(uuid16(0x180F)).include(uuid16(0x1801)),
+        Service::secondary(uuid16(0x1801)),
+    ];
+    let paths: Vec<dbus::Path<'static>> = vec!["/app/service0".into(), "/app/service1".into()];
+    assert_eq!(
+        include_paths(&services, &paths).unwrap(),
+        vec![vec![paths[1].clone()], vec![]]
+    );
+    let missing = vec![Service::new(uuid16(0x180F)).include(uuid16(0x1234))];
+    assert!(include_paths(&missing, &paths[..1]).is_err());
+}
@@ -1,4 +1,4 @@
-// This code was autogenerated with `dbus-codegen-rust -i org.bluez -c blocking --file src/bluez.xml -m None -o src/gen.rs`, see https://github.com/diwic/dbus-rs
+// This code was autogenerated with `dbus-codegen-rust -i org.bluez -c blocking --file src/bluez.xml -m None`, see https://github.com/diwic/dbus-rs
 use dbus;
 #[allow(unused_imports)]
 use dbus::arg;
@@ -89,6 +89,9 @@ pub trait Adapter1 {
     fn modalias(&self) -> Result<String, dbus::Error>;
     fn roles(&self) -> Result<Vec<String>, dbus::Error>;
     fn experimental_features(&self) -> Result<Vec<String>, dbus::Error>;
+    fn power_state(&self) -> Result<String, dbus::Error>;
+    fn manufacturer(&self) -> Result<u16, dbus::Error>;
+    fn version(&self) -> Result<u8, dbus::Error>;
 }
 
 impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Adapter1
@@ -121,7 +124,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Adapter1
 
     fn address(&self) -> Result<String, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.Adapter1",
             "Address",
         )
@@ -129,7 +132,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Adapter1
 
     fn address_type(&self) -> Result<String, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.Adapter1",
             "AddressType",
         )
@@ -137,7 +140,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Adapter1
 
     fn name(&self) -> Result<String, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.Adapter1",
             "Name",
         )
@@ -145,7 +148,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Adapter1
 
     fn alias(&self) -> Result<String, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.Adapter1",
             "Alias",
         )
@@ -153,7 +156,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Adapter1
 
     fn class(&self) -> Result<u32, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.Adapter1",
             "Class",
         )
@@ -161,7 +164,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Adapter1
 
     fn powered(&self) -> Result<bool, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.Adapter1",
             "Powered",
         )
@@ -169,7 +172,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Adapter1
 
     fn discoverable(&self) -> Result<bool, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.Adapter1",
             "Discoverable",
         )
@@ -177,7 +180,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Adapter1
 
     fn discoverable_timeout(&self) -> Result<u32, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.Adapter1",
             "DiscoverableTimeout",
         )
@@ -185,7 +188,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Adapter1
 
     fn pairable(&self) -> Result<bool, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.Adapter1",
             "Pairable",
         )
@@ -193,7 +196,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Adapter1
 
     fn pairable_timeout(&self) -> Result<u32, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.Adapter1",
             "PairableTimeout",
         )
@@ -201,7 +204,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Adapter1
 
     fn discovering(&self) -> Result<bool, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.Adapter1",
             "Discovering",
         )
@@ -209,7 +212,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Adapter1
 
     fn uuids(&self) -> Result<Vec<String>, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.Adapter1",
             "UUIDs",
         )
@@ -217,7 +220,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Adapter1
 
     fn modalias(&self) -> Result<String, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.Adapter1",
             "Modalias",
         )
@@ -225,7 +228,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Adapter1
 
     fn roles(&self) -> Result<Vec<String>, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.Adapter1",
             "Roles",
         )
@@ -233,15 +236,39 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Adapter1
 
     fn experimental_features(&self) -> Result<Vec<String>, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.Adapter1",
             "ExperimentalFeatures",
         )
     }
 
+    fn power_state(&self) -> Result<String, dbus::Error> {
+        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+            self,
+            "org.bluez.Adapter1",
+            "PowerState",
+        )
+    }
+
+    fn manufacturer(&self) -> Result<u16, dbus::Error> {
+        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+            self,
+            "org.bluez.Adapter1",
+            "Manufacturer",
+        )
+    }
+
+    fn version(&self) -> Result<u8, dbus::Error> {
+        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+            self,
+            "org.bluez.Adapter1",
+            "Version",
+        )
+    }
+
     fn set_alias(&self, value: String) -> Result<(), dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::set(
-            &self,
+            self,
             "org.bluez.Adapter1",
             "Alias",
             value,
@@ -250,7 +277,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Adapter1
 
     fn set_powered(&self, value: bool) -> Result<(), dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::set(
-            &self,
+            self,
             "org.bluez.Adapter1",
             "Powered",
             value,
@@ -259,7 +286,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Adapter1
 
     fn set_discoverable(&self, value: bool) -> Result<(), dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::set(
-            &self,
+            self,
             "org.bluez.Adapter1",
             "Discoverable",
             value,
@@ -268,7 +295,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Adapter1
 
     fn set_discoverable_timeout(&self, value: u32) -> Result<(), dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::set(
-            &self,
+            self,
             "org.bluez.Adapter1",
             "DiscoverableTimeout",
             value,
@@ -277,7 +304,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Adapter1
 
     fn set_pairable(&self, value: bool) -> Result<(), dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::set(
-            &self,
+            self,
             "org.bluez.Adapter1",
             "Pairable",
             value,
@@ -286,7 +313,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Adapter1
 
     fn set_pairable_timeout(&self, value: u32) -> Result<(), dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::set(
-            &self,
+            self,
             "org.bluez.Adapter1",
             "PairableTimeout",
             value,
@@ -380,7 +407,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Advertis
 
     fn supported_monitor_types(&self) -> Result<Vec<String>, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.AdvertisementMonitorManager1",
             "SupportedMonitorTypes",
         )
@@ -388,7 +415,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Advertis
 
     fn supported_features(&self) -> Result<Vec<String>, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.AdvertisementMonitorManager1",
             "SupportedFeatures",
         )
@@ -464,6 +491,51 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Media1
     }
 }
 
+pub trait Network1 {
+    fn connect(&self, uuid: &str) -> Result<String, dbus::Error>;
+    fn disconnect(&self) -> Result<(), dbus::Error>;
+    fn connected(&self) -> Result<bool, dbus::Error>;
+    fn interface(&self) -> Result<String, dbus::Error>;
+    fn uuid(&self) -> Result<String, dbus::Error>;
+}
+
+impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Network1
+    for blocking::Proxy<'a, C>
+{
+    fn connect(&self, uuid: &str) -> Result<String, dbus::Error> {
+        self.method_call("org.bluez.Network1", "Connect", (uuid,))
+            .and_then(|r: (String,)| Ok(r.0))
+    }
+
+    fn disconnect(&self) -> Result<(), dbus::Error> {
+        self.method_call("org.bluez.Network1", "Disconnect", ())
+    }
+
+    fn connected(&self) -> Result<bool, dbus::Error> {
+        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+            self,
+            "org.bluez.Network1",
+            "Connected",
+        )
+    }
+
+    fn interface(&self) -> Result<String, dbus::Error> {
+        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+            self,
+            "org.bluez.Network1",
+            "Interface",
+        )
+    }
+
+    fn uuid(&self) -> Result<String, dbus::Error> {
+        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+            self,
+            "org.bluez.Network1",
+            "UUID",
+        )
+    }
+}
+
 pub trait NetworkServer1 {
     fn register(&self, uuid: &str, bridge: &str) -> Result<(), dbus::Error>;
     fn unregister(&self, uuid: &str) -> Result<(), dbus::Error>;
@@ -521,7 +593,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> LEAdvert
 
     fn active_instances(&self) -> Result<u8, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.LEAdvertisingManager1",
             "ActiveInstances",
         )
@@ -529,7 +601,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> LEAdvert
 
     fn supported_instances(&self) -> Result<u8, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.LEAdvertisingManager1",
             "SupportedInstances",
         )
@@ -537,7 +609,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> LEAdvert
 
     fn supported_includes(&self) -> Result<Vec<String>, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.LEAdvertisingManager1",
             "SupportedIncludes",
         )
@@ -545,7 +617,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> LEAdvert
 
     fn supported_secondary_channels(&self) -> Result<Vec<String>, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.LEAdvertisingManager1",
             "SupportedSecondaryChannels",
         )
@@ -553,7 +625,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> LEAdvert
 
     fn supported_features(&self) -> Result<Vec<String>, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.LEAdvertisingManager1",
             "SupportedFeatures",
         )
@@ -561,7 +633,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> LEAdvert
 
     fn supported_capabilities(&self) -> Result<arg::PropMap, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.LEAdvertisingManager1",
             "SupportedCapabilities",
         )
@@ -612,6 +684,36 @@ pub trait Device1 {
     >;
     fn wake_allowed(&self) -> Result<bool, dbus::Error>;
     fn set_wake_allowed(&self, value: bool) -> Result<(), dbus::Error>;
+    fn sets(
+        &self,
+    ) -> Result<::std::collections::HashMap<dbus::Path<'static>, arg::PropMap>, dbus::Error>;
+}
+
+#[derive(Debug)]
+pub struct Device1Disconnected {
+    pub reason: String,
+    pub message: String,
+}
+
+impl arg::AppendAll for Device1Disconnected {
+    fn append(&self, i: &mut arg::IterAppend) {
+        arg::RefArg::append(&self.reason, i);
+        arg::RefArg::append(&self.message, i);
+    }
+}
+
+impl arg::ReadAll for Device1Disconnected {
+    fn read(i: &mut arg::Iter) -> Result<Self, arg::TypeMismatchError> {
+        Ok(Device1Disconnected {
+            reason: i.read()?,
+            message: i.read()?,
+        })
+    }
+}
+
+impl dbus::message::SignalArgs for Device1Disconnected {
+    const NAME: &'static str = "Disconnected";
+    const INTERFACE: &'static str = "org.bluez.Device1";
 }
 
 impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Device1
@@ -643,7 +745,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Device1
 
     fn address(&self) -> Result<String, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.Device1",
             "Address",
         )
@@ -651,7 +753,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Device1
 
     fn address_type(&self) -> Result<String, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.Device1",
             "AddressType",
         )
@@ -659,7 +761,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Device1
 
     fn name(&self) -> Result<String, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.Device1",
             "Name",
         )
@@ -667,7 +769,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Device1
 
     fn alias(&self) -> Result<String, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.Device1",
             "Alias",
         )
@@ -675,7 +777,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Device1
 
     fn class(&self) -> Result<u32, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.Device1",
             "Class",
         )
@@ -683,7 +785,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Device1
 
     fn appearance(&self) -> Result<u16, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.Device1",
             "Appearance",
         )
@@ -691,7 +793,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Device1
 
     fn icon(&self) -> Result<String, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.Device1",
             "Icon",
         )
@@ -699,7 +801,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Device1
 
     fn paired(&self) -> Result<bool, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.Device1",
             "Paired",
         )
@@ -707,7 +809,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Device1
 
     fn trusted(&self) -> Result<bool, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.Device1",
             "Trusted",
         )
@@ -715,7 +817,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Device1
 
     fn blocked(&self) -> Result<bool, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.Device1",
             "Blocked",
         )
@@ -723,7 +825,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Device1
 
     fn legacy_pairing(&self) -> Result<bool, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.Device1",
             "LegacyPairing",
         )
@@ -731,7 +833,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Device1
 
     fn rssi(&self) -> Result<i16, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.Device1",
             "RSSI",
         )
@@ -739,7 +841,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Device1
 
     fn connected(&self) -> Result<bool, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.Device1",
             "Connected",
         )
@@ -747,7 +849,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Device1
 
     fn uuids(&self) -> Result<Vec<String>, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.Device1",
             "UUIDs",
         )
@@ -755,7 +857,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Device1
 
     fn modalias(&self) -> Result<String, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.Device1",
             "Modalias",
         )
@@ -763,7 +865,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Device1
 
     fn adapter(&self) -> Result<dbus::Path<'static>, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.Device1",
             "Adapter",
         )
@@ -776,7 +878,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Device1
         dbus::Error,
     > {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.Device1",
             "ManufacturerData",
         )
@@ -784,7 +886,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Device1
 
     fn service_data(&self) -> Result<arg::PropMap, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.Device1",
             "ServiceData",
         )
@@ -792,7 +894,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Device1
 
     fn tx_power(&self) -> Result<i16, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.Device1",
             "TxPower",
         )
@@ -800,7 +902,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Device1
 
     fn services_resolved(&self) -> Result<bool, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.Device1",
             "ServicesResolved",
         )
@@ -808,7 +910,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Device1
 
     fn advertising_flags(&self) -> Result<Vec<u8>, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.Device1",
             "AdvertisingFlags",
         )
@@ -821,7 +923,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Device1
         dbus::Error,
     > {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.Device1",
             "AdvertisingData",
         )
@@ -829,15 +931,25 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Device1
 
     fn wake_allowed(&self) -> Result<bool, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.Device1",
             "WakeAllowed",
         )
     }
 
+    fn sets(
+        &self,
+    ) -> Result<::std::collections::HashMap<dbus::Path<'static>, arg::PropMap>, dbus::Error> {
+        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+            self,
+            "org.bluez.Device1",
+            "Sets",
+        )
+    }
+
     fn set_alias(&self, value: String) -> Result<(), dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::set(
-            &self,
+            self,
             "org.bluez.Device1",
             "Alias",
             value,
@@ -846,7 +958,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Device1
 
     fn set_trusted(&self, value: bool) -> Result<(), dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::set(
-            &self,
+            self,
             "org.bluez.Device1",
             "Trusted",
             value,
@@ -855,7 +967,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Device1
 
     fn set_blocked(&self, value: bool) -> Result<(), dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::set(
-            &self,
+            self,
             "org.bluez.Device1",
             "Blocked",
             value,
@@ -864,7 +976,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Device1
 
     fn set_wake_allowed(&self, value: bool) -> Result<(), dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::set(
-            &self,
+            self,
             "org.bluez.Device1",
             "WakeAllowed",
             value,
@@ -874,6 +986,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Device1
 
 pub trait Battery1 {
     fn percentage(&self) -> Result<u8, dbus::Error>;
+    fn source(&self) -> Result<String, dbus::Error>;
 }
 
 impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Battery1
@@ -881,11 +994,19 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> Battery1
 {
     fn percentage(&self) -> Result<u8, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.Battery1",
             "Percentage",
         )
     }
+
+    fn source(&self) -> Result<String, dbus::Error> {
+        <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
+            self,
+            "org.bluez.Battery1",
+            "Source",
+        )
+    }
 }
 
 pub trait GattService1 {
@@ -900,7 +1021,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> GattServ
 {
     fn uuid(&self) -> Result<String, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.GattService1",
             "UUID",
         )
@@ -908,7 +1029,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> GattServ
 
     fn device(&self) -> Result<dbus::Path<'static>, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.GattService1",
             "Device",
         )
@@ -916,7 +1037,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> GattServ
 
     fn primary(&self) -> Result<bool, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.GattService1",
             "Primary",
         )
@@ -924,7 +1045,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> GattServ
 
     fn includes(&self) -> Result<Vec<dbus::Path<'static>>, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.GattService1",
             "Includes",
         )
@@ -981,7 +1102,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> GattChar
 
     fn uuid(&self) -> Result<String, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.GattCharacteristic1",
             "UUID",
         )
@@ -989,7 +1110,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> GattChar
 
     fn service(&self) -> Result<dbus::Path<'static>, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.GattCharacteristic1",
             "Service",
         )
@@ -997,7 +1118,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> GattChar
 
     fn value(&self) -> Result<Vec<u8>, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.GattCharacteristic1",
             "Value",
         )
@@ -1005,7 +1126,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> GattChar
 
     fn notifying(&self) -> Result<bool, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.GattCharacteristic1",
             "Notifying",
         )
@@ -1013,7 +1134,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> GattChar
 
     fn flags(&self) -> Result<Vec<String>, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.GattCharacteristic1",
             "Flags",
         )
@@ -1021,7 +1142,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> GattChar
 
     fn write_acquired(&self) -> Result<bool, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.GattCharacteristic1",
             "WriteAcquired",
         )
@@ -1029,7 +1150,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> GattChar
 
     fn notify_acquired(&self) -> Result<bool, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.GattCharacteristic1",
             "NotifyAcquired",
         )
@@ -1058,7 +1179,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> GattDesc
 
     fn uuid(&self) -> Result<String, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.GattDescriptor1",
             "UUID",
         )
@@ -1066,7 +1187,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> GattDesc
 
     fn characteristic(&self) -> Result<dbus::Path<'static>, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.GattDescriptor1",
             "Characteristic",
         )
@@ -1074,7 +1195,7 @@ impl<'a, T: blocking::BlockingSender, C: ::std::ops::Deref<Target = T>> GattDesc
 
     fn value(&self) -> Result<Vec<u8>, dbus::Error> {
         <Self as blocking::stdintf::org_freedesktop_dbus::Properties>::get(
-            &self,
+            self,
             "org.bluez.GattDescriptor1",
             "Value",
         )
@@ -0,0 +1,335 @@
+// This code was autogenerated with `dbus-codegen-rust -i org.bluez --crossroads --file src/bluez_server.xml -m None`, see https://github.com/diwic/dbus-rs
+use dbus;
+#[allow(unused_imports)]
+use dbus::arg;
+use dbus_crossroads as crossroads;
+
+pub trait Profile1 {
+    fn release(&mut self) -> Result<(), dbus::MethodErr>;
+    fn new_connection(
+        &mut self,
+        device: dbus::Path<'static>,
+        fd: arg::OwnedFd,
+        fd_properties: arg::PropMap,
+    ) -> Result<(), dbus::MethodErr>;
+    fn request_disconnection(&mut self, device: dbus::Path<'static>)
+        -> Result<(), dbus::MethodErr>;
+}
+
+pub fn register_profile1<T>(cr: &mut crossroads::Crossroads) -> crossroads::IfaceToken<T>
+where
+    T: Profile1 + Send + 'static,
+{
+    cr.register("org.bluez.Profile1", |b| {
+        b.method("Release", (), (), |_, t: &mut T, ()| t.release());
+        b.method(
+            "NewConnection",
+            ("device", "fd", "fd_properties"),
+            (),
+            |_, t: &mut T, (device, fd, fd_properties)| t.new_connection(device, fd, fd_properties),
+        );
+        b.method(
+            "RequestDisconnection",
+            ("device",),
+            (),
+            |_, t: &mut T, (device,)| t.request_disconnection(device),
+        );
+    })
+}
+
+pub trait BatteryProvider1 {
+    fn device(&self) -> Result<dbus::Path<'static>, dbus::MethodErr>;
+    fn percentage(&self) -> Result<u8, dbus::MethodErr>;
+    fn source(&self) -> Result<String, dbus::MethodErr>;
+}
+
+pub fn register_battery_provider1<T>(cr: &mut crossroads::Crossroads) -> crossroads::IfaceToken<T>
+where
+    T: BatteryProvider1 + Send + 'static,
+{
+    cr.register("org.bluez.BatteryProvider1", |b| {
+        b.property::<dbus::Path<'static>, _>("Device")
+            .get(|_, t: &mut T| t.device());
+        b.property::<u8, _>("Percentage")
+            .get(|_, t: &mut T| t.percentage());
+        b.property::<String, _>("Source")
+            .get(|_, t: &mut T| t.source());
+    })
+}
+
+pub trait AdvertisementMonitor1 {
+    fn release(&mut self) -> Result<(), dbus::MethodErr>;
+    fn activate(&mut self) -> Result<(), dbus::MethodErr>;
+    fn device_found(&mut self, device: dbus::Path<'static>) -> Result<(), dbus::MethodErr>;
+    fn device_lost(&mut self, device: dbus::Path<'static>) -> Result<(), dbus::MethodErr>;
+    fn type_(&self) -> Result<String, dbus::MethodErr>;
+    fn rssihigh_threshold(&self) -> Result<i16, dbus::MethodErr>;
+    fn rssihigh_timeout(&self) -> Result<u16, dbus::MethodErr>;
+    fn rssilow_threshold(&self) -> Result<i16, dbus::MethodErr>;
+    fn rssilow_timeout(&self) -> Result<u16, dbus::MethodErr>;
+    fn rssisampling_period(&self) -> Result<u16, dbus::MethodErr>;
+    fn patterns(&self) -> Result<Vec<(u8, u8, Vec<u8>)>, dbus::MethodErr>;
+}
+
+pub fn register_advertisement_monitor1<T>(
+    cr: &mut crossroads::Crossroads,
+) -> crossroads::IfaceToken<T>
+where
+    T: AdvertisementMonitor1 + Send + 'static,
+{
+    cr.register("org.bluez.AdvertisementMonitor1", |b| {
+        b.method("Release", (), (), |_, t: &mut T, ()| t.release());
+        b.method("Activate", (), (), |_, t: &mut T, ()| t.activate());
+        b.method("DeviceFound", ("device",), (), |_, t: &mut T, (device,)| {
+            t.device_found(device)
+        });
+        b.method("DeviceLost", ("device",), (), |_, t: &mut T, (device,)| {
+            t.device_lost(device)
+        });
+        b.property::<String, _>("Type")
+            .get(|_, t: &mut T| t.type_());
+        b.property::<i16, _>("RSSIHighThreshold")
+            .get(|_, t: &mut T| t.rssihigh_threshold());
+        b.property::<u16, _>("RSSIHighTimeout")
+            .get(|_, t: &mut T| t.rssihigh_timeout());
+        b.property::<i16, _>("RSSILowThreshold")
+            .get(|_, t: &mut T| t.rssilow_threshold());
+        b.property::<u16, _>("RSSILowTimeout")
+            .get(|_, t: &mut T| t.rssilow_timeout());
+        b.property::<u16, _>("RSSISamplingPeriod")
+            .get(|_, t: &mut T| t.rssisampling_period());
+        b.property::<Vec<(u8, u8, Vec<u8>)>, _>("Patterns")
+            .get(|_, t: &mut T| t.patterns());
+    })
+}
+
+pub trait MediaEndpoint1 {
+    fn set_configuration(
+        &mut self,
+        transport: dbus::Path<'static>,
+        properties: arg::PropMap,
+    ) -> Result<(), dbus::MethodErr>;
+    fn select_configuration(&mut self, capabilities: Vec<u8>) -> Result<Vec<u8>, dbus::MethodErr>;
+    fn clear_configuration(
+        &mut self,
+        transport: dbus::Path<'static>,
+    ) -> Result<(), dbus::MethodErr>;
+    fn release(&mut self) -> Result<(), dbus::MethodErr>;
+}
+
+pub fn register_media_endpoint1<T>(cr: &mut crossroads::Crossroads) -> crossroads::IfaceToken<T>
+where
+    T: MediaEndpoint1 + Send + 'static,
+{
+    cr.register("org.bluez.MediaEndpoint1", |b| {
+        b.method(
+            "SetConfiguration",
+            ("transport", "properties"),
+            (),
+            |_, t: &mut T, (transport, properties)| t.set_configuration(transport, properties),
+        );
+        b.method(
+            "SelectConfiguration",
+            ("capabilities",),
+            ("configuration",),
+            |_, t: &mut T, (capabilities,)| t.select_configuration(capabilities).map(|x| (x,)),
+        );
+        b.method(
+            "ClearConfiguration",
+            ("transport",),
+            (),
+            |_, t: &mut T, (transport,)| t.clear_configuration(transport),
+        );
+        b.method("Release", (), (), |_, t: &mut T, ()| t.release());
+    })
+}
+
+pub trait LEAdvertisement1 {
+    fn release(&mut self) -> Result<(), dbus::MethodErr>;
+    fn type_(&self) -> Result<String, dbus::MethodErr>;
+    fn service_uuids(&self) -> Result<Vec<String>, dbus::MethodErr>;
+    fn manufacturer_data(
+        &self,
+    ) -> Result<
+        ::std::collections::HashMap<u16, arg::Variant<Box<dyn arg::RefArg + 'static>>>,
+        dbus::MethodErr,
+    >;
+    fn solicit_uuids(&self) -> Result<Vec<String>, dbus::MethodErr>;
+    fn service_data(&self) -> Result<arg::PropMap, dbus::MethodErr>;
+    fn includes(&self) -> Result<Vec<String>, dbus::MethodErr>;
+    fn local_name(&self) -> Result<String, dbus::MethodErr>;
+    fn appearance(&self) -> Result<u16, dbus::MethodErr>;
+    fn duration(&self) -> Result<u16, dbus::MethodErr>;
+    fn timeout(&self) -> Result<u16, dbus::MethodErr>;
+    fn discoverable(&self) -> Result<bool, dbus::MethodErr>;
+    fn secondary_channel(&self) -> Result<String, dbus::MethodErr>;
+    fn min_interval(&self) -> Result<u32, dbus::MethodErr>;
+    fn max_interval(&self) -> Result<u32, dbus::MethodErr>;
+    fn tx_power(&self) -> Result<i16, dbus::MethodErr>;
+}
+
+pub fn register_leadvertisement1<T>(cr: &mut crossroads::Crossroads) -> crossroads::IfaceToken<T>
+where
+    T: LEAdvertisement1 + Send + 'static,
+{
+    cr.register("org.bluez.LEAdvertisement1", |b| {
+        b.method("Release", (), (), |_, t: &mut T, ()| {
+            t.release()
+        });
+        b.property::<String, _>("Type")
+            .get(|_, t: &mut T| t.type_());
+        b.property::<Vec<String>, _>("ServiceUUIDs")
+            .get(|_, t: &mut T| t.service_uuids());
+        b.property::<::std::collections::HashMap<u16, arg::Variant<Box<dyn arg::RefArg + 'static>>>, _>("ManufacturerData")
+            .get(|_, t: &mut T| t.manufacturer_data());
+        b.property::<Vec<String>, _>("SolicitUUIDs")
+            .get(|_, t: &mut T| t.solicit_uuids());
+        b.property::<arg::PropMap, _>("ServiceData")
+            .get(|_, t: &mut T| t.service_data());
+        b.property::<Vec<String>, _>("Includes")
+            .get(|_, t: &mut T| t.includes());
+        b.property::<String, _>("LocalName")
+            .get(|_, t: &mut T| t.local_name());
+        b.property::<u16, _>("Appearance")
+            .get(|_, t: &mut T| t.appearance());
+        b.property::<u16, _>("Duration")
+            .get(|_, t: &mut T| t.duration());
+        b.property::<u16, _>("Timeout")
+            .get(|_, t: &mut T| t.timeout());
+        b.property::<bool, _>("Discoverable")
+            .get(|_, t: &mut T| t.discoverable());
+        b.property::<String, _>("SecondaryChannel")
+            .get(|_, t: &mut T| t.secondary_channel());
+        b.property::<u32, _>("MinInterval")
+            .get(|_, t: &mut T| t.min_interval());
+        b.property::<u32, _>("MaxInterval")
+            .get(|_, t: &mut T| t.max_interval());
+        b.property::<i16, _>("TxPower")
+            .get(|_, t: &mut T| t.tx_power());
+    })
+}
+
+pub trait GattService1 {
+    fn uuid(&self) -> Result<String, dbus::MethodErr>;
+    fn primary(&self) -> Result<bool, dbus::MethodErr>;
+    fn includes(&self) -> Result<Vec<dbus::Path<'static>>, dbus::MethodErr>;
+}
+
+pub fn register_gatt_service1<T>(cr: &mut crossroads::Crossroads) -> crossroads::IfaceToken<T>
+where
+    T: GattService1 + Send + 'static,
+{
+    cr.register("org.bluez.GattService1", |b| {
+        b.property::<String, _>("UUID").get(|_, t: &mut T| t.uuid());
+        b.property::<bool, _>("Primary")
+            .get(|_, t: &mut T| t.primary());
+        b.property::<Vec<dbus::Path<'static>>, _>("Includes")
+            .get(|_, t: &mut T| t.includes());
+    })
+}
+
+pub trait GattCharacteristic1 {
+    fn read_value(&mut self, options: arg::PropMap) -> Result<Vec<u8>, dbus::MethodErr>;
+    fn write_value(&mut self, value: Vec<u8>, options: arg::PropMap)
+        -> Result<(), dbus::MethodErr>;
+    fn acquire_write(
+        &mut self,
+        options: arg::PropMap,
+    ) -> Result<(arg::OwnedFd, u16), dbus::MethodErr>;
+    fn acquire_notify(
+        &mut self,
+        options: arg::PropMap,
+    ) -> Result<(arg::OwnedFd, u16), dbus::MethodErr>;
+    fn start_notify(&mut self) -> Result<(), dbus::MethodErr>;
+    fn stop_notify(&mut self) -> Result<(), dbus::MethodErr>;
+    fn uuid(&self) -> Result<String, dbus::MethodErr>;
+    fn service(&self) -> Result<dbus::Path<'static>, dbus::MethodErr>;
+    fn value(&self) -> Result<Vec<u8>, dbus::MethodErr>;
+    fn notifying(&self) -> Result<bool, dbus::MethodErr>;
+    fn flags(&self) -> Result<Vec<String>, dbus::MethodErr>;
+    fn write_acquired(&self) -> Result<bool, dbus::MethodErr>;
+    fn notify_acquired(&self) -> Result<bool, dbus::MethodErr>;
+}
+
+pub fn register_gatt_characteristic1<T>(
+    cr: &mut crossroads::Crossroads,
+) -> crossroads::IfaceToken<T>
+where
+    T: GattCharacteristic1 + Send + 'static,
+{
+    cr.register("org.bluez.GattCharacteristic1", |b| {
+        b.method(
+            "ReadValue",
+            ("options",),
+            ("value",),
+            |_, t: &mut T, (options,)| t.read_value(options).map(|x| (x,)),
+        );
+        b.method(
+            "WriteValue",
+            ("value", "options"),
+            (),
+            |_, t: &mut T, (value, options)| t.write_value(value, options),
+        );
+        b.method(
+            "AcquireWrite",
+            ("options",),
+            ("fd", "mtu"),
+            |_, t: &mut T, (options,)| t.acquire_write(options),
+        );
+        b.method(
+            "AcquireNotify",
+            ("options",),
+            ("fd", "mtu"),
+            |_, t: &mut T, (options,)| t.acquire_notify(options),
+        );
+        b.method("StartNotify", (), (), |_, t: &mut T, ()| t.start_notify());
+        b.method("StopNotify", (), (), |_, t: &mut T, ()| t.stop_notify());
+        b.property::<String, _>("UUID").get(|_, t: &mut T| t.uuid());
+        b.property::<dbus::Path<'static>, _>("Service")
+            .get(|_, t: &mut T| t.service());
+        b.property::<Vec<u8>, _>("Value")
+            .get(|_, t: &mut T| t.value());
+        b.property::<bool, _>("Notifying")
+            .get(|_, t: &mut T| t.notifying());
+        b.property::<Vec<String>, _>("Flags")
+            .get(|_, t: &mut T| t.flags());
+        b.property::<bool, _>("WriteAcquired")
+            .get(|_, t: &mut T| t.write_acquired());
+        b.property::<bool, _>("NotifyAcquired")
+            .get(|_, t: &mut T| t.notify_acquired());
+    })
+}
+
+pub trait GattDescriptor1 {
+    fn read_value(&mut self, options: arg::PropMap) -> Result<Vec<u8>, dbus::MethodErr>;
+    fn write_value(&mut self, value: Vec<u8>, options: arg::PropMap)
+        -> Result<(), dbus::MethodErr>;
+    fn uuid(&self) -> Result<String, dbus::MethodErr>;
+    fn characteristic(&self) -> Result<dbus::Path<'static>, dbus::MethodErr>;
+    fn flags(&self) -> Result<Vec<String>, dbus::MethodErr>;
+}
+
+pub fn register_gatt_descriptor1<T>(cr: &mut crossroads::Crossroads) -> crossroads::IfaceToken<T>
+where
+    T: GattDescriptor1 + Send + 'static,
+{
+    cr.register("org.bluez.GattDescriptor1", |b| {
+        b.method(
+            "ReadValue",
+            ("options",),
+            ("value",),
+            |_, t: &mut T, (options,)| t.read_value(options).map(|x| (x,)),
+        );
+        b.method(
+            "WriteValue",
+            ("value", "options"),
+            (),
+            |_, t: &mut T, (value, options)| t.write_value(value, options),
+        );
+        b.property::<String, _>("UUID").get(|_, t: &mut T| t.uuid());
+        b.property::<dbus::Path<'static>, _>("Characteristic")
+            .get(|_, t: &mut T| t.characteristic());
+        b.property::<Vec<String>, _>("Flags")
+            .get(|_, t: &mut T| t.flags());
+    })
+}
@@ -0,0 +1,185 @@
+//! Resolution of resolvable private addresses (RPAs) using identity resolving
+//! keys (IRKs).
+
+use std::collections::HashMap;
+use std::iter::FromIterator;
+
+use aes::cipher::generic_array::GenericArray;
+use aes::cipher::{BlockEncrypt, KeyInit};
+use aes::Aes128;
+
+use crate::util::{parse_address, parse_key};
+use crate::{get_property, Adapter, Device, Error, Timeouts};
+
+#[cfg(test)]
+mod test;
+
+/// Identity resolving key, most significant byte first. Note that BlueZ stores
+/// IRKs least significant byte first, so keys taken from its storage need to
+/// be reversed.
+pub type Irk = [u8; 16];
+
+/// Parse an IRK from its hexadecimal representation, most significant byte
+/// first.
+pub fn parse_irk(hex: &str) -> Result<Irk, Error> {
+    parse_key(hex, "identity resolving key")
+}
+
+/// The random address hash function `ah` from the Bluetooth Core
+/// specification, Vol 3, Part H, 2.2.2.
+pub fn ah(irk: &Irk, prand: [u8; 3]) -> [u8; 3] {
+    let mut block = [0; 16];
+    block[13..].copy_from_slice(&prand);
+    let mut block = GenericArray::from(block);
+    Aes128::new(GenericArray::from_slice(irk)).encrypt_block(&mut block);
+    [block[13], block[14], block[15]]
+}
+
+/// Maps resolvable private addresses to stable identities, using a set of
+/// known IRKs. The identity can be anything that identifies the peer to the
+/// application, such as its identity address or a user ID.
+#[derive(Clone, Debug)]
+pub struct IdentityResolver<T> {
+    keys: Vec<(Irk, T)>,
+}
+
+impl<T> Default for IdentityResolver<T> {
+    fn default() -> Self {
+        Self { keys: Vec::new() }
+    }
+}
+
+impl<T> FromIterator<(Irk, T)> for IdentityResolver<T> {
+    fn from_iter<I: IntoIterator<Item = (Irk, T)>>(iter: I) -> Self {
+        Self {
+            keys: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl<T> IdentityResolver<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, irk: Irk, identity: T) {
+        self.keys.push((irk, identity));
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Resolve an address in the "XX:XX:XX:XX:XX:XX" format used by BlueZ.
+    /// Returns `Ok(None)` if the address isn't a resolvable private address,
+    /// or if none of the known IRKs resolve it.
+    pub fn resolve(&self, address: &str) -> Result<Option<&T>, Error> {
+        let address = parse_address(address)?;
+        // The two most significant bits of a resolvable private address are
+        // 0b01
+        if address[0] >> 6 != 0b01 {
+            return Ok(None);
+        }
+        let prand = [address[0], address[1], address[2]];
+        let hash = [address[3], address[4], address[5]];
+        Ok(self
+            .keys
+            .iter()
+            .find(|(irk, _)| ah(irk, prand) == hash)
+            .map(|(_, identity)| identity))
+    }
+
+    /// Resolve the address of a Device1 object from its properties. Only
+    /// random addresses are considered.
+    fn resolve_properties(&self, properties: &dbus::arg::PropMap) -> Result<Option<&T>, Error> {
+        let address_type: &str = get_property(properties, Device::INTERFACE, "AddressType")?;
+        if address_type != "random" {
+            return Ok(None);
+        }
+        self.resolve(get_property(properties, Device::INTERFACE, "Address")?)
+    }
+}
+
+impl Device {
+    /// Resolve the identity of this device, if it uses a resolvable private
+    /// address that one of the resolver's IRKs resolves.
+    pub fn resolve_identity<'a, T>(
+        &self,
+        resolver: &'a IdentityResolver<T>,
+    ) -> Result<Option<&'a T>, Error> {
+        let mut properties = HashMap::new();
+        for property in &["Address", "AddressType"] {
+            if let Some(value) = self.properties.get(Self::INTERFACE, property)? {
+                properties.insert(property.to_string(), dbus::arg::Variant(value));
+            }
+        }
+        resolver.resolve_properties(&properties)
+    }
+}
+
+impl Adapter {
+    /// Find a device whose resolvable private address resolves to the given
+    /// identity. The same peer may be known to BlueZ under several addresses,
+    /// in which case any of them may be returned.
+    pub fn find_device_by_identity<T: PartialEq>(
+        &self,
+        resolver: &IdentityResolver<T>,
+        identity: &T,
+    ) -> Result<Option<Device>, Error> {
+        self.find_device_by_identity_with_timeouts(resolver, identity, &self.timeouts)
+    }
+
+    pub fn find_device_by_identity_with_timeouts<T: PartialEq>(
+        &self,
+        resolver: &IdentityResolver<T>,
+        identity: &T,
+        timeouts: &Timeouts,
+    ) -> Result<Option<Device>, Error> {
+        self.find_device_with_timeouts(
+            |p| Ok(resolver.resolve_properties(p)? == Some(identity)),
+            timeouts,
+        )
+    }
+
+    /// Find all discovered devices that can be resolved with the resolver,
+    /// together with their identities.
+    pub fn find_resolved_devices<'a, T>(
+        &self,
+        resolver: &'a IdentityResolver<T>,
+    ) -> Result<Vec<(Device, &'a T)>, Error> {
+        self.find_resolved_devices_with_timeouts(resolver, &self.timeouts)
+    }
+
+    pub fn find_resolved_devices_with_timeouts<'a, T>(
+        &self,
+        resolver: &'a IdentityResolver<T>,
+        timeouts: &Timeouts,
+    ) -> Result<Vec<(Device, &'a T)>, Error> {
+        let mut devices = vec![];
+        self.bluez
+            .objects
+            .find_map_object(
+                |path, interfaces| {
+                    let properties = interfaces.get(Device::INTERFACE)?;
+                    // Keep the identity, so each address is only resolved once
+                    let identity = match resolver.resolve_properties(properties) {
+                        Ok(identity) => identity?,
+                        Err(e) => return Some(e),
+                    };
+                    match Device::from_path(&self.bluez, path.clone().into_static(), timeouts) {
+                        Ok(device) => {
+                            devices.push((device, identity));
+                            None
+                        }
+                        Err(e) => Some(e),
+                    }
+                },
+                timeouts.lookup,
+            )?
+            .map_or(Ok(devices), Err)
+    }
+}
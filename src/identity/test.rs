@@ -0,0 +1,32 @@
+use super::*;
+
+// Sample data from the Bluetooth Core specification, Vol 3, Part H, D.7
+const IRK: &str = "ec0234a357c8ad05341010a60a397d9b";
+
+#[test]
+fn ah_sample_data() {
+    let irk = parse_irk(IRK).unwrap();
+    assert_eq!(ah(&irk, [0x70, 0x81, 0x94]), [0x0d, 0xfb, 0xaa]);
+}
+
+#[test]
+fn resolve() {
+    let mut resolver = IdentityResolver::new();
+    resolver.add([0; 16], "other");
+    resolver.add(parse_irk(IRK).unwrap(), "phone");
+    assert_eq!(
+        resolver.resolve("70:81:94:0D:FB:AA").unwrap(),
+        Some(&"phone")
+    );
+    // Wrong hash
+    assert_eq!(resolver.resolve("70:81:94:0D:FB:AB").unwrap(), None);
+    // Not a resolvable private address
+    assert_eq!(resolver.resolve("F0:81:94:0D:FB:AA").unwrap(), None);
+    assert!(resolver.resolve("70:81:94:0D:FB").is_err());
+}
+
+#[test]
+fn invalid_irk() {
+    assert!(parse_irk("ec0234a357c8ad05341010a60a397d9").is_err());
+    assert!(parse_irk("zz0234a357c8ad05341010a60a397d9b").is_err());
+}
@@ -0,0 +1,254 @@
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, FromRawFd, IntoRawFd, RawFd};
+use std::os::unix::net::{UnixDatagram, UnixStream};
+use std::time::Duration;
+
+use crate::Error;
+
+#[cfg(test)]
+mod test;
+
+/// Size of the ATT header that is subtracted from the MTU to get the maximum
+/// payload size.
+const ATT_HEADER_LEN: usize = 3;
+
+/// Convert a file descriptor returned by `AcquireNotify` or `AcquireWrite`
+/// into a socket. BlueZ creates these sockets in non-blocking mode, so switch
+/// to blocking mode, which is a more useful default.
+fn socket_from_fd(fd: dbus::arg::OwnedFd) -> Result<UnixDatagram, Error> {
+    // Safety: the file descriptor is owned, so nothing else can close it
+    let socket = unsafe { UnixDatagram::from_raw_fd(fd.into_raw_fd()) };
+    socket.set_nonblocking(false)?;
+    Ok(socket)
+}
+
+/// Convert a file descriptor for an RFCOMM or L2CAP connection, as passed to
+/// `Profile1.NewConnection`, into a blocking stream.
+pub(crate) fn stream_from_fd(fd: dbus::arg::OwnedFd) -> Result<UnixStream, Error> {
+    // Safety: the file descriptor is owned, so nothing else can close it
+    let stream = unsafe { UnixStream::from_raw_fd(fd.into_raw_fd()) };
+    stream.set_nonblocking(false)?;
+    Ok(stream)
+}
+
+/// Create a connected pair of sockets for a local characteristic that BlueZ
+/// acquires with `AcquireWrite` or `AcquireNotify`. The first socket is kept
+/// and the second is passed to BlueZ. Both are non-blocking, which is what
+/// BlueZ expects for its end.
+pub(crate) fn socket_pair() -> Result<(dbus::arg::OwnedFd, dbus::arg::OwnedFd), Error> {
+    let mut fds = [0; 2];
+    // Safety: socketpair() only writes two file descriptors into the array
+    let ret = unsafe {
+        libc::socketpair(
+            libc::AF_UNIX,
+            libc::SOCK_SEQPACKET | libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC,
+            0,
+            fds.as_mut_ptr(),
+        )
+    };
+    if ret < 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+    // Safety: the file descriptors were just created and nothing else owns
+    // them
+    Ok(unsafe {
+        (
+            dbus::arg::OwnedFd::from_raw_fd(fds[0]),
+            dbus::arg::OwnedFd::from_raw_fd(fds[1]),
+        )
+    })
+}
+
+/// Reader for notifications received through a file descriptor acquired with
+/// `AcquireNotify`. Each item produced by the iterator is a single
+/// notification packet.
+///
+/// The reader is in blocking mode by default. To use it with `poll` or
+/// `epoll`, switch it to non-blocking mode, in which case reads will fail
+/// with `std::io::ErrorKind::WouldBlock` if no notification is available.
+///
+/// Dropping the reader closes the file descriptor, which causes BlueZ to
+/// release the acquisition and stop notifications.
+#[derive(Debug)]
+pub struct NotifyReader {
+    socket: UnixDatagram,
+    mtu: u16,
+}
+
+impl NotifyReader {
+    pub(crate) fn new(fd: dbus::arg::OwnedFd, mtu: u16) -> Result<Self, Error> {
+        // A zero sized buffer would make every read look like a hangup
+        if mtu == 0 {
+            return Err(Error::InvalidValue("MTU must not be zero".into()));
+        }
+        Ok(Self {
+            socket: socket_from_fd(fd)?,
+            mtu,
+        })
+    }
+
+    pub fn mtu(&self) -> u16 {
+        self.mtu
+    }
+
+    pub fn set_nonblocking(&self, nonblocking: bool) -> Result<(), Error> {
+        Ok(self.socket.set_nonblocking(nonblocking)?)
+    }
+
+    /// Set the timeout for blocking reads. `None` means reads will block
+    /// indefinitely.
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> Result<(), Error> {
+        Ok(self.socket.set_read_timeout(timeout)?)
+    }
+
+    /// Receive a single notification packet, which may be empty. Returns
+    /// `Ok(None)` once BlueZ has closed its end of the socket, for example
+    /// because the device disconnected.
+    pub fn recv(&self) -> Result<Option<Vec<u8>>, Error> {
+        let mut buf = vec![0; usize::from(self.mtu)];
+        let len = self.socket.recv(&mut buf)?;
+        // SOCK_SEQPACKET sockets signal hangup with a zero length read, which
+        // looks the same as an empty notification
+        if len == 0 && self.is_hung_up()? {
+            return Ok(None);
+        }
+        buf.truncate(len);
+        Ok(Some(buf))
+    }
+
+    /// Whether the peer closed its end of the socket.
+    fn is_hung_up(&self) -> Result<bool, Error> {
+        let mut fd = libc::pollfd {
+            fd: self.socket.as_raw_fd(),
+            events: libc::POLLRDHUP,
+            revents: 0,
+        };
+        // Safety: poll() only writes to the single pollfd passed to it
+        if unsafe { libc::poll(&mut fd, 1, 0) } < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(fd.revents & (libc::POLLHUP | libc::POLLRDHUP) != 0)
+    }
+}
+
+impl Iterator for NotifyReader {
+    type Item = Result<Vec<u8>, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.recv().transpose()
+    }
+}
+
+impl AsRawFd for NotifyReader {
+    fn as_raw_fd(&self) -> RawFd {
+        self.socket.as_raw_fd()
+    }
+}
+
+impl AsFd for NotifyReader {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.socket.as_fd()
+    }
+}
+
+/// Channel for writing values through a file descriptor acquired with
+/// `AcquireWrite`. Each call to `send()` results in a single write without
+/// response.
+///
+/// The channel is in blocking mode by default, so writes wait for space in
+/// the socket buffer when the device cannot keep up. In non-blocking mode,
+/// writes instead fail with `std::io::ErrorKind::WouldBlock`, and the file
+/// descriptor can be polled for writability. `send_split()` is refused in
+/// non-blocking mode.
+///
+/// Dropping the channel closes the file descriptor, which causes BlueZ to
+/// release the acquisition.
+#[derive(Debug)]
+pub struct WriteChannel {
+    socket: UnixDatagram,
+    mtu: u16,
+}
+
+impl WriteChannel {
+    pub(crate) fn new(fd: dbus::arg::OwnedFd, mtu: u16) -> Result<Self, Error> {
+        Ok(Self {
+            socket: socket_from_fd(fd)?,
+            mtu,
+        })
+    }
+
+    pub fn mtu(&self) -> u16 {
+        self.mtu
+    }
+
+    /// Maximum number of bytes that can be written in a single packet.
+    pub fn max_payload(&self) -> usize {
+        usize::from(self.mtu).saturating_sub(ATT_HEADER_LEN)
+    }
+
+    pub fn set_nonblocking(&self, nonblocking: bool) -> Result<(), Error> {
+        Ok(self.socket.set_nonblocking(nonblocking)?)
+    }
+
+    /// Set the timeout for blocking writes. `None` means writes will block
+    /// indefinitely.
+    pub fn set_write_timeout(&self, timeout: Option<Duration>) -> Result<(), Error> {
+        Ok(self.socket.set_write_timeout(timeout)?)
+    }
+
+    /// Write a single packet. Fails with `Error::PayloadTooLarge` if the
+    /// payload does not fit in one packet.
+    pub fn send(&self, buf: &[u8]) -> Result<(), Error> {
+        let max = self.max_payload();
+        if buf.len() > max {
+            return Err(Error::PayloadTooLarge {
+                len: buf.len(),
+                max,
+            });
+        }
+        self.socket.send(buf)?;
+        Ok(())
+    }
+
+    /// Write a payload of any size, splitting it into as many packets as
+    /// necessary. This is only allowed in blocking mode, since a write that
+    /// would block partway through would leave a partial payload that can't
+    /// be resumed.
+    pub fn send_split(&self, buf: &[u8]) -> Result<(), Error> {
+        if self.is_nonblocking()? {
+            return Err(Error::InvalidValue(
+                "split writes need a channel in blocking mode".into(),
+            ));
+        }
+        let max = self.max_payload();
+        if max == 0 {
+            return Err(Error::PayloadTooLarge {
+                len: buf.len(),
+                max,
+            });
+        }
+        buf.chunks(max).try_for_each(|chunk| self.send(chunk))
+    }
+
+    /// Check the file descriptor itself, as it may have been switched to
+    /// non-blocking mode through `as_raw_fd()`.
+    fn is_nonblocking(&self) -> Result<bool, Error> {
+        // Safety: F_GETFL only reads the flags of a file descriptor we own
+        let flags = unsafe { libc::fcntl(self.socket.as_raw_fd(), libc::F_GETFL) };
+        if flags < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(flags & libc::O_NONBLOCK != 0)
+    }
+}
+
+impl AsRawFd for WriteChannel {
+    fn as_raw_fd(&self) -> RawFd {
+        self.socket.as_raw_fd()
+    }
+}
+
+impl AsFd for WriteChannel {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.socket.as_fd()
+    }
+}
@@ -0,0 +1,103 @@
+use std::os::unix::io::{FromRawFd, IntoRawFd};
+use std::os::unix::net::UnixDatagram;
+
+use super::*;
+
+fn pair() -> (dbus::arg::OwnedFd, UnixDatagram) {
+    let (a, b) = UnixDatagram::pair().unwrap();
+    (
+        unsafe { dbus::arg::OwnedFd::from_raw_fd(a.into_raw_fd()) },
+        b,
+    )
+}
+
+#[test]
+fn notify_reader_packets() {
+    let (fd, peer) = pair();
+    let reader = NotifyReader::new(fd, 23).unwrap();
+    peer.send(&[1, 2, 3]).unwrap();
+    peer.send(&[4]).unwrap();
+    assert_eq!(reader.recv().unwrap(), Some(vec![1, 2, 3]));
+    assert_eq!(reader.recv().unwrap(), Some(vec![4]));
+}
+
+#[test]
+fn notify_reader_nonblocking() {
+    let (fd, _peer) = pair();
+    let reader = NotifyReader::new(fd, 23).unwrap();
+    reader.set_nonblocking(true).unwrap();
+    match reader.recv() {
+        Err(Error::Io(e)) => assert_eq!(e.kind(), std::io::ErrorKind::WouldBlock),
+        r => panic!("unexpected result: {:?}", r),
+    }
+}
+
+#[test]
+fn notify_reader_zero_mtu() {
+    let (fd, _peer) = pair();
+    match NotifyReader::new(fd, 0) {
+        Err(Error::InvalidValue(_)) => {}
+        r => panic!("unexpected result: {:?}", r),
+    }
+}
+
+#[test]
+fn write_channel_rejects_large_payload() {
+    let (fd, _peer) = pair();
+    let channel = WriteChannel::new(fd, 23).unwrap();
+    assert_eq!(channel.max_payload(), 20);
+    match channel.send(&[0; 21]) {
+        Err(Error::PayloadTooLarge { len: 21, max: 20 }) => {}
+        r => panic!("unexpected result: {:?}", r),
+    }
+}
+
+#[test]
+fn write_channel_split() {
+    let (fd, peer) = pair();
+    let channel = WriteChannel::new(fd, 23).unwrap();
+    let payload: Vec<u8> = (0..45).collect();
+    channel.send_split(&payload).unwrap();
+
+    let mut buf = [0; 64];
+    let lens: Vec<usize> = (0..3).map(|_| peer.recv(&mut buf).unwrap()).collect();
+    assert_eq!(lens, vec![20, 20, 5]);
+}
+
+#[test]
+fn write_channel_split_nonblocking() {
+    let (fd, peer) = pair();
+    let channel = WriteChannel::new(fd, 23).unwrap();
+    channel.set_nonblocking(true).unwrap();
+    match channel.send_split(&[0; 45]) {
+        Err(Error::InvalidValue(_)) => {}
+        r => panic!("unexpected result: {:?}", r),
+    }
+    // Nothing was written
+    peer.set_nonblocking(true).unwrap();
+    assert!(peer.recv(&mut [0; 64]).is_err());
+}
+
+#[test]
+fn socket_pair_hangup() {
+    let (local, remote) = socket_pair().unwrap();
+    let channel = WriteChannel::new(local, 23).unwrap();
+    let reader = NotifyReader::new(remote, 23).unwrap();
+    channel.send(&[1, 2]).unwrap();
+    assert_eq!(reader.recv().unwrap(), Some(vec![1, 2]));
+    drop(channel);
+    assert_eq!(reader.recv().unwrap(), None);
+}
+
+#[test]
+fn socket_pair_empty_notification() {
+    let (local, remote) = socket_pair().unwrap();
+    let channel = WriteChannel::new(local, 23).unwrap();
+    let mut reader = NotifyReader::new(remote, 23).unwrap();
+    channel.send(&[]).unwrap();
+    channel.send(&[1]).unwrap();
+    assert_eq!(reader.next().unwrap().unwrap(), Vec::<u8>::new());
+    assert_eq!(reader.next().unwrap().unwrap(), vec![1]);
+    drop(channel);
+    assert!(reader.next().is_none());
+}
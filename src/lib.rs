@@ -1,29 +1,85 @@
+use std::cell::{Cell, OnceCell, RefCell};
 use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::rc::Rc;
 use std::time::Duration;
 
 use gen::*;
+use log::debug;
 use thiserror::Error;
 use uuid::Uuid;
 
 use crate::dbus::{ObjectManagerCache, RefArgCast, RefArgIter};
+use crate::util::Timeout;
 
+pub mod advertising;
+mod battery;
+pub mod codec;
+mod connection;
 mod dbus;
+mod gatt_database;
+pub mod gatt_server;
+#[cfg(not(feature = "codegen"))]
+#[allow(dead_code, clippy::all)]
 mod gen;
+#[cfg(feature = "codegen")]
+#[allow(dead_code, clippy::all)]
+mod gen {
+    include!(concat!(env!("OUT_DIR"), "/gen.rs"));
+}
+#[cfg(not(feature = "codegen"))]
+#[allow(non_snake_case, clippy::all)]
+mod gen_server;
+#[cfg(feature = "codegen")]
+#[allow(non_snake_case, clippy::all)]
+mod gen_server {
+    include!(concat!(env!("OUT_DIR"), "/gen_server.rs"));
+}
+mod identity;
+mod io;
+pub mod media;
+mod network;
+pub mod profile;
+mod retry;
+mod serial;
+mod server;
+#[cfg(test)]
+mod test;
 mod util;
+mod uuids;
+
+pub use battery::{BatteryChanges, BatteryProvider, ProvidedBattery};
+pub use codec::{Characteristic, GattCodec};
+pub use connection::{ConnectFailure, DisconnectReason, Disconnected};
+pub use dbus_crossroads;
+pub use gatt_database::{
+    GattCharacteristicInfo, GattDatabase, GattDescriptorInfo, GattServiceInfo, GattValues,
+};
+pub use identity::{ah, parse_irk, IdentityResolver, Irk};
+pub use io::{NotifyReader, WriteChannel};
+pub use network::{Network, NetworkRole, NetworkServer};
+pub use retry::RetryPolicy;
+pub use serial::SerialPort;
+pub use server::ObjectServer;
+pub use uuids::{uuid16, uuid32, BluetoothUuidExt, UuidName, BLUETOOTH_BASE_UUID};
 
 pub type DBusProxy = dbus::blocking::Proxy<'static, Rc<dbus::blocking::LocalConnection>>;
 
 #[derive(Debug, Error)]
 pub enum Error {
-    #[error("{kind:?}: {cause}")]
+    #[error("{}{kind:?}: {cause}", context_prefix(.context))]
     Bluez {
         #[source]
         cause: dbus::TypedError,
         kind: ErrorKind,
+        context: Option<ErrorContext>,
+    },
+    #[error("{}{cause}", context_prefix(.context))]
+    DBus {
+        #[source]
+        cause: dbus::TypedError,
+        context: Option<ErrorContext>,
     },
-    #[error("{0}")]
-    DBus(dbus::TypedError),
     #[error("object missing interface: {0}")]
     MissingInterface(&'static str),
     #[error("missing property: {interface}.{property}")]
@@ -33,9 +89,15 @@ pub enum Error {
     },
     #[error("UUID error: {0}")]
     Uuid(#[from] uuid::Error),
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("payload too large: {len} bytes, maximum is {max}")]
+    PayloadTooLarge { len: usize, max: usize },
+    #[error("invalid value: {0}")]
+    InvalidValue(String),
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum ErrorKind {
     InvalidArguments,
     InProgress,
@@ -63,54 +125,141 @@ pub enum ErrorKind {
     OutOfRange,
     HealthError,
     NotAcquired,
+    /// An `org.bluez.Error.*` error not known to this crate, with its full
+    /// error name
+    Other(String),
+}
+
+/// The D-Bus operation that failed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ErrorContext {
+    /// Object path the operation was performed on
+    pub path: String,
+    pub interface: &'static str,
+    /// Method or property name
+    pub member: &'static str,
+}
+
+impl fmt::Display for ErrorContext {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}.{}", self.path, self.interface, self.member)
+    }
+}
+
+fn context_prefix(context: &Option<ErrorContext>) -> String {
+    context
+        .as_ref()
+        .map_or_else(String::new, |c| format!("{}: ", c))
+}
+
+impl Error {
+    /// Get the BlueZ error kind, if this is an error returned by BlueZ.
+    pub fn kind(&self) -> Option<&ErrorKind> {
+        match self {
+            Error::Bluez { kind, .. } => Some(kind),
+            _ => None,
+        }
+    }
+
+    /// Get the operation that failed, if this error came from a D-Bus call
+    /// made by this crate.
+    pub fn context(&self) -> Option<&ErrorContext> {
+        match self {
+            Error::Bluez { context, .. } | Error::DBus { context, .. } => context.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Whether the operation may succeed if retried later, for example
+    /// because another operation was still in progress or the adapter wasn't
+    /// ready yet. A call that timed out without a reply is only transient for
+    /// operations that can safely be repeated, which `RetryPolicy` takes
+    /// care of.
+    pub fn is_transient(&self) -> bool {
+        self.retry_after().is_some()
+    }
+
+    /// Whether BlueZ didn't reply within the method call timeout, in which
+    /// case the operation may or may not have taken effect.
+    pub fn is_no_reply(&self) -> bool {
+        matches!(self, Error::DBus { cause, .. } if matches!(cause.kind, dbus::ErrorKind::NoReply))
+    }
+
+    /// Suggested delay before retrying the operation, or `None` if the error
+    /// isn't transient.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Error::Bluez { kind, .. } => match kind {
+                ErrorKind::InProgress | ErrorKind::NotReady => Some(Duration::from_secs(1)),
+                ErrorKind::ConnectionAttemptFailed => Some(Duration::from_secs(2)),
+                _ => None,
+            },
+            // BlueZ didn't reply within the method call timeout
+            Error::DBus { cause, .. } => match cause.kind {
+                dbus::ErrorKind::NoReply => Some(Duration::from_millis(0)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Attach the object path and operation to an error from a D-Bus call.
+    fn with_context(mut self, path: &str, interface: &'static str, member: &'static str) -> Self {
+        if let Error::Bluez { context, .. } | Error::DBus { context, .. } = &mut self {
+            *context = Some(ErrorContext {
+                path: path.to_owned(),
+                interface,
+                member,
+            });
+        }
+        self
+    }
 }
 
 impl From<dbus::TypedError> for Error {
     fn from(cause: dbus::TypedError) -> Self {
-        match cause.kind {
-            dbus::ErrorKind::Custom => {
-                if let Some(name) = cause.cause.name() {
-                    let kind = match name {
-                        "org.bluez.Error.InvalidArguments" => ErrorKind::InvalidArguments,
-                        "org.bluez.Error.InProgress" => ErrorKind::InProgress,
-                        "org.bluez.Error.AlreadyExists" => ErrorKind::AlreadyExists,
-                        "org.bluez.Error.NotSupported" => ErrorKind::NotSupported,
-                        "org.bluez.Error.NotConnected" => ErrorKind::NotConnected,
-                        "org.bluez.Error.AlreadyConnected" => ErrorKind::AlreadyConnected,
-                        "org.bluez.Error.NotAvailable" => ErrorKind::NotAvailable,
-                        "org.bluez.Error.DoesNotExist" => ErrorKind::DoesNotExist,
-                        "org.bluez.Error.NotAuthorized" => ErrorKind::NotAuthorized,
-                        "org.bluez.Error.NotPermitted" => ErrorKind::NotPermitted,
-                        "org.bluez.Error.NoSuchAdapter" => ErrorKind::NoSuchAdapter,
-                        "org.bluez.Error.AgentNotAvailable" => ErrorKind::AgentNotAvailable,
-                        "org.bluez.Error.NotReady" => ErrorKind::NotReady,
-                        "org.bluez.Error.Failed" => ErrorKind::Failed,
-                        "org.bluez.Error.InvalidValueLength" => ErrorKind::InvalidValueLength,
-                        "org.bluez.Error.InvalidOffset" => ErrorKind::InvalidOffset,
-                        "org.bluez.Error.Rejected" => ErrorKind::Rejected,
-                        "org.bluez.Error.Canceled" => ErrorKind::Canceled,
-                        "org.bluez.Error.AuthenticationCanceled" => {
-                            ErrorKind::AuthenticationCanceled
-                        }
-                        "org.bluez.Error.AuthenticationFailed" => ErrorKind::AuthenticationFailed,
-                        "org.bluez.Error.AuthenticationRejected" => {
-                            ErrorKind::AuthenticationRejected
-                        }
-                        "org.bluez.Error.AuthenticationTimeout" => ErrorKind::AuthenticationTimeout,
-                        "org.bluez.Error.ConnectionAttemptFailed" => {
-                            ErrorKind::ConnectionAttemptFailed
-                        }
-                        "org.bluez.Error.OutOfRange" => ErrorKind::OutOfRange,
-                        "org.bluez.Error.HealthError" => ErrorKind::HealthError,
-                        "org.bluez.Error.NotAcquired" => ErrorKind::NotAcquired,
-                        _ => return Error::DBus(cause),
-                    };
-                    Error::Bluez { cause, kind }
-                } else {
-                    Error::DBus(cause)
+        let name = match (&cause.kind, cause.cause.name()) {
+            (dbus::ErrorKind::Custom, Some(name)) if name.starts_with("org.bluez.Error.") => name,
+            _ => {
+                return Error::DBus {
+                    cause,
+                    context: None,
                 }
             }
-            _ => Error::DBus(cause),
+        };
+        let kind = match name {
+            "org.bluez.Error.InvalidArguments" => ErrorKind::InvalidArguments,
+            "org.bluez.Error.InProgress" => ErrorKind::InProgress,
+            "org.bluez.Error.AlreadyExists" => ErrorKind::AlreadyExists,
+            "org.bluez.Error.NotSupported" => ErrorKind::NotSupported,
+            "org.bluez.Error.NotConnected" => ErrorKind::NotConnected,
+            "org.bluez.Error.AlreadyConnected" => ErrorKind::AlreadyConnected,
+            "org.bluez.Error.NotAvailable" => ErrorKind::NotAvailable,
+            "org.bluez.Error.DoesNotExist" => ErrorKind::DoesNotExist,
+            "org.bluez.Error.NotAuthorized" => ErrorKind::NotAuthorized,
+            "org.bluez.Error.NotPermitted" => ErrorKind::NotPermitted,
+            "org.bluez.Error.NoSuchAdapter" => ErrorKind::NoSuchAdapter,
+            "org.bluez.Error.AgentNotAvailable" => ErrorKind::AgentNotAvailable,
+            "org.bluez.Error.NotReady" => ErrorKind::NotReady,
+            "org.bluez.Error.Failed" => ErrorKind::Failed,
+            "org.bluez.Error.InvalidValueLength" => ErrorKind::InvalidValueLength,
+            "org.bluez.Error.InvalidOffset" => ErrorKind::InvalidOffset,
+            "org.bluez.Error.Rejected" => ErrorKind::Rejected,
+            "org.bluez.Error.Canceled" => ErrorKind::Canceled,
+            "org.bluez.Error.AuthenticationCanceled" => ErrorKind::AuthenticationCanceled,
+            "org.bluez.Error.AuthenticationFailed" => ErrorKind::AuthenticationFailed,
+            "org.bluez.Error.AuthenticationRejected" => ErrorKind::AuthenticationRejected,
+            "org.bluez.Error.AuthenticationTimeout" => ErrorKind::AuthenticationTimeout,
+            "org.bluez.Error.ConnectionAttemptFailed" => ErrorKind::ConnectionAttemptFailed,
+            "org.bluez.Error.OutOfRange" => ErrorKind::OutOfRange,
+            "org.bluez.Error.HealthError" => ErrorKind::HealthError,
+            "org.bluez.Error.NotAcquired" => ErrorKind::NotAcquired,
+            name => ErrorKind::Other(name.to_owned()),
+        };
+        Error::Bluez {
+            cause,
+            kind,
+            context: None,
         }
     }
 }
@@ -121,15 +270,95 @@ impl From<dbus::Error> for Error {
     }
 }
 
+trait ResultExt<T> {
+    /// Record which method or property of the proxy's object failed.
+    fn context(
+        self,
+        proxy: &DBusProxy,
+        interface: &'static str,
+        member: &'static str,
+    ) -> Result<T, Error>;
+}
+
+impl<T, E: Into<Error>> ResultExt<T> for Result<T, E> {
+    fn context(
+        self,
+        proxy: &DBusProxy,
+        interface: &'static str,
+        member: &'static str,
+    ) -> Result<T, Error> {
+        self.map_err(|e| e.into().with_context(&proxy.path, interface, member))
+    }
+}
+
+/// Get a property from a property map, returning an error if it is missing.
+fn get_property<'a, T: RefArgCast<'a>>(
+    properties: &'a dbus::arg::PropMap,
+    interface: &'static str,
+    property: &'static str,
+) -> Result<T, Error> {
+    get_optional_property(properties, property)?.ok_or(Error::MissingProperty {
+        interface,
+        property,
+    })
+}
+
+/// Get a property from a property map, returning `Ok(None)` if it is missing.
+fn get_optional_property<'a, T: RefArgCast<'a>>(
+    properties: &'a dbus::arg::PropMap,
+    property: &str,
+) -> Result<Option<T>, Error> {
+    properties
+        .get(property)
+        .map(|v| Ok(T::ref_arg_cast(&*v.0)?))
+        .transpose()
+}
+
+/// Default timeouts for BlueZ operations. Objects inherit the timeouts of the
+/// object they were found through, so setting them on `Bluez` applies to all
+/// adapters, devices, services and characteristics. Methods that wait for
+/// objects to appear also have a `_with_timeouts` variant to override them for
+/// a single call.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Timeouts {
+    /// Timeout for D-Bus method calls and property accesses
+    pub method_call: Duration,
+    /// How long to wait for an object to appear, e.g. a device that hasn't
+    /// been discovered yet
+    pub lookup: Duration,
+    /// Timeout for `Device::connect()`, which includes resolving services
+    pub connect: Duration,
+    /// Timeout for `Device::pair()`, which may include user interaction
+    pub pair: Duration,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self {
+            method_call: Duration::from_secs(25),
+            lookup: Duration::from_secs(5),
+            connect: Duration::from_secs(30),
+            pair: Duration::from_secs(60),
+        }
+    }
+}
+
 pub struct Bluez {
     connection: Rc<dbus::blocking::LocalConnection>,
     objects: ObjectManagerCache<'static, Rc<dbus::blocking::LocalConnection>>,
+    timeouts: Cell<Timeouts>,
+    retry_policy: RefCell<RetryPolicy>,
+    server: OnceCell<ObjectServer>,
 }
 
 impl Bluez {
     const BUS_NAME: &'static str = "org.bluez";
 
-    pub fn new(timeout: Duration) -> Result<Self, Error> {
+    pub fn new() -> Result<Self, Error> {
+        Self::with_timeouts(Timeouts::default())
+    }
+
+    pub fn with_timeouts(timeouts: Timeouts) -> Result<Self, Error> {
         let connection = Rc::new(dbus::blocking::LocalConnection::new_system()?);
 
         let bus_name = dbus::strings::BusName::from(Self::BUS_NAME);
@@ -141,11 +370,84 @@ impl Bluez {
                 connection,
                 destination: bus_name,
                 path: root_path,
-                timeout,
+                timeout: timeouts.method_call,
             })?,
+            timeouts: Cell::new(timeouts),
+            retry_policy: RefCell::new(RetryPolicy::none()),
+            server: OnceCell::new(),
         })
     }
 
+    pub fn timeouts(&self) -> Timeouts {
+        self.timeouts.get()
+    }
+
+    /// Set the default timeouts for objects created from this connection.
+    /// Objects that already exist keep their timeouts.
+    pub fn set_timeouts(&self, timeouts: Timeouts) {
+        self.timeouts.set(timeouts);
+    }
+
+    /// Get the policy used to retry operations such as `Device::connect()`
+    /// that fail with a transient error. Operations aren't retried unless a
+    /// policy is set with `set_retry_policy()`.
+    pub fn retry_policy(&self) -> RetryPolicy {
+        self.retry_policy.borrow().clone()
+    }
+
+    /// Set the retry policy for all objects created from this connection,
+    /// e.g. `RetryPolicy::attempts(3)`.
+    pub fn set_retry_policy(&self, policy: RetryPolicy) {
+        self.retry_policy.replace(policy);
+    }
+
+    /// Process a single incoming message, waiting up to `timeout` for one to
+    /// arrive. Returns `Ok(false)` if the timeout expired.
+    ///
+    /// While objects such as a GATT application are registered with BlueZ,
+    /// this must be called in a loop so that calls from BlueZ are answered.
+    /// Calls that may take long, such as `Device::connect()` or registering
+    /// exported objects, also process incoming messages while they wait.
+    pub fn process(&self, timeout: Duration) -> Result<bool, Error> {
+        Ok(self.connection.process(timeout)?)
+    }
+
+    /// Process messages until `duration` has passed.
+    pub(crate) fn wait(&self, duration: Duration) -> Result<(), Error> {
+        let timeout = Timeout::start(duration);
+        while timeout.get() != Duration::from_millis(0) {
+            self.process(timeout.get())?;
+        }
+        Ok(())
+    }
+
+    /// The server for objects exported to BlueZ, which is created on first
+    /// use so that method calls aren't handled unless something is exported.
+    pub fn object_server(&self) -> &ObjectServer {
+        self.server
+            .get_or_init(|| server::ObjectServer::new(self.connection.clone()))
+    }
+
+    /// Call a method that may take long, such as `Device1.Connect`. If
+    /// objects are exported, calls to them keep being answered while waiting,
+    /// see `ObjectServer::call()`. Otherwise this is a normal blocking call.
+    pub(crate) fn call_method<A, R>(
+        &self,
+        proxy: &DBusProxy,
+        interface: &str,
+        member: &str,
+        args: A,
+    ) -> Result<R, dbus::Error>
+    where
+        A: dbus::arg::AppendAll,
+        R: dbus::arg::ReadAll,
+    {
+        match self.server.get() {
+            Some(server) => server.call(proxy, interface, member, args),
+            None => proxy.method_call(interface, member, args),
+        }
+    }
+
     fn with_proxy(
         &self,
         path: impl Into<dbus::strings::Path<'static>>,
@@ -193,20 +495,25 @@ impl Bluez {
         )
     }
 
-    pub fn get_first_adapter(
+    pub fn get_first_adapter(self: Rc<Self>) -> Result<Option<Adapter>, Error> {
+        let timeouts = self.timeouts();
+        self.get_first_adapter_with_timeouts(&timeouts)
+    }
+
+    pub fn get_first_adapter_with_timeouts(
         self: Rc<Self>,
-        adapter_timeout: Duration,
-        timeout: Duration,
+        timeouts: &Timeouts,
     ) -> Result<Option<Adapter>, Error> {
         self.find_map_interface_object(
             Adapter::INTERFACE,
             |object, _| {
-                Ok(Some(Adapter::new(
-                    self.clone(),
-                    self.with_proxy(object.clone().into_static(), adapter_timeout),
+                Ok(Some(Adapter::from_path(
+                    &self,
+                    object.clone().into_static(),
+                    timeouts,
                 )))
             },
-            timeout,
+            timeouts.lookup,
         )
     }
 }
@@ -218,14 +525,13 @@ pub enum DiscoveryTransport {
     Le,
 }
 
-impl ToString for DiscoveryTransport {
-    fn to_string(&self) -> String {
-        match self {
+impl fmt::Display for DiscoveryTransport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
             Self::Auto => "auto",
             Self::BrEdr => "bredr",
             Self::Le => "le",
-        }
-        .into()
+        })
     }
 }
 
@@ -257,21 +563,46 @@ impl Default for DiscoveryFilter {
 pub struct Adapter {
     bluez: Rc<Bluez>,
     adapter: DBusProxy,
+    timeouts: Timeouts,
 }
 
 impl Adapter {
     const INTERFACE: &'static str = "org.bluez.Adapter1";
 
     pub fn new(bluez: Rc<Bluez>, adapter: DBusProxy) -> Self {
-        Self { bluez, adapter }
+        let timeouts = Timeouts {
+            method_call: adapter.timeout,
+            ..bluez.timeouts()
+        };
+        Self {
+            bluez,
+            adapter,
+            timeouts,
+        }
+    }
+
+    fn from_path(bluez: &Rc<Bluez>, path: dbus::Path<'static>, timeouts: &Timeouts) -> Self {
+        Self {
+            bluez: bluez.clone(),
+            adapter: bluez.with_proxy(path, timeouts.method_call),
+            timeouts: *timeouts,
+        }
+    }
+
+    pub fn timeouts(&self) -> Timeouts {
+        self.timeouts
     }
 
     pub fn start_discovery(&self) -> Result<(), Error> {
-        Ok(self.adapter.start_discovery()?)
+        self.adapter
+            .start_discovery()
+            .context(&self.adapter, Self::INTERFACE, "StartDiscovery")
     }
 
     pub fn stop_discovery(&self) -> Result<(), Error> {
-        Ok(self.adapter.stop_discovery()?)
+        self.adapter
+            .stop_discovery()
+            .context(&self.adapter, Self::INTERFACE, "StopDiscovery")
     }
 
     pub fn set_discovery_filter(&self, filter: DiscoveryFilter) -> Result<(), Error> {
@@ -306,37 +637,51 @@ impl Adapter {
             "Pattern".into(),
             dbus::arg::Variant(Box::new(filter.pattern)),
         );
-        Ok(self.adapter.set_discovery_filter(properties)?)
+        self.adapter.set_discovery_filter(properties).context(
+            &self.adapter,
+            Self::INTERFACE,
+            "SetDiscoveryFilter",
+        )
     }
 
     pub fn powered(&self) -> Result<bool, Error> {
-        Ok(self.adapter.powered()?)
+        self.adapter
+            .powered()
+            .context(&self.adapter, Self::INTERFACE, "Powered")
     }
 
     pub fn set_powered(&self, on: bool) -> Result<(), Error> {
-        Ok(self.adapter.set_powered(on)?)
+        self.adapter
+            .set_powered(on)
+            .context(&self.adapter, Self::INTERFACE, "Powered")
     }
 
     pub fn find_device(
         &self,
         f: impl Fn(&dbus::arg::PropMap) -> Result<bool, Error>,
-        device_timeout: Duration,
-        timeout: Duration,
+    ) -> Result<Option<Device>, Error> {
+        self.find_device_with_timeouts(f, &self.timeouts)
+    }
+
+    pub fn find_device_with_timeouts(
+        &self,
+        f: impl Fn(&dbus::arg::PropMap) -> Result<bool, Error>,
+        timeouts: &Timeouts,
     ) -> Result<Option<Device>, Error> {
         self.bluez.find_map_interface_object(
             Device::INTERFACE,
             |object, interface| {
                 Ok(if f(interface)? {
-                    Some(Device::new(
-                        self.bluez.clone(),
-                        self.bluez
-                            .with_proxy(object.clone().into_static(), device_timeout),
+                    Some(Device::from_path(
+                        &self.bluez,
+                        object.clone().into_static(),
+                        timeouts,
                     )?)
                 } else {
                     None
                 })
             },
-            timeout,
+            timeouts.lookup,
         )
     }
 
@@ -346,8 +691,14 @@ impl Adapter {
     pub fn find_devices(
         &self,
         f: impl Fn(&dbus::arg::PropMap) -> Result<bool, Error>,
-        device_timeout: Duration,
-        timeout: Duration,
+    ) -> Result<Vec<Device>, Error> {
+        self.find_devices_with_timeouts(f, &self.timeouts)
+    }
+
+    pub fn find_devices_with_timeouts(
+        &self,
+        f: impl Fn(&dbus::arg::PropMap) -> Result<bool, Error>,
+        timeouts: &Timeouts,
     ) -> Result<Vec<Device>, Error> {
         let mut devices = vec![];
         self.bluez
@@ -363,36 +714,39 @@ impl Adapter {
                         })
                         .and_then(|r| {
                             r.and_then(|_| {
-                                devices.push(Device::new(
-                                    self.bluez.clone(),
-                                    self.bluez
-                                        .with_proxy(path.clone().into_static(), device_timeout),
+                                devices.push(Device::from_path(
+                                    &self.bluez,
+                                    path.clone().into_static(),
+                                    timeouts,
                                 )?);
                                 Ok(())
                             })
                             .err()
                         })
                 },
-                timeout,
+                timeouts.lookup,
             )?
             .map_or(Ok(devices), Err)
     }
 
-    pub fn get_devices(
-        &self,
-        device_timeout: Duration,
-        timeout: Duration,
-    ) -> Result<Vec<Device>, Error> {
-        self.find_devices(|_| Ok(true), device_timeout, timeout)
+    pub fn get_devices(&self) -> Result<Vec<Device>, Error> {
+        self.get_devices_with_timeouts(&self.timeouts)
+    }
+
+    pub fn get_devices_with_timeouts(&self, timeouts: &Timeouts) -> Result<Vec<Device>, Error> {
+        self.find_devices_with_timeouts(|_| Ok(true), timeouts)
+    }
+
+    pub fn find_device_by_address(&self, address: &str) -> Result<Option<Device>, Error> {
+        self.find_device_by_address_with_timeouts(address, &self.timeouts)
     }
 
-    pub fn find_device_by_address(
+    pub fn find_device_by_address_with_timeouts(
         &self,
         address: &str,
-        device_timeout: Duration,
-        timeout: Duration,
+        timeouts: &Timeouts,
     ) -> Result<Option<Device>, Error> {
-        self.find_device(
+        self.find_device_with_timeouts(
             |p| {
                 p.get("Address")
                     .ok_or(Error::MissingProperty {
@@ -402,18 +756,23 @@ impl Adapter {
                     .and_then(|a| Ok(<&str>::ref_arg_cast(a)?))
                     .map(|a| a == address)
             },
-            device_timeout,
-            timeout,
+            timeouts,
         )
     }
 
     pub fn find_devices_by_uuids(
         &self,
         f: impl Fn(HashSet<Uuid>) -> bool,
-        device_timeout: Duration,
-        timeout: Duration,
     ) -> Result<Vec<Device>, Error> {
-        self.find_devices(
+        self.find_devices_by_uuids_with_timeouts(f, &self.timeouts)
+    }
+
+    pub fn find_devices_by_uuids_with_timeouts(
+        &self,
+        f: impl Fn(HashSet<Uuid>) -> bool,
+        timeouts: &Timeouts,
+    ) -> Result<Vec<Device>, Error> {
+        self.find_devices_with_timeouts(
             |p| {
                 p.get("UUIDs")
                     .map(|u| -> Result<RefArgIter<&str>, Error> {
@@ -427,36 +786,45 @@ impl Adapter {
                         .map(&f)
                     })
             },
-            device_timeout,
-            timeout,
+            timeouts,
         )
     }
 
-    pub fn find_devices_with_uuid(
+    pub fn find_devices_with_uuid(&self, uuid: &Uuid) -> Result<Vec<Device>, Error> {
+        self.find_devices_with_uuid_with_timeouts(uuid, &self.timeouts)
+    }
+
+    pub fn find_devices_with_uuid_with_timeouts(
         &self,
         uuid: &Uuid,
-        device_timeout: Duration,
-        timeout: Duration,
+        timeouts: &Timeouts,
     ) -> Result<Vec<Device>, Error> {
-        self.find_devices_by_uuids(|uuids| uuids.contains(uuid), device_timeout, timeout)
+        debug!("Finding devices with UUID {}", uuid.display_name());
+        self.find_devices_by_uuids_with_timeouts(|uuids| uuids.contains(uuid), timeouts)
     }
 
-    pub fn find_devices_with_uuids(
+    pub fn find_devices_with_uuids(&self, uuids: &HashSet<Uuid>) -> Result<Vec<Device>, Error> {
+        self.find_devices_with_uuids_with_timeouts(uuids, &self.timeouts)
+    }
+
+    pub fn find_devices_with_uuids_with_timeouts(
         &self,
         uuids: &HashSet<Uuid>,
-        device_timeout: Duration,
-        timeout: Duration,
+        timeouts: &Timeouts,
     ) -> Result<Vec<Device>, Error> {
-        self.find_devices_by_uuids(|u| !u.is_disjoint(uuids), device_timeout, timeout)
+        self.find_devices_by_uuids_with_timeouts(|u| !u.is_disjoint(uuids), timeouts)
     }
 
-    pub fn find_devices_with_all_uuids(
+    pub fn find_devices_with_all_uuids(&self, uuids: &HashSet<Uuid>) -> Result<Vec<Device>, Error> {
+        self.find_devices_with_all_uuids_with_timeouts(uuids, &self.timeouts)
+    }
+
+    pub fn find_devices_with_all_uuids_with_timeouts(
         &self,
         uuids: &HashSet<Uuid>,
-        device_timeout: Duration,
-        timeout: Duration,
+        timeouts: &Timeouts,
     ) -> Result<Vec<Device>, Error> {
-        self.find_devices_by_uuids(|u| u.is_superset(uuids), device_timeout, timeout)
+        self.find_devices_by_uuids_with_timeouts(|u| u.is_superset(uuids), timeouts)
     }
 }
 
@@ -464,26 +832,87 @@ pub struct Device {
     bluez: Rc<Bluez>,
     device: DBusProxy,
     properties: dbus::PropertyCache<'static, Rc<dbus::blocking::LocalConnection>>,
+    timeouts: Timeouts,
+    disconnected: Rc<RefCell<Option<Disconnected>>>,
+    /// Match for `Device1.Disconnected`, only added once needed so that
+    /// handles that never wait for a disconnection don't add a match rule
+    disconnected_token: Cell<Option<dbus::channel::Token>>,
 }
 
 impl Device {
     const INTERFACE: &'static str = "org.bluez.Device1";
 
     pub fn new(bluez: Rc<Bluez>, device: DBusProxy) -> Result<Self, Error> {
+        let timeouts = Timeouts {
+            method_call: device.timeout,
+            ..bluez.timeouts()
+        };
         let properties = dbus::PropertyCache::new(device.clone())?;
         Ok(Self {
             bluez,
             device,
             properties,
+            timeouts,
+            disconnected: Rc::new(RefCell::new(None)),
+            disconnected_token: Cell::new(None),
         })
     }
 
+    fn from_path(
+        bluez: &Rc<Bluez>,
+        path: dbus::Path<'static>,
+        timeouts: &Timeouts,
+    ) -> Result<Self, Error> {
+        let mut device = Self::new(bluez.clone(), bluez.with_proxy(path, timeouts.method_call))?;
+        device.timeouts = *timeouts;
+        Ok(device)
+    }
+
+    pub fn timeouts(&self) -> Timeouts {
+        self.timeouts
+    }
+
+    /// Proxy for method calls that take longer than usual.
+    fn proxy_with_timeout(&self, timeout: Duration) -> DBusProxy {
+        DBusProxy {
+            timeout,
+            ..self.device.clone()
+        }
+    }
+
+    /// Connect to the device, retrying transient errors according to the
+    /// retry policy of the `Bluez` connection.
     pub fn connect(&self) -> Result<(), Error> {
-        Ok(self.device.connect()?)
+        self.connect_with_retry(&self.bluez.retry_policy())
+    }
+
+    pub fn connect_with_retry(&self, policy: &RetryPolicy) -> Result<(), Error> {
+        self.watch_disconnected()?;
+        let proxy = self.proxy_with_timeout(self.timeouts.connect);
+        policy.retry_on(Some(&self.bluez), false, || {
+            self.bluez
+                .call_method(&proxy, Self::INTERFACE, "Connect", ())
+                .context(&proxy, Self::INTERFACE, "Connect")
+        })
+    }
+
+    /// Pair with the device, retrying transient errors according to the retry
+    /// policy of the `Bluez` connection.
+    pub fn pair(&self) -> Result<(), Error> {
+        self.pair_with_retry(&self.bluez.retry_policy())
+    }
+
+    pub fn pair_with_retry(&self, policy: &RetryPolicy) -> Result<(), Error> {
+        let proxy = self.proxy_with_timeout(self.timeouts.pair);
+        policy.retry_on(Some(&self.bluez), false, || {
+            self.bluez
+                .call_method(&proxy, Self::INTERFACE, "Pair", ())
+                .context(&proxy, Self::INTERFACE, "Pair")
+        })
     }
 
     pub fn disconnect(&self) -> Result<(), Error> {
-        Ok(self.device.disconnect()?)
+        Device1::disconnect(&self.device).context(&self.device, Self::INTERFACE, "Disconnect")
     }
 
     pub fn name(&self) -> Result<String, Error> {
@@ -499,18 +928,21 @@ impl Device {
     }
 
     pub fn uuids(&self) -> Result<HashSet<Uuid>, Error> {
-        Ok(Device1::uuids(&self.device)?
+        Ok(Device1::uuids(&self.device)
+            .context(&self.device, Self::INTERFACE, "UUIDs")?
             .into_iter()
             .map(|u| Uuid::parse_str(&u))
             .collect::<Result<_, _>>()?)
     }
 
     pub fn address(&self) -> Result<String, Error> {
-        Ok(Device1::address(&self.device)?)
+        Device1::address(&self.device).context(&self.device, Self::INTERFACE, "Address")
     }
 
     pub fn paired(&self) -> Result<bool, Error> {
-        Ok(self.device.paired()?)
+        self.device
+            .paired()
+            .context(&self.device, Self::INTERFACE, "Paired")
     }
 
     /// Get the service data from the most recent advertisement. If no service
@@ -532,31 +964,69 @@ impl Device {
             .unwrap_or_default())
     }
 
+    /// Get the manufacturer data from the most recent advertisement, keyed by
+    /// company identifier. If no manufacturer data is available from BlueZ, an
+    /// empty map will be returned.
+    pub fn manufacturer_data(&self) -> Result<HashMap<u16, Vec<u8>>, Error> {
+        Ok(self
+            .properties
+            .get(Self::INTERFACE, "ManufacturerData")?
+            .map(|ref_arg| <HashMap<u16, Vec<u8>>>::ref_arg_cast(ref_arg.as_ref()))
+            .transpose()?
+            .unwrap_or_default())
+    }
+
     pub fn rssi(&self) -> Result<i16, Error> {
-        Ok(self.device.rssi()?)
+        self.device
+            .rssi()
+            .context(&self.device, Self::INTERFACE, "RSSI")
     }
 
     /// Get the battery interface for this device. If the battery interface is
-    /// not available, this method will wait up to the timeout for it to appear.
-    pub fn battery(
-        &self,
-        battery_timeout: Duration,
-        timeout: Duration,
-    ) -> Result<Option<Battery>, Error> {
-        self.bluez.find_map_object(
-            |object, interfaces| {
-                Ok(
-                    (object == &self.device.path && interfaces.contains_key(Battery::INTERFACE))
+    /// not available, this method will wait up to the lookup timeout for it to
+    /// appear, unless the services of the device have already been resolved,
+    /// in which case `Ok(None)` is returned right away.
+    pub fn battery(&self) -> Result<Option<Battery>, Error> {
+        self.battery_with_timeouts(&self.timeouts)
+    }
+
+    pub fn battery_with_timeouts(&self, timeouts: &Timeouts) -> Result<Option<Battery>, Error> {
+        let timeout = Timeout::start(timeouts.lookup);
+        loop {
+            let battery =
+                self.bluez.find_map_object(
+                    |object, interfaces| {
+                        Ok((object == &self.device.path
+                            && interfaces.contains_key(Battery::INTERFACE))
                         .then(|| {
                             Battery::new(
                                 self.bluez
-                                    .with_proxy(object.clone().into_static(), battery_timeout),
+                                    .with_proxy(object.clone().into_static(), timeouts.method_call),
                             )
-                        }),
-                )
-            },
-            timeout,
-        )
+                        }))
+                    },
+                    Duration::from_millis(0),
+                )?;
+            if battery.is_some() {
+                return Ok(battery);
+            }
+            // BlueZ sets up the battery while resolving services, so a device
+            // without one by then doesn't have one
+            if self.services_resolved()? {
+                return Ok(None);
+            }
+            if timeout.get() == Duration::from_millis(0) || !self.bluez.process(timeout.get())? {
+                return Ok(None);
+            }
+        }
+    }
+
+    /// Whether BlueZ has finished discovering the GATT services of the
+    /// device since it connected.
+    pub fn services_resolved(&self) -> Result<bool, Error> {
+        self.device
+            .services_resolved()
+            .context(&self.device, Self::INTERFACE, "ServicesResolved")
     }
 
     /// Block until the a device property changes, or the timeout expires.
@@ -569,8 +1039,14 @@ impl Device {
     pub fn find_service(
         &self,
         f: impl Fn(&dbus::arg::PropMap) -> Result<bool, Error>,
-        service_timeout: Duration,
-        timeout: Duration,
+    ) -> Result<Option<GattService>, Error> {
+        self.find_service_with_timeouts(f, &self.timeouts)
+    }
+
+    pub fn find_service_with_timeouts(
+        &self,
+        f: impl Fn(&dbus::arg::PropMap) -> Result<bool, Error>,
+        timeouts: &Timeouts,
     ) -> Result<Option<GattService>, Error> {
         self.bluez.find_map_interface_object(
             GattService::INTERFACE,
@@ -586,26 +1062,34 @@ impl Device {
                     .map(|device| device == self.device.path)?
                     && f(interface)?
                 {
-                    Ok(Some(GattService::new(
-                        self.bluez.clone(),
-                        self.bluez
-                            .with_proxy(object.clone().into_static(), service_timeout),
+                    Ok(Some(GattService::from_path(
+                        &self.bluez,
+                        object.clone().into_static(),
+                        timeouts,
                     )))
                 } else {
                     Ok(None)
                 }
             },
-            timeout,
+            timeouts.lookup,
         )
     }
 
-    pub fn find_service_by_uuid(
+    pub fn find_service_by_uuid(&self, uuid: &Uuid) -> Result<Option<GattService>, Error> {
+        self.find_service_by_uuid_with_timeouts(uuid, &self.timeouts)
+    }
+
+    pub fn find_service_by_uuid_with_timeouts(
         &self,
         uuid: &Uuid,
-        service_timeout: Duration,
-        timeout: Duration,
+        timeouts: &Timeouts,
     ) -> Result<Option<GattService>, Error> {
-        self.find_service(
+        debug!(
+            "Finding service {} of device {}",
+            uuid.display_name(),
+            self.device.path
+        );
+        self.find_service_with_timeouts(
             |p| {
                 p.get("UUID")
                     .ok_or(Error::MissingProperty {
@@ -616,29 +1100,121 @@ impl Device {
                     .and_then(|u| Ok(Uuid::parse_str(u)?))
                     .map(|u| u == *uuid)
             },
-            service_timeout,
-            timeout,
+            timeouts,
         )
     }
 }
 
+impl Drop for Device {
+    fn drop(&mut self) {
+        if let Some(token) = self.disconnected_token.get() {
+            self.device.match_stop(token, true).ok();
+        }
+    }
+}
+
 pub struct GattService {
     bluez: Rc<Bluez>,
     service: DBusProxy,
+    timeouts: Timeouts,
 }
 
 impl GattService {
     const INTERFACE: &'static str = "org.bluez.GattService1";
 
     pub fn new(bluez: Rc<Bluez>, service: DBusProxy) -> Self {
-        Self { bluez, service }
+        let timeouts = Timeouts {
+            method_call: service.timeout,
+            ..bluez.timeouts()
+        };
+        Self {
+            bluez,
+            service,
+            timeouts,
+        }
+    }
+
+    fn from_path(bluez: &Rc<Bluez>, path: dbus::Path<'static>, timeouts: &Timeouts) -> Self {
+        Self {
+            bluez: bluez.clone(),
+            service: bluez.with_proxy(path, timeouts.method_call),
+            timeouts: *timeouts,
+        }
+    }
+
+    pub fn timeouts(&self) -> Timeouts {
+        self.timeouts
+    }
+
+    pub fn uuid(&self) -> Result<Uuid, Error> {
+        let uuid =
+            GattService1::uuid(&self.service).context(&self.service, Self::INTERFACE, "UUID")?;
+        Ok(Uuid::parse_str(&uuid)?)
+    }
+
+    pub fn is_primary(&self) -> Result<bool, Error> {
+        self.service
+            .primary()
+            .context(&self.service, Self::INTERFACE, "Primary")
+    }
+
+    /// Get the device this service belongs to.
+    pub fn device(&self) -> Result<Device, Error> {
+        let path = GattService1::device(&self.service).context(
+            &self.service,
+            Self::INTERFACE,
+            "Device",
+        )?;
+        Device::from_path(&self.bluez, path, &self.timeouts)
+    }
+
+    /// Get the services included by this service. Included services are often
+    /// secondary services, which can only be reached this way.
+    pub fn includes(&self) -> Result<Vec<GattService>, Error> {
+        Ok(self
+            .service
+            .includes()
+            .context(&self.service, Self::INTERFACE, "Includes")?
+            .into_iter()
+            .map(|path| GattService::from_path(&self.bluez, path, &self.timeouts))
+            .collect())
+    }
+
+    /// List all characteristics of this service that are currently known to
+    /// BlueZ. Unlike `find_characteristic()`, this doesn't wait for
+    /// characteristics to appear, so it should only be called after services
+    /// have been resolved.
+    pub fn characteristics(&self) -> Result<Vec<GattCharacteristic>, Error> {
+        let mut paths = vec![];
+        let mut result = Ok(());
+        self.bluez.objects.for_each_object(|object, interfaces| {
+            if let Some(p) = interfaces.get(GattCharacteristic::INTERFACE) {
+                match get_property::<&str>(p, GattCharacteristic::INTERFACE, "Service") {
+                    Ok(s) if self.service.path == s => paths.push(object.clone().into_static()),
+                    Ok(_) => {}
+                    Err(e) => result = Err(e),
+                }
+            }
+        })?;
+        result?;
+        paths.sort();
+        Ok(paths
+            .into_iter()
+            .map(|path| GattCharacteristic::from_path(&self.bluez, path, &self.timeouts))
+            .collect())
     }
 
     pub fn find_characteristic(
         &self,
         f: impl Fn(&dbus::arg::PropMap) -> Result<bool, Error>,
-        characteristic_timeout: Duration,
-        timeout: Duration,
+    ) -> Result<Option<GattCharacteristic>, Error> {
+        self.find_characteristic_with_timeouts(f, &self.timeouts)
+    }
+
+    pub fn find_characteristic_with_timeouts(
+        &self,
+        f: impl Fn(&dbus::arg::PropMap) -> Result<bool, Error>,
+        timeouts: &Timeouts,
     ) -> Result<Option<GattCharacteristic>, Error> {
         self.bluez.find_map_interface_object(
             GattCharacteristic::INTERFACE,
@@ -650,28 +1226,40 @@ impl GattService {
                         property: "Service",
                     })
                     .and_then(|s| Ok(<&str>::ref_arg_cast(s)?))
-                    .map(|s| dbus::Path::from(s) == self.service.path)?
+                    .map(|s| self.service.path == s)?
                     && f(interface)?
                 {
-                    Ok(Some(GattCharacteristic::new(self.bluez.with_proxy(
+                    Ok(Some(GattCharacteristic::from_path(
+                        &self.bluez,
                         object.clone().into_static(),
-                        characteristic_timeout,
-                    ))))
+                        timeouts,
+                    )))
                 } else {
                     Ok(None)
                 }
             },
-            timeout,
+            timeouts.lookup,
         )
     }
 
     pub fn find_characteristic_by_uuid(
         &self,
         uuid: &Uuid,
-        characteristic_timeout: Duration,
-        timeout: Duration,
     ) -> Result<Option<GattCharacteristic>, Error> {
-        self.find_characteristic(
+        self.find_characteristic_by_uuid_with_timeouts(uuid, &self.timeouts)
+    }
+
+    pub fn find_characteristic_by_uuid_with_timeouts(
+        &self,
+        uuid: &Uuid,
+        timeouts: &Timeouts,
+    ) -> Result<Option<GattCharacteristic>, Error> {
+        debug!(
+            "Finding characteristic {} of service {}",
+            uuid.display_name(),
+            self.service.path
+        );
+        self.find_characteristic_with_timeouts(
             |p| {
                 p.get("UUID")
                     .ok_or(Error::MissingProperty {
@@ -682,52 +1270,131 @@ impl GattService {
                     .and_then(|u| Ok(Uuid::parse_str(u)?))
                     .map(|u| u == *uuid)
             },
-            characteristic_timeout,
-            timeout,
+            timeouts,
         )
     }
 }
 
 pub struct GattCharacteristic {
+    /// Connection the characteristic was found through, which provides the
+    /// retry policy. `None` for characteristics created with `new()`.
+    bluez: Option<Rc<Bluez>>,
     characteristic: DBusProxy,
+    timeouts: Timeouts,
 }
 
 impl GattCharacteristic {
     const INTERFACE: &'static str = "org.bluez.GattCharacteristic1";
 
     pub fn new(characteristic: DBusProxy) -> Self {
-        Self { characteristic }
+        let timeouts = Timeouts {
+            method_call: characteristic.timeout,
+            ..Timeouts::default()
+        };
+        Self {
+            bluez: None,
+            characteristic,
+            timeouts,
+        }
     }
 
-    pub fn acquire_notify(&self) -> Result<(dbus::arg::OwnedFd, u16), Error> {
-        Ok(self.characteristic.acquire_notify(HashMap::new())?)
+    fn from_path(bluez: &Rc<Bluez>, path: dbus::Path<'static>, timeouts: &Timeouts) -> Self {
+        Self {
+            bluez: Some(bluez.clone()),
+            characteristic: bluez.with_proxy(path, timeouts.method_call),
+            timeouts: *timeouts,
+        }
     }
 
-    pub fn acquire_write(&self) -> Result<(dbus::arg::OwnedFd, u16), Error> {
-        Ok(self.characteristic.acquire_write(HashMap::new())?)
+    pub fn timeouts(&self) -> Timeouts {
+        self.timeouts
     }
 
-    pub fn read_value(&self) -> Result<Vec<u8>, Error> {
-        Ok(GattCharacteristic1::read_value(
+    fn retry_policy(&self) -> RetryPolicy {
+        self.bluez
+            .as_ref()
+            .map_or_else(RetryPolicy::none, |b| b.retry_policy())
+    }
+
+    /// Acquire a file descriptor for receiving notifications, which is much
+    /// more efficient than receiving them through `PropertiesChanged` signals.
+    pub fn acquire_notify(&self) -> Result<NotifyReader, Error> {
+        let (fd, mtu) = self.characteristic.acquire_notify(HashMap::new()).context(
             &self.characteristic,
-            HashMap::new(),
-        )?)
+            Self::INTERFACE,
+            "AcquireNotify",
+        )?;
+        NotifyReader::new(fd, mtu)
+    }
+
+    /// Acquire a file descriptor for writing values without response.
+    pub fn acquire_write(&self) -> Result<WriteChannel, Error> {
+        let (fd, mtu) = self.characteristic.acquire_write(HashMap::new()).context(
+            &self.characteristic,
+            Self::INTERFACE,
+            "AcquireWrite",
+        )?;
+        WriteChannel::new(fd, mtu)
+    }
+
+    /// Read the value of the characteristic, retrying transient errors
+    /// according to the retry policy of the `Bluez` connection.
+    pub fn read_value(&self) -> Result<Vec<u8>, Error> {
+        self.read_value_with_retry(&self.retry_policy())
+    }
+
+    pub fn read_value_with_retry(&self, policy: &RetryPolicy) -> Result<Vec<u8>, Error> {
+        policy.retry_on(self.bluez.as_deref(), true, || {
+            let options = dbus::arg::PropMap::new();
+            self.call_method("ReadValue", (options,))
+                .map(|(value,)| value)
+        })
     }
 
     pub fn start_notify(&self) -> Result<(), Error> {
-        Ok(self.characteristic.start_notify()?)
+        self.characteristic.start_notify().context(
+            &self.characteristic,
+            Self::INTERFACE,
+            "StartNotify",
+        )
     }
 
     pub fn stop_notify(&self) -> Result<(), Error> {
-        Ok(self.characteristic.stop_notify()?)
+        self.characteristic.stop_notify().context(
+            &self.characteristic,
+            Self::INTERFACE,
+            "StopNotify",
+        )
     }
 
+    /// Write the value of the characteristic, retrying transient errors
+    /// according to the retry policy of the `Bluez` connection. Writes that
+    /// time out without a reply aren't retried, since the value may already
+    /// have been written.
     pub fn write_value(&self, buf: Vec<u8>) -> Result<(), Error> {
-        Ok(GattCharacteristic1::write_value(
-            &self.characteristic,
-            buf,
-            HashMap::new(),
-        )?)
+        self.write_value_with_retry(buf, &self.retry_policy())
+    }
+
+    pub fn write_value_with_retry(&self, buf: Vec<u8>, policy: &RetryPolicy) -> Result<(), Error> {
+        policy.retry_on(self.bluez.as_deref(), false, || {
+            let options = dbus::arg::PropMap::new();
+            self.call_method("WriteValue", (buf.clone(), options))
+        })
+    }
+
+    /// Read and write calls wait for the remote device, so they go through
+    /// `Bluez::call_method()` if the characteristic belongs to a `Bluez`.
+    fn call_method<A, R>(&self, member: &'static str, args: A) -> Result<R, Error>
+    where
+        A: dbus::arg::AppendAll,
+        R: dbus::arg::ReadAll,
+    {
+        let characteristic = &self.characteristic;
+        match &self.bluez {
+            Some(bluez) => bluez.call_method(characteristic, Self::INTERFACE, member, args),
+            None => characteristic.method_call(Self::INTERFACE, member, args),
+        }
+        .context(characteristic, Self::INTERFACE, member)
     }
 }
 
@@ -743,6 +1410,24 @@ impl Battery {
     }
 
     pub fn percentage(&self) -> Result<u8, Error> {
-        Ok(self.battery.percentage()?)
+        self.battery
+            .percentage()
+            .context(&self.battery, Self::INTERFACE, "Percentage")
+    }
+
+    /// Where the battery level comes from, e.g. "GATT Battery Service" or
+    /// "HFP". Returns `Ok(None)` if BlueZ doesn't say.
+    pub fn source(&self) -> Result<Option<String>, Error> {
+        crate::dbus::missing_as_default(Battery1::source(&self.battery).map(Some)).context(
+            &self.battery,
+            Self::INTERFACE,
+            "Source",
+        )
+    }
+
+    /// Subscribe to updates of the battery level. Only changes received
+    /// after this call are reported.
+    pub fn changes(&self) -> Result<BatteryChanges, Error> {
+        BatteryChanges::new(&self.battery)
     }
 }
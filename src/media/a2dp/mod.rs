@@ -0,0 +1,383 @@
+//! A2DP codec capabilities and configurations, in the format BlueZ exchanges
+//! with media endpoints.
+
+use crate::Error;
+
+#[cfg(test)]
+mod test;
+
+/// A2DP codec IDs, as used for `Endpoint::new()`.
+pub mod codec {
+    pub const SBC: u8 = 0x00;
+    pub const MPEG12: u8 = 0x01;
+    pub const AAC: u8 = 0x02;
+    pub const VENDOR: u8 = 0xFF;
+}
+
+/// The values whose bits are set in a bit field, where the first value is
+/// bit `top` and the following values the bits below it.
+fn bits<T: Copy>(values: &[T], field: u32, top: u32) -> Vec<T> {
+    values
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| field & (1 << (top - *i as u32)) != 0)
+        .map(|(_, &v)| v)
+        .collect()
+}
+
+/// Build a bit field from values, the inverse of `bits()`.
+fn to_bits<T: Copy + PartialEq + std::fmt::Debug>(
+    values: &[T],
+    selected: &[T],
+    top: u32,
+    name: &str,
+) -> Result<u32, Error> {
+    selected.iter().try_fold(0, |field, v| {
+        let i = values
+            .iter()
+            .position(|x| x == v)
+            .ok_or_else(|| Error::InvalidValue(format!("unsupported {}: {:?}", name, v)))?;
+        Ok(field | 1 << (top - i as u32))
+    })
+}
+
+fn check_len(buf: &[u8], len: usize, codec: &str) -> Result<(), Error> {
+    if buf.len() != len {
+        return Err(Error::InvalidValue(format!(
+            "{} capabilities must be {} bytes, got {}",
+            codec,
+            len,
+            buf.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Pick the first value in order of preference that both sides support.
+fn pick<T: Copy + PartialEq>(preference: &[T], local: &[T], remote: &[T]) -> Option<T> {
+    preference
+        .iter()
+        .copied()
+        .find(|v| local.contains(v) && remote.contains(v))
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ChannelMode {
+    Mono,
+    DualChannel,
+    Stereo,
+    JointStereo,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AllocationMethod {
+    Snr,
+    Loudness,
+}
+
+const SBC_FREQUENCIES: [u32; 4] = [16000, 32000, 44100, 48000];
+const SBC_CHANNEL_MODES: [ChannelMode; 4] = [
+    ChannelMode::Mono,
+    ChannelMode::DualChannel,
+    ChannelMode::Stereo,
+    ChannelMode::JointStereo,
+];
+const SBC_BLOCK_LENGTHS: [u8; 4] = [4, 8, 12, 16];
+const SBC_SUBBANDS: [u8; 2] = [4, 8];
+const SBC_ALLOCATION_METHODS: [AllocationMethod; 2] =
+    [AllocationMethod::Snr, AllocationMethod::Loudness];
+
+/// Bitpool range for high quality stereo, as recommended by the A2DP
+/// specification
+pub const SBC_MIN_BITPOOL: u8 = 2;
+pub const SBC_MAX_BITPOOL: u8 = 53;
+
+/// The SBC features an endpoint supports. Sampling frequencies are in Hz.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SbcCapabilities {
+    pub sampling_frequencies: Vec<u32>,
+    pub channel_modes: Vec<ChannelMode>,
+    pub block_lengths: Vec<u8>,
+    pub subbands: Vec<u8>,
+    pub allocation_methods: Vec<AllocationMethod>,
+    pub min_bitpool: u8,
+    pub max_bitpool: u8,
+}
+
+impl SbcCapabilities {
+    /// Everything SBC supports, with the recommended bitpool range.
+    pub fn all() -> Self {
+        Self {
+            sampling_frequencies: SBC_FREQUENCIES.to_vec(),
+            channel_modes: SBC_CHANNEL_MODES.to_vec(),
+            block_lengths: SBC_BLOCK_LENGTHS.to_vec(),
+            subbands: SBC_SUBBANDS.to_vec(),
+            allocation_methods: SBC_ALLOCATION_METHODS.to_vec(),
+            min_bitpool: SBC_MIN_BITPOOL,
+            max_bitpool: SBC_MAX_BITPOOL,
+        }
+    }
+
+    pub fn parse(buf: &[u8]) -> Result<Self, Error> {
+        check_len(buf, 4, "SBC")?;
+        Ok(Self {
+            sampling_frequencies: bits(&SBC_FREQUENCIES, (buf[0] >> 4).into(), 3),
+            channel_modes: bits(&SBC_CHANNEL_MODES, (buf[0] & 0x0F).into(), 3),
+            block_lengths: bits(&SBC_BLOCK_LENGTHS, (buf[1] >> 4).into(), 3),
+            subbands: bits(&SBC_SUBBANDS, ((buf[1] >> 2) & 0x03).into(), 1),
+            allocation_methods: bits(&SBC_ALLOCATION_METHODS, (buf[1] & 0x03).into(), 1),
+            min_bitpool: buf[2],
+            max_bitpool: buf[3],
+        })
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        let frequencies = to_bits(
+            &SBC_FREQUENCIES,
+            &self.sampling_frequencies,
+            3,
+            "SBC sampling frequency",
+        )?;
+        let channel_modes = to_bits(&SBC_CHANNEL_MODES, &self.channel_modes, 3, "channel mode")?;
+        let block_lengths = to_bits(&SBC_BLOCK_LENGTHS, &self.block_lengths, 3, "block length")?;
+        let subbands = to_bits(&SBC_SUBBANDS, &self.subbands, 1, "number of subbands")?;
+        let allocation_methods = to_bits(
+            &SBC_ALLOCATION_METHODS,
+            &self.allocation_methods,
+            1,
+            "allocation method",
+        )?;
+        Ok(vec![
+            (frequencies << 4 | channel_modes) as u8,
+            (block_lengths << 4 | subbands << 2 | allocation_methods) as u8,
+            self.min_bitpool,
+            self.max_bitpool,
+        ])
+    }
+
+    /// Choose the best configuration supported by both this and the remote
+    /// endpoint, or `None` if there is none.
+    pub fn select(&self, remote: &SbcCapabilities) -> Option<SbcConfiguration> {
+        let min_bitpool = self.min_bitpool.max(remote.min_bitpool);
+        let max_bitpool = self.max_bitpool.min(remote.max_bitpool);
+        if min_bitpool > max_bitpool {
+            return None;
+        }
+        Some(SbcConfiguration {
+            sampling_frequency: pick(
+                &[48000, 44100, 32000, 16000],
+                &self.sampling_frequencies,
+                &remote.sampling_frequencies,
+            )?,
+            channel_mode: pick(
+                &[
+                    ChannelMode::JointStereo,
+                    ChannelMode::Stereo,
+                    ChannelMode::DualChannel,
+                    ChannelMode::Mono,
+                ],
+                &self.channel_modes,
+                &remote.channel_modes,
+            )?,
+            block_length: pick(&[16, 12, 8, 4], &self.block_lengths, &remote.block_lengths)?,
+            subbands: pick(&[8, 4], &self.subbands, &remote.subbands)?,
+            allocation_method: pick(
+                &[AllocationMethod::Loudness, AllocationMethod::Snr],
+                &self.allocation_methods,
+                &remote.allocation_methods,
+            )?,
+            min_bitpool,
+            max_bitpool,
+        })
+    }
+}
+
+/// A single SBC configuration, chosen from the capabilities of both sides.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SbcConfiguration {
+    pub sampling_frequency: u32,
+    pub channel_mode: ChannelMode,
+    pub block_length: u8,
+    pub subbands: u8,
+    pub allocation_method: AllocationMethod,
+    pub min_bitpool: u8,
+    pub max_bitpool: u8,
+}
+
+impl SbcConfiguration {
+    /// Parse a configuration, which must have exactly one value for each
+    /// field.
+    pub fn parse(buf: &[u8]) -> Result<Self, Error> {
+        let c = SbcCapabilities::parse(buf)?;
+        Ok(Self {
+            sampling_frequency: single(&c.sampling_frequencies, "sampling frequency")?,
+            channel_mode: single(&c.channel_modes, "channel mode")?,
+            block_length: single(&c.block_lengths, "block length")?,
+            subbands: single(&c.subbands, "number of subbands")?,
+            allocation_method: single(&c.allocation_methods, "allocation method")?,
+            min_bitpool: c.min_bitpool,
+            max_bitpool: c.max_bitpool,
+        })
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        SbcCapabilities::from(*self).to_bytes()
+    }
+}
+
+impl From<SbcConfiguration> for SbcCapabilities {
+    fn from(c: SbcConfiguration) -> Self {
+        Self {
+            sampling_frequencies: vec![c.sampling_frequency],
+            channel_modes: vec![c.channel_mode],
+            block_lengths: vec![c.block_length],
+            subbands: vec![c.subbands],
+            allocation_methods: vec![c.allocation_method],
+            min_bitpool: c.min_bitpool,
+            max_bitpool: c.max_bitpool,
+        }
+    }
+}
+
+fn single<T: Copy>(values: &[T], name: &str) -> Result<T, Error> {
+    match values {
+        [v] => Ok(*v),
+        _ => Err(Error::InvalidValue(format!(
+            "configuration must have exactly one {}",
+            name
+        ))),
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum AacObjectType {
+    Mpeg2AacLc,
+    Mpeg4AacLc,
+    Mpeg4AacLtp,
+    Mpeg4AacScalable,
+}
+
+const AAC_OBJECT_TYPES: [AacObjectType; 4] = [
+    AacObjectType::Mpeg2AacLc,
+    AacObjectType::Mpeg4AacLc,
+    AacObjectType::Mpeg4AacLtp,
+    AacObjectType::Mpeg4AacScalable,
+];
+const AAC_FREQUENCIES: [u32; 12] = [
+    8000, 11025, 12000, 16000, 22050, 24000, 32000, 44100, 48000, 64000, 88200, 96000,
+];
+const AAC_CHANNELS: [u8; 2] = [1, 2];
+
+/// The AAC features an endpoint supports. Sampling frequencies are in Hz and
+/// the bitrate in bits per second, where 0 means unspecified.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AacCapabilities {
+    pub object_types: Vec<AacObjectType>,
+    pub sampling_frequencies: Vec<u32>,
+    pub channels: Vec<u8>,
+    pub vbr: bool,
+    pub bitrate: u32,
+}
+
+impl AacCapabilities {
+    pub fn parse(buf: &[u8]) -> Result<Self, Error> {
+        check_len(buf, 6, "AAC")?;
+        let frequencies = u32::from(buf[1]) << 4 | u32::from(buf[2]) >> 4;
+        Ok(Self {
+            // Newer object types in the low bits aren't supported
+            object_types: bits(&AAC_OBJECT_TYPES, u32::from(buf[0]), 7),
+            sampling_frequencies: bits(&AAC_FREQUENCIES, frequencies, 11),
+            channels: bits(&AAC_CHANNELS, u32::from(buf[2] >> 2 & 0x03), 1),
+            vbr: buf[3] & 0x80 != 0,
+            bitrate: u32::from(buf[3] & 0x7F) << 16 | u32::from(buf[4]) << 8 | u32::from(buf[5]),
+        })
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        let object_types = to_bits(&AAC_OBJECT_TYPES, &self.object_types, 7, "object type")?;
+        let frequencies = to_bits(
+            &AAC_FREQUENCIES,
+            &self.sampling_frequencies,
+            11,
+            "AAC sampling frequency",
+        )?;
+        let channels = to_bits(&AAC_CHANNELS, &self.channels, 1, "number of channels")?;
+        if self.bitrate > 0x7F_FFFF {
+            return Err(Error::InvalidValue(format!(
+                "AAC bitrate out of range: {}",
+                self.bitrate
+            )));
+        }
+        Ok(vec![
+            object_types as u8,
+            (frequencies >> 4) as u8,
+            ((frequencies & 0x0F) << 4 | channels << 2) as u8,
+            u8::from(self.vbr) << 7 | (self.bitrate >> 16) as u8,
+            (self.bitrate >> 8) as u8,
+            self.bitrate as u8,
+        ])
+    }
+
+    /// Choose the best configuration supported by both this and the remote
+    /// endpoint, or `None` if there is none.
+    pub fn select(&self, remote: &AacCapabilities) -> Option<AacConfiguration> {
+        let mut frequencies = AAC_FREQUENCIES;
+        frequencies.reverse();
+        let bitrate = match (self.bitrate, remote.bitrate) {
+            (0, b) | (b, 0) => b,
+            (a, b) => a.min(b),
+        };
+        Some(AacConfiguration {
+            object_type: pick(&AAC_OBJECT_TYPES, &self.object_types, &remote.object_types)?,
+            sampling_frequency: pick(
+                &frequencies,
+                &self.sampling_frequencies,
+                &remote.sampling_frequencies,
+            )?,
+            channels: pick(&[2, 1], &self.channels, &remote.channels)?,
+            vbr: self.vbr && remote.vbr,
+            bitrate,
+        })
+    }
+}
+
+/// A single AAC configuration, chosen from the capabilities of both sides.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AacConfiguration {
+    pub object_type: AacObjectType,
+    pub sampling_frequency: u32,
+    pub channels: u8,
+    pub vbr: bool,
+    pub bitrate: u32,
+}
+
+impl AacConfiguration {
+    /// Parse a configuration, which must have exactly one value for each
+    /// field.
+    pub fn parse(buf: &[u8]) -> Result<Self, Error> {
+        let c = AacCapabilities::parse(buf)?;
+        Ok(Self {
+            object_type: single(&c.object_types, "object type")?,
+            sampling_frequency: single(&c.sampling_frequencies, "sampling frequency")?,
+            channels: single(&c.channels, "number of channels")?,
+            vbr: c.vbr,
+            bitrate: c.bitrate,
+        })
+    }
+
+    pub fn to_bytes(&self) -> Result<Vec<u8>, Error> {
+        AacCapabilities::from(*self).to_bytes()
+    }
+}
+
+impl From<AacConfiguration> for AacCapabilities {
+    fn from(c: AacConfiguration) -> Self {
+        Self {
+            object_types: vec![c.object_type],
+            sampling_frequencies: vec![c.sampling_frequency],
+            channels: vec![c.channels],
+            vbr: c.vbr,
+            bitrate: c.bitrate,
+        }
+    }
+}
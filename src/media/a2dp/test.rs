@@ -0,0 +1,107 @@
+use super::*;
+
+#[test]
+fn sbc_capabilities() {
+    let all = SbcCapabilities::all();
+    assert_eq!(all.to_bytes().unwrap(), vec![0xFF, 0xFF, 2, 53]);
+    assert_eq!(SbcCapabilities::parse(&[0xFF, 0xFF, 2, 53]).unwrap(), all);
+
+    let caps = SbcCapabilities::parse(&[0x22, 0x15, 2, 35]).unwrap();
+    assert_eq!(caps.sampling_frequencies, vec![44100]);
+    assert_eq!(caps.channel_modes, vec![ChannelMode::Stereo]);
+    assert_eq!(caps.block_lengths, vec![16]);
+    assert_eq!(caps.subbands, vec![8]);
+    assert_eq!(caps.allocation_methods, vec![AllocationMethod::Loudness]);
+    assert!(SbcCapabilities::parse(&[0xFF, 0xFF, 2]).is_err());
+
+    let mut invalid = SbcCapabilities::all();
+    invalid.sampling_frequencies.push(8000);
+    assert!(invalid.to_bytes().is_err());
+}
+
+#[test]
+fn sbc_select() {
+    let remote = SbcCapabilities {
+        sampling_frequencies: vec![44100, 48000],
+        channel_modes: vec![ChannelMode::Mono, ChannelMode::Stereo],
+        block_lengths: vec![4, 8, 12, 16],
+        subbands: vec![4, 8],
+        allocation_methods: vec![AllocationMethod::Snr, AllocationMethod::Loudness],
+        min_bitpool: 10,
+        max_bitpool: 64,
+    };
+    let configuration = SbcCapabilities::all().select(&remote).unwrap();
+    assert_eq!(
+        configuration,
+        SbcConfiguration {
+            sampling_frequency: 48000,
+            channel_mode: ChannelMode::Stereo,
+            block_length: 16,
+            subbands: 8,
+            allocation_method: AllocationMethod::Loudness,
+            min_bitpool: 10,
+            max_bitpool: 53,
+        }
+    );
+    let bytes = configuration.to_bytes().unwrap();
+    assert_eq!(bytes, vec![0x12, 0x15, 10, 53]);
+    assert_eq!(SbcConfiguration::parse(&bytes).unwrap(), configuration);
+    // A capability blob has more than one value per field
+    assert!(SbcConfiguration::parse(&[0xFF, 0xFF, 2, 53]).is_err());
+
+    let disjoint = SbcCapabilities {
+        min_bitpool: 60,
+        ..remote
+    };
+    assert_eq!(SbcCapabilities::all().select(&disjoint), None);
+}
+
+#[test]
+fn aac_capabilities() {
+    let buf = [0x80, 0x01, 0x8C, 0x83, 0xE8, 0x00];
+    let caps = AacCapabilities::parse(&buf).unwrap();
+    assert_eq!(
+        caps,
+        AacCapabilities {
+            object_types: vec![AacObjectType::Mpeg2AacLc],
+            sampling_frequencies: vec![44100, 48000],
+            channels: vec![1, 2],
+            vbr: true,
+            bitrate: 256000,
+        }
+    );
+    assert_eq!(caps.to_bytes().unwrap(), buf);
+}
+
+#[test]
+fn aac_select() {
+    let local = AacCapabilities {
+        object_types: vec![AacObjectType::Mpeg2AacLc, AacObjectType::Mpeg4AacLc],
+        sampling_frequencies: vec![44100, 48000],
+        channels: vec![1, 2],
+        vbr: true,
+        bitrate: 0,
+    };
+    let remote = AacCapabilities {
+        object_types: vec![AacObjectType::Mpeg4AacLc],
+        sampling_frequencies: vec![44100],
+        channels: vec![2],
+        vbr: false,
+        bitrate: 320000,
+    };
+    let configuration = local.select(&remote).unwrap();
+    assert_eq!(
+        configuration,
+        AacConfiguration {
+            object_type: AacObjectType::Mpeg4AacLc,
+            sampling_frequency: 44100,
+            channels: 2,
+            vbr: false,
+            bitrate: 320000,
+        }
+    );
+    assert_eq!(
+        AacConfiguration::parse(&configuration.to_bytes().unwrap()).unwrap(),
+        configuration
+    );
+}
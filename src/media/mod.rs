@@ -0,0 +1,275 @@
+//! Media endpoints, through which BlueZ negotiates A2DP audio streams with
+//! remote devices.
+//!
+//! An endpoint announces a codec and its capabilities when it is registered
+//! with `Adapter::register_endpoint()`. BlueZ calls the `MediaEndpoint`
+//! handler to choose and set the configuration of each stream, while
+//! `Bluez::process()` is processing messages.
+
+pub mod a2dp;
+
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use dbus::arg::{PropMap, Variant};
+use dbus::MethodErr;
+use uuid::Uuid;
+
+use crate::gen::Media1;
+use crate::gen_server::{register_media_endpoint1, MediaEndpoint1};
+use crate::{get_optional_property, get_property, Adapter, Bluez, DBusProxy, Error, ResultExt};
+use a2dp::{codec, AacCapabilities, SbcCapabilities};
+
+#[cfg(test)]
+mod test;
+
+const MANAGER_INTERFACE: &str = "org.bluez.Media1";
+const TRANSPORT_INTERFACE: &str = "org.bluez.MediaTransport1";
+
+/// Error returned by a `MediaEndpoint` to reject a configuration.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, thiserror::Error)]
+pub enum EndpointError {
+    #[error("invalid arguments")]
+    InvalidArguments,
+    #[error("not supported")]
+    NotSupported,
+    #[error("rejected")]
+    Rejected,
+}
+
+impl EndpointError {
+    /// The D-Bus error name returned to BlueZ.
+    pub fn error_name(&self) -> &'static str {
+        match self {
+            Self::InvalidArguments => "org.bluez.Error.InvalidArguments",
+            Self::NotSupported => "org.bluez.Error.NotSupported",
+            Self::Rejected => "org.bluez.Error.Rejected",
+        }
+    }
+}
+
+impl From<EndpointError> for MethodErr {
+    fn from(e: EndpointError) -> Self {
+        (e.error_name(), e.to_string()).into()
+    }
+}
+
+/// A stream configured by BlueZ for an endpoint.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TransportConfiguration {
+    /// Path of the `org.bluez.MediaTransport1` object for the stream
+    pub transport: dbus::Path<'static>,
+    /// Object path of the remote device
+    pub device: dbus::Path<'static>,
+    pub uuid: Option<String>,
+    pub codec: Option<u8>,
+    /// Codec configuration, e.g. for `SbcConfiguration::parse()`
+    pub configuration: Vec<u8>,
+}
+
+impl TransportConfiguration {
+    fn parse(transport: dbus::Path<'static>, properties: &PropMap) -> Result<Self, MethodErr> {
+        let parse = || -> Result<Self, Error> {
+            Ok(Self {
+                transport: transport.clone(),
+                device: get_property(properties, TRANSPORT_INTERFACE, "Device")?,
+                uuid: get_optional_property(properties, "UUID")?,
+                codec: get_optional_property(properties, "Codec")?,
+                configuration: get_optional_property(properties, "Configuration")?
+                    .unwrap_or_default(),
+            })
+        };
+        parse().map_err(|e| MethodErr::invalid_arg(&e.to_string()))
+    }
+}
+
+/// Handler for calls from BlueZ to a media endpoint. Handlers are called
+/// from `Bluez::process()`, so they shouldn't block.
+pub trait MediaEndpoint: Send + 'static {
+    /// Choose a configuration from the capabilities of the remote endpoint,
+    /// when this side starts the stream.
+    fn select_configuration(&mut self, capabilities: &[u8]) -> Result<Vec<u8>, EndpointError>;
+
+    /// BlueZ set up a stream with the given configuration. Returning an
+    /// error rejects the configuration.
+    fn set_configuration(
+        &mut self,
+        configuration: &TransportConfiguration,
+    ) -> Result<(), EndpointError>;
+
+    /// The stream using `transport` was closed.
+    fn clear_configuration(&mut self, _transport: &dbus::Path<'static>) {}
+
+    /// BlueZ unregistered the endpoint, for example because the adapter was
+    /// removed.
+    fn release(&mut self) {}
+}
+
+/// Codec and capabilities announced by an endpoint.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Endpoint {
+    uuid: Uuid,
+    codec: u8,
+    capabilities: Vec<u8>,
+    delay_reporting: Option<bool>,
+}
+
+impl Endpoint {
+    /// An endpoint for the A2DP role with the given UUID, i.e.
+    /// `uuid16(0x110B)` for a sink or `uuid16(0x110A)` for a source, with
+    /// one of the codecs in `a2dp::codec`.
+    pub fn new(uuid: Uuid, codec: u8, capabilities: Vec<u8>) -> Self {
+        Self {
+            uuid,
+            codec,
+            capabilities,
+            delay_reporting: None,
+        }
+    }
+
+    pub fn sbc(uuid: Uuid, capabilities: &SbcCapabilities) -> Result<Self, Error> {
+        Ok(Self::new(uuid, codec::SBC, capabilities.to_bytes()?))
+    }
+
+    pub fn aac(uuid: Uuid, capabilities: &AacCapabilities) -> Result<Self, Error> {
+        Ok(Self::new(uuid, codec::AAC, capabilities.to_bytes()?))
+    }
+
+    /// Whether the endpoint reports its audio delay through the transport.
+    pub fn delay_reporting(mut self, delay_reporting: bool) -> Self {
+        self.delay_reporting = Some(delay_reporting);
+        self
+    }
+
+    /// Properties for `Media1.RegisterEndpoint`.
+    fn properties(&self) -> PropMap {
+        let mut properties = PropMap::new();
+        properties.insert("UUID".into(), Variant(Box::new(self.uuid.to_string())));
+        properties.insert("Codec".into(), Variant(Box::new(self.codec)));
+        properties.insert(
+            "Capabilities".into(),
+            Variant(Box::new(self.capabilities.clone())),
+        );
+        if let Some(delay_reporting) = self.delay_reporting {
+            properties.insert("DelayReporting".into(), Variant(Box::new(delay_reporting)));
+        }
+        properties
+    }
+}
+
+struct EndpointObject {
+    handler: Box<dyn MediaEndpoint>,
+    released: Arc<AtomicBool>,
+}
+
+impl MediaEndpoint1 for EndpointObject {
+    fn set_configuration(
+        &mut self,
+        transport: dbus::Path<'static>,
+        properties: PropMap,
+    ) -> Result<(), MethodErr> {
+        let configuration = TransportConfiguration::parse(transport, &properties)?;
+        Ok(self.handler.set_configuration(&configuration)?)
+    }
+
+    fn select_configuration(&mut self, capabilities: Vec<u8>) -> Result<Vec<u8>, MethodErr> {
+        Ok(self.handler.select_configuration(&capabilities)?)
+    }
+
+    fn clear_configuration(&mut self, transport: dbus::Path<'static>) -> Result<(), MethodErr> {
+        self.handler.clear_configuration(&transport);
+        Ok(())
+    }
+
+    fn release(&mut self) -> Result<(), MethodErr> {
+        self.released.store(true, Ordering::Relaxed);
+        self.handler.release();
+        Ok(())
+    }
+}
+
+/// A media endpoint registered with BlueZ. Dropping the handle unregisters
+/// the endpoint.
+pub struct EndpointHandle {
+    bluez: Rc<Bluez>,
+    adapter: DBusProxy,
+    path: dbus::Path<'static>,
+    released: Arc<AtomicBool>,
+}
+
+impl EndpointHandle {
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Whether BlueZ has released the endpoint, after which it is no longer
+    /// used and has to be registered again.
+    pub fn is_released(&self) -> Result<bool, Error> {
+        // Release is handled while processing messages
+        self.bluez.process(Duration::from_millis(0))?;
+        Ok(self.released.load(Ordering::Relaxed))
+    }
+}
+
+impl Drop for EndpointHandle {
+    fn drop(&mut self) {
+        let server = self.bluez.object_server();
+        if !self.released.load(Ordering::Relaxed) {
+            // BlueZ clears the configuration of open streams and releases
+            // the endpoint before replying, which reaches the handler while
+            // waiting
+            server
+                .call::<_, ()>(
+                    &self.adapter,
+                    MANAGER_INTERFACE,
+                    "UnregisterEndpoint",
+                    (self.path.clone(),),
+                )
+                .ok();
+        }
+        server.remove::<EndpointObject>(&self.path);
+    }
+}
+
+impl Adapter {
+    /// Export a media endpoint and register it with BlueZ, which offers it
+    /// to remote devices. The endpoint is unregistered when the returned
+    /// handle is dropped.
+    pub fn register_endpoint(
+        &self,
+        endpoint: Endpoint,
+        handler: impl MediaEndpoint,
+    ) -> Result<EndpointHandle, Error> {
+        let server = self.bluez.object_server();
+        let iface = server.interface("media-endpoint", register_media_endpoint1::<EndpointObject>);
+        let path = server.unique_path("endpoint");
+        let released = Arc::new(AtomicBool::new(false));
+        server.insert(
+            path.clone(),
+            &[iface],
+            EndpointObject {
+                handler: Box::new(handler),
+                released: released.clone(),
+            },
+        );
+        let handle = EndpointHandle {
+            bluez: self.bluez.clone(),
+            adapter: self.adapter.clone(),
+            path: path.clone(),
+            released,
+        };
+        // BlueZ doesn't call the endpoint while registering it, so a plain
+        // blocking call is fine. If it fails, the endpoint was never
+        // registered, so mark it released to only remove the object when
+        // the handle is dropped.
+        Media1::register_endpoint(&self.adapter, path, endpoint.properties())
+            .context(&self.adapter, MANAGER_INTERFACE, "RegisterEndpoint")
+            .map_err(|e| {
+                handle.released.store(true, Ordering::Relaxed);
+                e
+            })?;
+        Ok(handle)
+    }
+}
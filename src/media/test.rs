@@ -0,0 +1,66 @@
+use super::*;
+use crate::uuid16;
+
+#[test]
+fn endpoint_error_reply() {
+    let e = MethodErr::from(EndpointError::NotSupported);
+    assert_eq!(&**e.errorname(), "org.bluez.Error.NotSupported");
+}
+
+#[test]
+fn endpoint_properties() {
+    let endpoint = Endpoint::sbc(uuid16(0x110B), &a2dp::SbcCapabilities::all())
+        .unwrap()
+        .delay_reporting(true);
+    let properties = endpoint.properties();
+    assert_eq!(
+        get_optional_property::<&str>(&properties, "UUID").unwrap(),
+        Some("0000110b-0000-1000-8000-00805f9b34fb")
+    );
+    assert_eq!(
+        get_optional_property::<u8>(&properties, "Codec").unwrap(),
+        Some(codec::SBC)
+    );
+    assert_eq!(
+        get_optional_property::<Vec<u8>>(&properties, "Capabilities").unwrap(),
+        Some(vec![0xFF, 0xFF, 2, 53])
+    );
+    assert_eq!(
+        get_optional_property::<bool>(&properties, "DelayReporting").unwrap(),
+        Some(true)
+    );
+}
+
+#[test]
+fn parse_transport_configuration() {
+    let mut properties = PropMap::new();
+    properties.insert(
+        "Device".into(),
+        Variant(Box::new(dbus::Path::from(
+            "/org/bluez/hci0/dev_00_11_22_33_44_55",
+        ))),
+    );
+    properties.insert("Codec".into(), Variant(Box::new(codec::SBC)));
+    properties.insert(
+        "Configuration".into(),
+        Variant(Box::new(vec![0x21u8, 0x15, 2, 53])),
+    );
+    let transport = dbus::Path::from("/org/bluez/hci0/dev_00_11_22_33_44_55/fd0");
+    assert_eq!(
+        TransportConfiguration::parse(transport.clone(), &properties).unwrap(),
+        TransportConfiguration {
+            transport,
+            device: "/org/bluez/hci0/dev_00_11_22_33_44_55".into(),
+            uuid: None,
+            codec: Some(codec::SBC),
+            configuration: vec![0x21, 0x15, 2, 53],
+        }
+    );
+}
+
+#[test]
+fn parse_transport_configuration_without_device() {
+    let transport = dbus::Path::from("/org/bluez/hci0/dev_00_11_22_33_44_55/fd0");
+    let e = TransportConfiguration::parse(transport, &PropMap::new()).unwrap_err();
+    assert_eq!(&**e.errorname(), "org.freedesktop.DBus.Error.InvalidArgs");
+}
@@ -0,0 +1,190 @@
+//! Bluetooth networking with the Personal Area Network profile, to tether to
+//! a gateway or to share a network with other devices.
+
+use std::fmt;
+use std::rc::Rc;
+use std::time::Duration;
+
+use uuid::Uuid;
+
+use crate::gen::{Network1, NetworkServer1};
+use crate::{uuid16, Adapter, Bluez, DBusProxy, Device, Error, ResultExt};
+
+#[cfg(test)]
+mod test;
+
+const INTERFACE: &str = "org.bluez.Network1";
+const SERVER_INTERFACE: &str = "org.bluez.NetworkServer1";
+
+/// PAN role of a device.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum NetworkRole {
+    /// PAN user, a client of a NAP or GN
+    Panu,
+    /// Network access point, which bridges to another network
+    Nap,
+    /// Group ad-hoc network, which only connects its clients
+    Gn,
+}
+
+impl NetworkRole {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Panu => "panu",
+            Self::Nap => "nap",
+            Self::Gn => "gn",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "panu" => Some(Self::Panu),
+            "nap" => Some(Self::Nap),
+            "gn" => Some(Self::Gn),
+            _ => None,
+        }
+    }
+
+    pub fn uuid(self) -> Uuid {
+        match self {
+            Self::Panu => uuid16(0x1115),
+            Self::Nap => uuid16(0x1116),
+            Self::Gn => uuid16(0x1117),
+        }
+    }
+
+    pub fn from_uuid(uuid: Uuid) -> Option<Self> {
+        [Self::Panu, Self::Nap, Self::Gn]
+            .iter()
+            .copied()
+            .find(|r| r.uuid() == uuid)
+    }
+}
+
+impl fmt::Display for NetworkRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// PAN connection to a remote device, from `Device::network()`.
+pub struct Network {
+    bluez: Rc<Bluez>,
+    network: DBusProxy,
+    connect_timeout: Duration,
+}
+
+impl Network {
+    /// Connect to the PAN service of the remote device with the given role,
+    /// usually `NetworkRole::Nap`. Returns the name of the network interface
+    /// created for the connection, e.g. "bnep0", which still needs to be
+    /// configured, for example with DHCP.
+    pub fn connect(&self, role: NetworkRole) -> Result<String, Error> {
+        let proxy = DBusProxy {
+            timeout: self.connect_timeout,
+            ..self.network.clone()
+        };
+        self.bluez
+            .call_method(&proxy, INTERFACE, "Connect", (role.as_str(),))
+            .map(|(interface,)| interface)
+            .context(&proxy, INTERFACE, "Connect")
+    }
+
+    pub fn disconnect(&self) -> Result<(), Error> {
+        Network1::disconnect(&self.network).context(&self.network, INTERFACE, "Disconnect")
+    }
+
+    pub fn connected(&self) -> Result<bool, Error> {
+        Network1::connected(&self.network).context(&self.network, INTERFACE, "Connected")
+    }
+
+    /// Name of the network interface, or `None` if not connected.
+    pub fn interface(&self) -> Result<Option<String>, Error> {
+        crate::dbus::missing_as_default(Network1::interface(&self.network).map(Some)).context(
+            &self.network,
+            INTERFACE,
+            "Interface",
+        )
+    }
+
+    /// UUID of the role of the remote device in the connection, or `None` if
+    /// not connected.
+    pub fn uuid(&self) -> Result<Option<Uuid>, Error> {
+        Ok(
+            crate::dbus::missing_as_default(Network1::uuid(&self.network).map(Some))
+                .context(&self.network, INTERFACE, "UUID")?
+                .map(|u| Uuid::parse_str(&u))
+                .transpose()?,
+        )
+    }
+}
+
+/// A PAN server registered with `Adapter::register_network_server()`.
+/// Dropping it unregisters the server.
+pub struct NetworkServer {
+    adapter: DBusProxy,
+    role: NetworkRole,
+}
+
+impl NetworkServer {
+    pub fn role(&self) -> NetworkRole {
+        self.role
+    }
+}
+
+impl Drop for NetworkServer {
+    fn drop(&mut self) {
+        NetworkServer1::unregister(&self.adapter, self.role.as_str()).ok();
+    }
+}
+
+impl Adapter {
+    /// Accept PAN connections from remote devices in the given role, which
+    /// must be `NetworkRole::Nap` or `NetworkRole::Gn`. The interface of
+    /// each connection is added to `bridge`, which must be an existing
+    /// Linux bridge.
+    pub fn register_network_server(
+        &self,
+        role: NetworkRole,
+        bridge: &str,
+    ) -> Result<NetworkServer, Error> {
+        if role == NetworkRole::Panu {
+            return Err(Error::InvalidValue(
+                "network server role must be nap or gn".into(),
+            ));
+        }
+        NetworkServer1::register(&self.adapter, role.as_str(), bridge).context(
+            &self.adapter,
+            SERVER_INTERFACE,
+            "Register",
+        )?;
+        Ok(NetworkServer {
+            adapter: self.adapter.clone(),
+            role,
+        })
+    }
+}
+
+impl Device {
+    /// Get the PAN interface of the device, or `None` if it doesn't offer a
+    /// PAN service.
+    pub fn network(&self) -> Result<Option<Network>, Error> {
+        self.bluez.find_map_object(
+            |object, interfaces| {
+                Ok(
+                    (object == &self.device.path && interfaces.contains_key(INTERFACE)).then(
+                        || Network {
+                            bluez: self.bluez.clone(),
+                            network: self.bluez.with_proxy(
+                                object.clone().into_static(),
+                                self.timeouts.method_call,
+                            ),
+                            connect_timeout: self.timeouts.connect,
+                        },
+                    ),
+                )
+            },
+            Duration::from_millis(0),
+        )
+    }
+}
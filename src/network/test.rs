@@ -0,0 +1,12 @@
+use super::*;
+
+#[test]
+fn network_role() {
+    for role in [NetworkRole::Panu, NetworkRole::Nap, NetworkRole::Gn] {
+        assert_eq!(NetworkRole::from_name(role.as_str()), Some(role));
+        assert_eq!(NetworkRole::from_uuid(role.uuid()), Some(role));
+    }
+    assert_eq!(NetworkRole::Nap.uuid(), uuid16(0x1116));
+    assert_eq!(NetworkRole::from_name("NAP"), None);
+    assert_eq!(NetworkRole::from_uuid(uuid16(0x1101)), None);
+}
@@ -0,0 +1,435 @@
+//! Bluetooth Classic profiles implemented outside of BlueZ, such as the
+//! Serial Port Profile.
+//!
+//! A `Profile` is registered with `Bluez::register_profile()`. BlueZ then
+//! handles SDP and connection setup, and passes each connected RFCOMM or
+//! L2CAP socket to the profile as a `ProfileConnection`, which is received
+//! from `ProfileHandle::wait_event()`.
+
+use std::io::{Read, Write};
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, RawFd};
+use std::os::unix::net::UnixStream;
+use std::rc::Rc;
+use std::time::Duration;
+
+use dbus::arg::{OwnedFd, PropMap, Variant};
+use dbus::MethodErr;
+use uuid::Uuid;
+
+use crate::gen_server::{register_profile1, Profile1};
+use crate::io::stream_from_fd;
+use crate::server::EventQueue;
+use crate::util::Timeout;
+use crate::{get_optional_property, Bluez, DBusProxy, Device, Error, ResultExt, Timeouts};
+
+#[cfg(test)]
+mod test;
+
+const MANAGER_INTERFACE: &str = "org.bluez.ProfileManager1";
+const MANAGER_PATH: &str = "/org/bluez";
+
+/// Which side of the connection the profile implements.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    /// Connect to remote devices implementing the profile
+    Client,
+    /// Accept connections from remote devices
+    Server,
+}
+
+impl Role {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Client => "client",
+            Self::Server => "server",
+        }
+    }
+}
+
+/// A profile to register with BlueZ. Options that aren't set use the defaults
+/// BlueZ has for the UUID, if it knows the profile.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Profile {
+    uuid: Uuid,
+    name: Option<String>,
+    service: Option<Uuid>,
+    role: Option<Role>,
+    channel: Option<u16>,
+    psm: Option<u16>,
+    require_authentication: Option<bool>,
+    require_authorization: Option<bool>,
+    auto_connect: Option<bool>,
+    service_record: Option<String>,
+    version: Option<u16>,
+    features: Option<u16>,
+}
+
+impl Profile {
+    pub fn new(uuid: Uuid) -> Self {
+        Self {
+            uuid,
+            name: None,
+            service: None,
+            role: None,
+            channel: None,
+            psm: None,
+            require_authentication: None,
+            require_authorization: None,
+            auto_connect: None,
+            service_record: None,
+            version: None,
+            features: None,
+        }
+    }
+
+    /// Human readable name of the service in the SDP record.
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    /// The primary service class UUID in the SDP record, if different from
+    /// the profile UUID.
+    pub fn service(mut self, service: Uuid) -> Self {
+        self.service = Some(service);
+        self
+    }
+
+    pub fn role(mut self, role: Role) -> Self {
+        self.role = Some(role);
+        self
+    }
+
+    /// RFCOMM channel to listen on.
+    pub fn channel(mut self, channel: u16) -> Self {
+        self.channel = Some(channel);
+        self
+    }
+
+    /// L2CAP PSM to listen on.
+    pub fn psm(mut self, psm: u16) -> Self {
+        self.psm = Some(psm);
+        self
+    }
+
+    pub fn require_authentication(mut self, require: bool) -> Self {
+        self.require_authentication = Some(require);
+        self
+    }
+
+    pub fn require_authorization(mut self, require: bool) -> Self {
+        self.require_authorization = Some(require);
+        self
+    }
+
+    /// Whether BlueZ connects the profile automatically when a device
+    /// supporting it connects.
+    pub fn auto_connect(mut self, auto_connect: bool) -> Self {
+        self.auto_connect = Some(auto_connect);
+        self
+    }
+
+    /// Complete SDP record in XML format, replacing the one BlueZ would
+    /// generate.
+    pub fn service_record(mut self, record: impl Into<String>) -> Self {
+        self.service_record = Some(record.into());
+        self
+    }
+
+    /// Profile version in the SDP record.
+    pub fn version(mut self, version: u16) -> Self {
+        self.version = Some(version);
+        self
+    }
+
+    /// Profile features in the SDP record.
+    pub fn features(mut self, features: u16) -> Self {
+        self.features = Some(features);
+        self
+    }
+
+    /// Options for `ProfileManager1.RegisterProfile`.
+    fn options(&self) -> PropMap {
+        let mut options = PropMap::new();
+        let mut insert = |key: &str, value: Box<dyn dbus::arg::RefArg>| {
+            options.insert(key.to_owned(), Variant(value));
+        };
+        if let Some(name) = &self.name {
+            insert("Name", Box::new(name.clone()));
+        }
+        if let Some(service) = self.service {
+            insert("Service", Box::new(service.to_string()));
+        }
+        if let Some(role) = self.role {
+            insert("Role", Box::new(role.as_str().to_owned()));
+        }
+        if let Some(channel) = self.channel {
+            insert("Channel", Box::new(channel));
+        }
+        if let Some(psm) = self.psm {
+            insert("PSM", Box::new(psm));
+        }
+        if let Some(require) = self.require_authentication {
+            insert("RequireAuthentication", Box::new(require));
+        }
+        if let Some(require) = self.require_authorization {
+            insert("RequireAuthorization", Box::new(require));
+        }
+        if let Some(auto_connect) = self.auto_connect {
+            insert("AutoConnect", Box::new(auto_connect));
+        }
+        if let Some(record) = &self.service_record {
+            insert("ServiceRecord", Box::new(record.clone()));
+        }
+        if let Some(version) = self.version {
+            insert("Version", Box::new(version));
+        }
+        if let Some(features) = self.features {
+            insert("Features", Box::new(features));
+        }
+        options
+    }
+}
+
+/// Properties of a new connection, taken from the SDP record of the remote
+/// device.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct ConnectionProperties {
+    version: Option<u16>,
+    features: Option<u16>,
+}
+
+impl ConnectionProperties {
+    fn parse(properties: &PropMap) -> Result<Self, Error> {
+        Ok(Self {
+            version: get_optional_property(properties, "Version")?,
+            features: get_optional_property(properties, "Features")?,
+        })
+    }
+}
+
+/// Call from BlueZ to the profile object. Devices are only looked up once the
+/// event is picked up by `ProfileHandle::wait_event()`.
+#[derive(Debug)]
+enum RawEvent {
+    NewConnection(dbus::Path<'static>, OwnedFd, ConnectionProperties),
+    RequestDisconnection(dbus::Path<'static>),
+    Released,
+}
+
+pub enum ProfileEvent {
+    /// A device connected to the profile
+    NewConnection(ProfileConnection),
+    /// BlueZ asks to disconnect from the device, which is done by dropping
+    /// its `ProfileConnection`
+    RequestDisconnection(Device),
+    /// BlueZ unregistered the profile, for example because it exited
+    Released,
+}
+
+struct ProfileObject {
+    events: EventQueue<RawEvent>,
+}
+
+impl Profile1 for ProfileObject {
+    fn release(&mut self) -> Result<(), MethodErr> {
+        self.events.push(RawEvent::Released);
+        Ok(())
+    }
+
+    fn new_connection(
+        &mut self,
+        device: dbus::Path<'static>,
+        fd: OwnedFd,
+        fd_properties: PropMap,
+    ) -> Result<(), MethodErr> {
+        let properties =
+            ConnectionProperties::parse(&fd_properties).map_err(|e| MethodErr::failed(&e))?;
+        self.events
+            .push(RawEvent::NewConnection(device, fd, properties));
+        Ok(())
+    }
+
+    fn request_disconnection(&mut self, device: dbus::Path<'static>) -> Result<(), MethodErr> {
+        self.events.push(RawEvent::RequestDisconnection(device));
+        Ok(())
+    }
+}
+
+/// A connection to a profile, which reads and writes the RFCOMM or L2CAP
+/// socket passed by BlueZ. It is in blocking mode by default.
+///
+/// Dropping the connection closes the socket, which disconnects the profile.
+pub struct ProfileConnection {
+    device: Device,
+    stream: UnixStream,
+    properties: ConnectionProperties,
+}
+
+impl ProfileConnection {
+    /// The remote device.
+    pub fn device(&self) -> &Device {
+        &self.device
+    }
+
+    /// Profile version of the remote device, if known from its SDP record.
+    pub fn version(&self) -> Option<u16> {
+        self.properties.version
+    }
+
+    /// Profile features of the remote device, if known from its SDP record.
+    pub fn features(&self) -> Option<u16> {
+        self.properties.features
+    }
+
+    pub fn set_nonblocking(&self, nonblocking: bool) -> Result<(), Error> {
+        Ok(self.stream.set_nonblocking(nonblocking)?)
+    }
+
+    /// Set the timeout for blocking reads. `None` means reads will block
+    /// indefinitely.
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> Result<(), Error> {
+        Ok(self.stream.set_read_timeout(timeout)?)
+    }
+
+    /// Set the timeout for blocking writes. `None` means writes will block
+    /// indefinitely.
+    pub fn set_write_timeout(&self, timeout: Option<Duration>) -> Result<(), Error> {
+        Ok(self.stream.set_write_timeout(timeout)?)
+    }
+}
+
+impl Read for ProfileConnection {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.stream.read(buf)
+    }
+}
+
+impl Write for ProfileConnection {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.stream.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.stream.flush()
+    }
+}
+
+impl AsRawFd for ProfileConnection {
+    fn as_raw_fd(&self) -> RawFd {
+        self.stream.as_raw_fd()
+    }
+}
+
+impl AsFd for ProfileConnection {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.stream.as_fd()
+    }
+}
+
+/// A profile registered with BlueZ. Dropping the handle unregisters the
+/// profile, but connections that were already passed on stay open.
+pub struct ProfileHandle {
+    bluez: Rc<Bluez>,
+    manager: DBusProxy,
+    path: dbus::Path<'static>,
+    events: EventQueue<RawEvent>,
+    timeouts: Timeouts,
+    registered: bool,
+}
+
+impl ProfileHandle {
+    /// Wait for the next event from BlueZ, returning `Ok(None)` if the
+    /// timeout expires first. Events that arrived since the last call are
+    /// returned immediately.
+    pub fn wait_event(&self, timeout: Duration) -> Result<Option<ProfileEvent>, Error> {
+        let event = match self.events.wait(&self.bluez, timeout)? {
+            Some(event) => event,
+            None => return Ok(None),
+        };
+        let device = |path| Device::from_path(&self.bluez, path, &self.timeouts);
+        Ok(Some(match event {
+            RawEvent::NewConnection(path, fd, properties) => {
+                ProfileEvent::NewConnection(ProfileConnection {
+                    device: device(path)?,
+                    stream: stream_from_fd(fd)?,
+                    properties,
+                })
+            }
+            RawEvent::RequestDisconnection(path) => {
+                ProfileEvent::RequestDisconnection(device(path)?)
+            }
+            RawEvent::Released => ProfileEvent::Released,
+        }))
+    }
+
+    /// Wait for the next connection, skipping other events. Returns
+    /// `Ok(None)` if the timeout expires first.
+    pub fn accept(&self, timeout: Duration) -> Result<Option<ProfileConnection>, Error> {
+        let timeout = Timeout::start(timeout);
+        loop {
+            match self.wait_event(timeout.get())? {
+                Some(ProfileEvent::NewConnection(connection)) => return Ok(Some(connection)),
+                Some(_) => {}
+                None => return Ok(None),
+            }
+        }
+    }
+}
+
+impl Drop for ProfileHandle {
+    fn drop(&mut self) {
+        let server = self.bluez.object_server();
+        if self.registered {
+            server
+                .call::<_, ()>(
+                    &self.manager,
+                    MANAGER_INTERFACE,
+                    "UnregisterProfile",
+                    (self.path.clone(),),
+                )
+                .ok();
+        }
+        server.remove::<ProfileObject>(&self.path);
+    }
+}
+
+impl Bluez {
+    /// Export a profile and register it with BlueZ. Connections to the
+    /// profile are received through `ProfileHandle::wait_event()`. The
+    /// profile is unregistered when the returned handle is dropped.
+    pub fn register_profile(self: Rc<Self>, profile: Profile) -> Result<ProfileHandle, Error> {
+        let timeouts = self.timeouts();
+        let manager = self.with_proxy(MANAGER_PATH, timeouts.method_call);
+        let server = self.object_server();
+        let iface = server.interface("profile", register_profile1::<ProfileObject>);
+        let path = server.unique_path("profile");
+        let events = EventQueue::new();
+        server.insert(
+            path.clone(),
+            &[iface],
+            ProfileObject {
+                events: events.clone(),
+            },
+        );
+        let mut handle = ProfileHandle {
+            bluez: self.clone(),
+            manager: manager.clone(),
+            path: path.clone(),
+            events,
+            timeouts,
+            registered: false,
+        };
+        // If this fails, dropping the handle removes the object again
+        server
+            .call::<_, ()>(
+                &manager,
+                MANAGER_INTERFACE,
+                "RegisterProfile",
+                (path, profile.uuid.to_string(), profile.options()),
+            )
+            .context(&manager, MANAGER_INTERFACE, "RegisterProfile")?;
+        handle.registered = true;
+        Ok(handle)
+    }
+}
@@ -0,0 +1,45 @@
+use super::*;
+use crate::uuid16;
+
+#[test]
+fn profile_options() {
+    let profile = Profile::new(uuid16(0x1101))
+        .name("Serial Port")
+        .role(Role::Server)
+        .channel(3)
+        .require_authentication(true);
+    let options = profile.options();
+    assert_eq!(options.len(), 4);
+    assert_eq!(
+        get_optional_property::<&str>(&options, "Name").unwrap(),
+        Some("Serial Port")
+    );
+    assert_eq!(
+        get_optional_property::<&str>(&options, "Role").unwrap(),
+        Some("server")
+    );
+    assert_eq!(
+        get_optional_property::<u16>(&options, "Channel").unwrap(),
+        Some(3)
+    );
+    assert_eq!(
+        get_optional_property::<bool>(&options, "RequireAuthentication").unwrap(),
+        Some(true)
+    );
+    assert!(Profile::new(uuid16(0x1101)).options().is_empty());
+}
+
+#[test]
+fn connection_properties() {
+    let mut properties = PropMap::new();
+    properties.insert("Version".into(), Variant(Box::new(0x0102u16)));
+    assert_eq!(
+        ConnectionProperties::parse(&properties).unwrap(),
+        ConnectionProperties {
+            version: Some(0x0102),
+            features: None,
+        }
+    );
+    properties.insert("Features".into(), Variant(Box::new("bogus".to_owned())));
+    assert!(ConnectionProperties::parse(&properties).is_err());
+}
@@ -0,0 +1,162 @@
+//! Retrying of BlueZ operations that failed with a transient error.
+
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::thread;
+use std::time::Duration;
+
+use log::debug;
+
+use crate::util::Timeout;
+use crate::{Bluez, Error};
+
+#[cfg(test)]
+mod test;
+
+/// Policy for retrying operations that fail with a transient error, as
+/// determined by `Error::is_transient()`. Other errors are returned
+/// immediately.
+///
+/// The default policy makes a single attempt, like `RetryPolicy::none()`, so
+/// retries have to be enabled by raising `max_attempts`.
+///
+/// The delay before each retry grows exponentially from `initial_backoff`,
+/// up to `max_backoff`, and is never shorter than the delay suggested by
+/// `Error::retry_after()`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts, including the first one
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    /// Factor the backoff is multiplied by after each attempt
+    pub multiplier: u32,
+    /// Fraction of the backoff that is randomly added or subtracted, between
+    /// 0.0 and 1.0
+    pub jitter: f64,
+    /// Overall time limit for all attempts. No retry is made if it would start
+    /// after the deadline.
+    pub deadline: Option<Duration>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 1,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(5),
+            multiplier: 2,
+            jitter: 0.2,
+            deadline: None,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Policy that never retries.
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    /// Policy that makes up to `max_attempts` attempts with the default
+    /// backoff.
+    pub fn attempts(max_attempts: u32) -> Self {
+        Self {
+            max_attempts,
+            ..Self::default()
+        }
+    }
+
+    /// Backoff before the given retry, starting at 1, without jitter.
+    fn backoff(&self, retry: u32) -> Duration {
+        let factor = self.multiplier.saturating_pow(retry.saturating_sub(1));
+        self.initial_backoff
+            .checked_mul(factor)
+            .map_or(self.max_backoff, |b| b.min(self.max_backoff))
+    }
+
+    /// Apply jitter to a backoff, with `random` uniformly distributed between
+    /// -1.0 and 1.0.
+    fn jittered(&self, backoff: Duration, random: f64) -> Duration {
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        backoff.mul_f64(1.0 + jitter * random)
+    }
+
+    /// Run an operation until it succeeds, fails with a non-transient error,
+    /// or the attempts or deadline are exhausted. In the latter case the last
+    /// error is returned. The thread sleeps between attempts.
+    pub fn retry<T>(&self, operation: impl FnMut() -> Result<T, Error>) -> Result<T, Error> {
+        self.retry_operation(operation, true, sleep)
+    }
+
+    /// Like `retry()`, for operations that must not be repeated if they may
+    /// already have taken effect, such as writes. A call that timed out
+    /// without a reply may have reached the device, so it isn't retried.
+    pub fn retry_non_idempotent<T>(
+        &self,
+        operation: impl FnMut() -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        self.retry_operation(operation, false, sleep)
+    }
+
+    /// Retry an operation of this crate. If it belongs to a `Bluez`
+    /// connection, messages are processed between attempts instead of
+    /// sleeping, so that exported objects, which BlueZ may call while
+    /// pairing for example, keep being answered.
+    pub(crate) fn retry_on<T>(
+        &self,
+        bluez: Option<&Bluez>,
+        idempotent: bool,
+        operation: impl FnMut() -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        match bluez {
+            Some(bluez) => self.retry_operation(operation, idempotent, |delay| bluez.wait(delay)),
+            None => self.retry_operation(operation, idempotent, sleep),
+        }
+    }
+
+    /// `wait` is called with the delay before each retry.
+    fn retry_operation<T>(
+        &self,
+        mut operation: impl FnMut() -> Result<T, Error>,
+        idempotent: bool,
+        mut wait: impl FnMut(Duration) -> Result<(), Error>,
+    ) -> Result<T, Error> {
+        let deadline = self.deadline.map(Timeout::start);
+        let mut attempt = 1;
+        loop {
+            let error = match operation() {
+                Ok(value) => return Ok(value),
+                Err(e) => e,
+            };
+            if !idempotent && error.is_no_reply() {
+                return Err(error);
+            }
+            let retry_after = match error.retry_after() {
+                Some(retry_after) if attempt < self.max_attempts => retry_after,
+                _ => return Err(error),
+            };
+            let delay = self
+                .jittered(self.backoff(attempt), random())
+                .max(retry_after);
+            if deadline.as_ref().is_some_and(|d| d.get() <= delay) {
+                return Err(error);
+            }
+            debug!("Retrying in {:?} after transient error: {}", delay, error);
+            wait(delay)?;
+            attempt += 1;
+        }
+    }
+}
+
+fn sleep(delay: Duration) -> Result<(), Error> {
+    thread::sleep(delay);
+    Ok(())
+}
+
+/// Random number between -1.0 and 1.0. Jitter doesn't need to be of high
+/// quality, so the randomly seeded hasher from the standard library is enough.
+fn random() -> f64 {
+    let bits = RandomState::new().build_hasher().finish();
+    (bits >> 11) as f64 / (1u64 << 53) as f64 * 2.0 - 1.0
+}
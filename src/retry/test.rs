@@ -0,0 +1,163 @@
+use std::cell::Cell;
+
+use super::*;
+
+fn transient() -> Error {
+    dbus::Error::new_custom(
+        "org.bluez.Error.InProgress",
+        "Operation already in progress",
+    )
+    .into()
+}
+
+fn fast_policy(max_attempts: u32) -> RetryPolicy {
+    RetryPolicy {
+        max_attempts,
+        initial_backoff: Duration::from_millis(1),
+        jitter: 0.0,
+        ..RetryPolicy::default()
+    }
+}
+
+#[test]
+fn backoff() {
+    let policy = RetryPolicy {
+        initial_backoff: Duration::from_millis(100),
+        max_backoff: Duration::from_millis(1000),
+        multiplier: 3,
+        ..RetryPolicy::default()
+    };
+    assert_eq!(policy.backoff(1), Duration::from_millis(100));
+    assert_eq!(policy.backoff(2), Duration::from_millis(300));
+    assert_eq!(policy.backoff(3), Duration::from_millis(900));
+    assert_eq!(policy.backoff(4), Duration::from_millis(1000));
+    assert_eq!(policy.backoff(100), Duration::from_millis(1000));
+    assert_eq!(
+        policy.jittered(Duration::from_millis(100), -1.0),
+        Duration::from_millis(80)
+    );
+    for _ in 0..100 {
+        assert!((-1.0..=1.0).contains(&random()));
+    }
+}
+
+#[test]
+fn stops_after_max_attempts() {
+    let attempts = Cell::new(0);
+    // Use a retry delay of zero by failing with a D-Bus timeout
+    let result: Result<(), _> = fast_policy(3).retry(|| {
+        attempts.set(attempts.get() + 1);
+        Err(dbus::Error::new_custom("org.freedesktop.DBus.Error.NoReply", "timeout").into())
+    });
+    assert!(result.unwrap_err().is_transient());
+    assert_eq!(attempts.get(), 3);
+}
+
+#[test]
+fn does_not_retry_permanent_errors() {
+    let attempts = Cell::new(0);
+    let result: Result<(), _> = fast_policy(3).retry(|| {
+        attempts.set(attempts.get() + 1);
+        Err(dbus::Error::new_custom("org.bluez.Error.NotAuthorized", "Not authorized").into())
+    });
+    assert!(result.is_err());
+    assert_eq!(attempts.get(), 1);
+}
+
+#[test]
+fn deadline() {
+    let attempts = Cell::new(0);
+    let policy = RetryPolicy {
+        deadline: Some(Duration::from_millis(100)),
+        ..fast_policy(10)
+    };
+    // InProgress suggests waiting longer than the deadline
+    let result: Result<(), _> = policy.retry(|| {
+        attempts.set(attempts.get() + 1);
+        Err(transient())
+    });
+    assert!(result.is_err());
+    assert_eq!(attempts.get(), 1);
+}
+
+#[test]
+fn succeeds_after_retry() {
+    let attempts = Cell::new(0);
+    let result = fast_policy(3).retry(|| {
+        attempts.set(attempts.get() + 1);
+        if attempts.get() < 2 {
+            Err(dbus::Error::new_custom("org.freedesktop.DBus.Error.NoReply", "timeout").into())
+        } else {
+            Ok(42)
+        }
+    });
+    assert_eq!(result.unwrap(), 42);
+}
+
+#[test]
+fn default_does_not_retry() {
+    assert_eq!(RetryPolicy::default(), RetryPolicy::none());
+    let attempts = Cell::new(0);
+    let result: Result<(), _> = RetryPolicy::default().retry(|| {
+        attempts.set(attempts.get() + 1);
+        Err(transient())
+    });
+    assert!(result.is_err());
+    assert_eq!(attempts.get(), 1);
+}
+
+#[test]
+fn non_idempotent_no_reply() {
+    let attempts = Cell::new(0);
+    let result: Result<(), _> = fast_policy(3).retry_non_idempotent(|| {
+        attempts.set(attempts.get() + 1);
+        Err(dbus::Error::new_custom("org.freedesktop.DBus.Error.NoReply", "timeout").into())
+    });
+    assert!(result.unwrap_err().is_no_reply());
+    assert_eq!(attempts.get(), 1);
+
+    // Errors reported by BlueZ mean the operation didn't happen
+    let attempts = Cell::new(0);
+    let result = fast_policy(3).retry_non_idempotent(|| {
+        attempts.set(attempts.get() + 1);
+        if attempts.get() < 2 {
+            Err(dbus::Error::new_custom("org.bluez.Error.NotReady", "Resource Not Ready").into())
+        } else {
+            Ok(())
+        }
+    });
+    assert!(result.is_ok());
+    assert_eq!(attempts.get(), 2);
+}
+
+#[test]
+fn wait_between_attempts() {
+    let mut delays = vec![];
+    let attempts = Cell::new(0);
+    let result = fast_policy(3).retry_operation(
+        || {
+            attempts.set(attempts.get() + 1);
+            if attempts.get() < 3 {
+                Err(transient())
+            } else {
+                Ok(())
+            }
+        },
+        true,
+        |delay| {
+            delays.push(delay);
+            Ok(())
+        },
+    );
+    assert!(result.is_ok());
+    assert_eq!(delays.len(), 2);
+    assert!(delays[1] >= Duration::from_millis(2));
+
+    // An error while waiting, such as a lost connection, ends the retries
+    let result: Result<(), _> = fast_policy(3).retry_operation(
+        || Err(transient()),
+        true,
+        |_| Err(Error::InvalidValue("connection lost".into())),
+    );
+    assert!(matches!(result, Err(Error::InvalidValue(_))));
+}
@@ -0,0 +1,153 @@
+//! Serial Port Profile client, for classic Bluetooth devices that expose a
+//! byte stream over RFCOMM, such as barcode scanners and OBD-II dongles.
+
+use std::io::{BufRead, BufReader, Read, Write};
+use std::time::Duration;
+
+use uuid::Uuid;
+
+use crate::profile::{Profile, ProfileConnection, ProfileHandle, Role};
+use crate::{Device, Error, ResultExt};
+
+#[cfg(test)]
+mod test;
+
+/// An RFCOMM connection opened with `Device::open_serial()`. Besides `Read`
+/// and `Write`, it has helpers for the line based protocols most serial
+/// devices speak. Reads go through a buffer, so mixing `read()` and
+/// `read_line()` is fine.
+///
+/// Dropping the port closes the connection.
+pub struct SerialPort {
+    reader: BufReader<ProfileConnection>,
+    /// Part of a line received before a read timed out
+    pending: Vec<u8>,
+    line_ending: Vec<u8>,
+    // Unregistering the profile would disconnect it
+    _profile: ProfileHandle,
+}
+
+impl SerialPort {
+    /// The remote device.
+    pub fn device(&self) -> &Device {
+        self.reader.get_ref().device()
+    }
+
+    /// Set the line ending appended by `write_line()`, which is "\r\n" by
+    /// default. Many OBD-II adapters expect just "\r".
+    pub fn set_line_ending(&mut self, line_ending: impl Into<Vec<u8>>) {
+        self.line_ending = line_ending.into();
+    }
+
+    /// Set the timeout for blocking reads. `None` means reads will block
+    /// indefinitely.
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> Result<(), Error> {
+        self.reader.get_ref().set_read_timeout(timeout)
+    }
+
+    /// Set the timeout for blocking writes. `None` means writes will block
+    /// indefinitely.
+    pub fn set_write_timeout(&self, timeout: Option<Duration>) -> Result<(), Error> {
+        self.reader.get_ref().set_write_timeout(timeout)
+    }
+
+    /// Read the next non-empty line, without its line ending. Lines may end
+    /// with "\n", "\r" or "\r\n". Returns `Ok(None)` once the device closed
+    /// the connection. If the read times out, the part of the line received
+    /// so far is kept for the next call.
+    pub fn read_line(&mut self) -> Result<Option<String>, Error> {
+        Ok(read_line(&mut self.reader, &mut self.pending)?)
+    }
+
+    /// Write `line` followed by the line ending.
+    pub fn write_line(&mut self, line: &str) -> Result<(), Error> {
+        let mut buf = Vec::with_capacity(line.len() + self.line_ending.len());
+        buf.extend_from_slice(line.as_bytes());
+        buf.extend_from_slice(&self.line_ending);
+        Ok(self.reader.get_mut().write_all(&buf)?)
+    }
+}
+
+/// Read a line from `reader` into `pending`, then take it out of `pending`.
+fn read_line(reader: &mut impl BufRead, pending: &mut Vec<u8>) -> std::io::Result<Option<String>> {
+    loop {
+        let buf = reader.fill_buf()?;
+        if buf.is_empty() {
+            // End of stream, return what is left
+            return Ok((!pending.is_empty())
+                .then(|| String::from_utf8_lossy(&std::mem::take(pending)).into_owned()));
+        }
+        match buf.iter().position(|&b| b == b'\n' || b == b'\r') {
+            Some(end) => {
+                pending.extend_from_slice(&buf[..end]);
+                reader.consume(end + 1);
+                // Skip the empty line between "\r" and "\n"
+                if !pending.is_empty() {
+                    return Ok(Some(
+                        String::from_utf8_lossy(&std::mem::take(pending)).into_owned(),
+                    ));
+                }
+            }
+            None => {
+                let len = buf.len();
+                pending.extend_from_slice(buf);
+                reader.consume(len);
+            }
+        }
+    }
+}
+
+impl Read for SerialPort {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        if !self.pending.is_empty() {
+            let len = buf.len().min(self.pending.len());
+            buf[..len].copy_from_slice(&self.pending[..len]);
+            self.pending.drain(..len);
+            return Ok(len);
+        }
+        self.reader.read(buf)
+    }
+}
+
+impl Write for SerialPort {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.reader.get_mut().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.reader.get_mut().flush()
+    }
+}
+
+impl Device {
+    /// Open a serial connection to the service with the given UUID, usually
+    /// the Serial Port Profile (`uuid16(0x1101)`). This registers a client
+    /// profile for the UUID with BlueZ for as long as the port is open.
+    pub fn open_serial(&self, uuid: Uuid) -> Result<SerialPort, Error> {
+        let profile = self
+            .bluez
+            .clone()
+            .register_profile(Profile::new(uuid).role(Role::Client))?;
+        let proxy = self.proxy_with_timeout(self.timeouts.connect);
+        // BlueZ only replies once the profile accepted the connection, so
+        // calls to the profile must be answered while waiting
+        self.bluez
+            .object_server()
+            .call::<_, ()>(
+                &proxy,
+                Device::INTERFACE,
+                "ConnectProfile",
+                (uuid.to_string(),),
+            )
+            .context(&proxy, Device::INTERFACE, "ConnectProfile")?;
+        let connection = profile
+            .accept(Duration::from_millis(0))?
+            .ok_or_else(|| dbus::Error::new_failed("profile connected without a connection"))?;
+        Ok(SerialPort {
+            reader: BufReader::new(connection),
+            pending: Vec::new(),
+            line_ending: b"\r\n".to_vec(),
+            _profile: profile,
+        })
+    }
+}
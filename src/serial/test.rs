@@ -0,0 +1,34 @@
+use std::io::Cursor;
+
+use super::*;
+
+#[test]
+fn read_lines() {
+    let mut reader = BufReader::with_capacity(4, Cursor::new(b"41 0C\r\n1A F8\r\r>".to_vec()));
+    let mut pending = Vec::new();
+    assert_eq!(
+        read_line(&mut reader, &mut pending).unwrap(),
+        Some("41 0C".into())
+    );
+    assert_eq!(
+        read_line(&mut reader, &mut pending).unwrap(),
+        Some("1A F8".into())
+    );
+    // The prompt isn't followed by a line ending
+    assert_eq!(
+        read_line(&mut reader, &mut pending).unwrap(),
+        Some(">".into())
+    );
+    assert_eq!(read_line(&mut reader, &mut pending).unwrap(), None);
+}
+
+#[test]
+fn read_line_keeps_partial_line() {
+    let mut pending = b"12".to_vec();
+    let mut reader = Cursor::new(b"34\n".to_vec());
+    assert_eq!(
+        read_line(&mut reader, &mut pending).unwrap(),
+        Some("1234".into())
+    );
+    assert!(pending.is_empty());
+}
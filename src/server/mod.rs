@@ -0,0 +1,327 @@
+//! Exporting objects on the BlueZ connection, for interfaces that BlueZ calls
+//! back into, such as GATT applications, agents and profiles.
+
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, VecDeque};
+use std::convert::TryInto;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use dbus::arg::{AppendAll, IterAppend, PropMap, ReadAll};
+use dbus::blocking::stdintf::org_freedesktop_dbus::{
+    ObjectManagerInterfacesAdded, ObjectManagerInterfacesRemoved, PropertiesPropertiesChanged,
+};
+use dbus::blocking::LocalConnection;
+use dbus::channel::{MatchingReceiver, Sender, Token};
+use dbus::message::{MatchRule, MessageType, SignalArgs};
+use dbus::{Message, MethodErr};
+use dbus_crossroads::{Crossroads, IfaceToken};
+
+use crate::util::{wait_for, Timeout};
+use crate::{Bluez, DBusProxy, Error};
+
+/// Prefix for the paths of all objects exported by this crate
+const ROOT_PATH: &str = "/blurst";
+
+/// Serves objects exported on the BlueZ connection, using `dbus_crossroads`.
+/// Every object gets `org.freedesktop.DBus.Properties` and introspection, and
+/// objects added with `add_object_manager()` also implement
+/// `org.freedesktop.DBus.ObjectManager`.
+///
+/// Method calls are answered whenever messages are processed: by
+/// `Bluez::process()`, by `call()`, and while this crate waits for signals.
+/// Calls that may take long, such as `Device::connect()`, `Device::pair()`,
+/// `Network::connect()` and reading or writing characteristics, go through
+/// `call()` once the server exists, so exported objects keep working while
+/// they wait. Other calls block without answering, so handlers that must run
+/// while a call of your own is pending should use `call()` for it. Get the
+/// server from `Bluez::object_server()`.
+pub struct ObjectServer {
+    connection: Rc<LocalConnection>,
+    crossroads: Rc<RefCell<Crossroads>>,
+    interfaces: RefCell<HashMap<&'static str, Box<dyn Any>>>,
+    next_id: Cell<u32>,
+    token: Token,
+}
+
+impl ObjectServer {
+    pub(crate) fn new(connection: Rc<LocalConnection>) -> Self {
+        let crossroads = Rc::new(RefCell::new(Crossroads::new()));
+        let token = {
+            let crossroads = crossroads.clone();
+            connection.start_receive(
+                MatchRule::new_method_call(),
+                Box::new(move |msg, conn| {
+                    match crossroads.try_borrow_mut() {
+                        Ok(mut cr) => {
+                            cr.handle_message(msg, conn).ok();
+                        }
+                        // Only possible if messages are processed from
+                        // within a handler
+                        Err(_) => {
+                            let reply =
+                                dbus::MethodErr::failed("object server is busy").to_message(&msg);
+                            conn.send(reply).ok();
+                        }
+                    }
+                    true
+                }),
+            )
+        };
+        Self {
+            connection,
+            crossroads,
+            interfaces: RefCell::new(HashMap::new()),
+            next_id: Cell::new(0),
+            token,
+        }
+    }
+
+    /// Access the objects and interfaces directly. `f` must not process
+    /// messages, since incoming calls can't be answered while it runs.
+    pub fn with_crossroads<R>(&self, f: impl FnOnce(&mut Crossroads) -> R) -> R {
+        f(&mut self.crossroads.borrow_mut())
+    }
+
+    /// Export an object implementing the given interfaces.
+    pub fn insert<T: Send + 'static>(
+        &self,
+        path: dbus::Path<'static>,
+        interfaces: &[IfaceToken<T>],
+        data: T,
+    ) {
+        self.with_crossroads(|cr| cr.insert(path, interfaces, data));
+    }
+
+    /// Remove an exported object, returning its data if it had type `T`.
+    pub fn remove<T: Send + 'static>(&self, path: &dbus::Path<'static>) -> Option<T> {
+        self.with_crossroads(|cr| cr.remove(path))
+    }
+
+    /// Access the data of an exported object, if it exists and has type `T`.
+    pub fn with_data<T: Send + 'static, R>(
+        &self,
+        path: &dbus::Path<'static>,
+        f: impl FnOnce(&mut T) -> R,
+    ) -> Option<R> {
+        self.with_crossroads(|cr| cr.data_mut(path).map(f))
+    }
+
+    /// Get the token for an interface, registering it the first time. `key`
+    /// must be unique for each interface definition, since the same D-Bus
+    /// interface may be registered with different members.
+    pub fn interface<T: Send + 'static>(
+        &self,
+        key: &'static str,
+        register: impl FnOnce(&mut Crossroads) -> IfaceToken<T>,
+    ) -> IfaceToken<T> {
+        let mut interfaces = self.interfaces.borrow_mut();
+        let token = interfaces
+            .entry(key)
+            .or_insert_with(|| Box::new(register(&mut self.crossroads.borrow_mut())));
+        *token
+            .downcast_ref::<IfaceToken<T>>()
+            .expect("interface registered with different data type")
+    }
+
+    /// Allocate a new unique object path, e.g. `/blurst/gatt0`.
+    pub fn unique_path(&self, name: &str) -> dbus::Path<'static> {
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+        format!("{}/{}{}", ROOT_PATH, name, id).into()
+    }
+
+    pub fn connection(&self) -> &Rc<LocalConnection> {
+        &self.connection
+    }
+
+    /// Export an object at `path` implementing
+    /// `org.freedesktop.DBus.ObjectManager`, which BlueZ uses to discover
+    /// the objects below it.
+    pub fn add_object_manager(&self, path: dbus::Path<'static>) {
+        self.with_crossroads(|cr| {
+            let object_manager = cr.object_manager();
+            cr.insert(path, &[object_manager], ());
+        });
+    }
+
+    /// Emit a signal from an exported object.
+    pub fn emit<S: SignalArgs + AppendAll>(
+        &self,
+        path: &dbus::Path<'_>,
+        signal: &S,
+    ) -> Result<(), Error> {
+        self.connection
+            .channel()
+            .send(signal.to_emit_message(path))
+            .map(|_| ())
+            .map_err(|_| dbus::Error::new_failed("failed to send signal").into())
+    }
+
+    /// Tell clients that properties of an exported object changed. The
+    /// crossroads property handlers must return the new values.
+    pub fn emit_properties_changed(
+        &self,
+        path: &dbus::Path<'_>,
+        interface: &str,
+        changed_properties: PropMap,
+    ) -> Result<(), Error> {
+        let signal = PropertiesPropertiesChanged {
+            interface_name: interface.to_owned(),
+            changed_properties,
+            invalidated_properties: Vec::new(),
+        };
+        self.emit(path, &signal)
+    }
+
+    /// Tell clients of the object manager at `manager` that an object below
+    /// it gained interfaces, with their properties.
+    pub fn emit_interfaces_added(
+        &self,
+        manager: &dbus::Path<'_>,
+        object: dbus::Path<'static>,
+        interfaces: HashMap<String, PropMap>,
+    ) -> Result<(), Error> {
+        self.emit(
+            manager,
+            &ObjectManagerInterfacesAdded { object, interfaces },
+        )
+    }
+
+    /// Tell clients of the object manager at `manager` that an object below
+    /// it lost interfaces.
+    pub fn emit_interfaces_removed(
+        &self,
+        manager: &dbus::Path<'_>,
+        object: dbus::Path<'static>,
+        interfaces: Vec<String>,
+    ) -> Result<(), Error> {
+        self.emit(
+            manager,
+            &ObjectManagerInterfacesRemoved { object, interfaces },
+        )
+    }
+
+    /// Call a method and wait for the reply while continuing to serve exported
+    /// objects. This is needed for calls like `RegisterApplication`, during
+    /// which BlueZ calls back into the objects being registered, which would
+    /// deadlock with a normal blocking call.
+    pub fn call<A: AppendAll, R: ReadAll>(
+        &self,
+        proxy: &DBusProxy,
+        interface: &str,
+        member: &str,
+        args: A,
+    ) -> Result<R, dbus::Error> {
+        let mut msg = Message::new_method_call(
+            proxy.destination.clone(),
+            proxy.path.clone(),
+            interface,
+            member,
+        )
+        .map_err(|e| dbus::Error::new_failed(&e))?;
+        args.append(&mut IterAppend::new(&mut msg));
+        let serial = self
+            .connection
+            .channel()
+            .send(msg)
+            .map_err(|_| dbus::Error::new_failed("failed to send message"))?;
+
+        let reply = Rc::new(RefCell::new(None));
+        let tokens: Vec<Token> = [MessageType::MethodReturn, MessageType::Error]
+            .iter()
+            .map(|&msg_type| {
+                let reply = reply.clone();
+                let mut rule = MatchRule::new();
+                rule.msg_type = Some(msg_type);
+                self.connection.start_receive(
+                    rule,
+                    Box::new(move |msg: Message, _: &_| {
+                        if msg.get_reply_serial() == Some(serial) {
+                            reply.replace(Some(msg));
+                        }
+                        true
+                    }),
+                )
+            })
+            .collect();
+
+        let timeout = Timeout::start(proxy.timeout);
+        let result = loop {
+            if let Some(msg) = reply.take() {
+                break Ok(msg);
+            }
+            if timeout.get() == Duration::from_millis(0) {
+                break Err(dbus::Error::new_custom(
+                    "org.freedesktop.DBus.Error.NoReply",
+                    "Did not receive a reply",
+                ));
+            }
+            if let Err(e) = self.connection.process(timeout.get()) {
+                break Err(e);
+            }
+        };
+        for token in tokens {
+            self.connection.stop_receive(token);
+        }
+
+        let mut msg = result?;
+        msg.as_result()?;
+        msg.read_all()
+    }
+}
+
+impl Drop for ObjectServer {
+    fn drop(&mut self) {
+        self.connection.stop_receive(self.token);
+    }
+}
+
+/// Calls to an exported object, queued by its method handlers until the
+/// handle that owns the object picks them up. Clones share the same queue.
+pub(crate) struct EventQueue<T>(Arc<Mutex<VecDeque<T>>>);
+
+impl<T> EventQueue<T> {
+    pub(crate) fn new() -> Self {
+        Self(Arc::new(Mutex::new(VecDeque::new())))
+    }
+
+    pub(crate) fn push(&self, event: T) {
+        self.0.lock().unwrap().push_back(event);
+    }
+
+    /// Process messages until an event is queued, returning `Ok(None)` if
+    /// the timeout expires first.
+    pub(crate) fn wait(&self, bluez: &Bluez, timeout: Duration) -> Result<Option<T>, Error> {
+        wait_for(
+            timeout,
+            |t| bluez.process(t),
+            || self.0.lock().unwrap().pop_front(),
+        )
+    }
+}
+
+impl<T> Clone for EventQueue<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+/// Value of an optional property. Properties that aren't set are left out of
+/// `GetAll` by returning an error.
+pub(crate) fn optional<T>(value: Option<T>, name: &str) -> Result<T, MethodErr> {
+    value.ok_or_else(|| MethodErr::no_property(name))
+}
+
+/// Convert a duration to a property in seconds, saturating at the maximum.
+pub(crate) fn seconds(duration: Duration) -> u16 {
+    duration.as_secs().try_into().unwrap_or(u16::MAX)
+}
+
+/// Convert a duration to a property in milliseconds, saturating at the
+/// maximum.
+pub(crate) fn milliseconds(duration: Duration) -> u32 {
+    duration.as_millis().try_into().unwrap_or(u32::MAX)
+}
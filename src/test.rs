@@ -0,0 +1,78 @@
+use super::*;
+
+fn bluez_error(name: &str) -> Error {
+    dbus::Error::new_custom(name, "message").into()
+}
+
+#[test]
+fn error_kinds() {
+    assert_eq!(
+        bluez_error("org.bluez.Error.InProgress").kind(),
+        Some(&ErrorKind::InProgress)
+    );
+    // Unknown BlueZ errors keep their name
+    assert_eq!(
+        bluez_error("org.bluez.Error.NewError").kind(),
+        Some(&ErrorKind::Other("org.bluez.Error.NewError".into()))
+    );
+    assert!(matches!(
+        bluez_error("org.freedesktop.DBus.Error.UnknownMethod"),
+        Error::DBus { .. }
+    ));
+}
+
+#[test]
+fn transient_errors() {
+    for name in &[
+        "org.bluez.Error.InProgress",
+        "org.bluez.Error.NotReady",
+        "org.bluez.Error.ConnectionAttemptFailed",
+        "org.freedesktop.DBus.Error.NoReply",
+    ] {
+        assert!(bluez_error(name).is_transient(), "{}", name);
+    }
+    for name in &[
+        "org.bluez.Error.NotAuthorized",
+        "org.bluez.Error.DoesNotExist",
+        "org.bluez.Error.NewError",
+    ] {
+        let error = bluez_error(name);
+        assert!(!error.is_transient(), "{}", name);
+        assert_eq!(error.retry_after(), None);
+    }
+}
+
+#[test]
+fn error_context() {
+    let error = bluez_error("org.bluez.Error.InProgress").with_context(
+        "/org/bluez/hci0/dev_00_11_22_33_44_55",
+        Device::INTERFACE,
+        "Connect",
+    );
+    assert_eq!(error.context().unwrap().member, "Connect");
+    assert_eq!(
+        error.to_string(),
+        "/org/bluez/hci0/dev_00_11_22_33_44_55 org.bluez.Device1.Connect: InProgress: \
+         D-Bus error: Custom: message"
+    );
+}
+
+#[test]
+fn wait_for_queued_value() {
+    let processed = RefCell::new(vec![]);
+    let process = |timeout| {
+        processed.borrow_mut().push(timeout);
+        Ok(false)
+    };
+    // Values that are already queued are returned without waiting
+    let mut queue = vec![2, 1];
+    let value = util::wait_for(Duration::from_secs(60), process, || queue.pop());
+    assert_eq!(value.unwrap(), Some(1));
+    assert_eq!(*processed.borrow(), [Duration::from_millis(0)]);
+
+    // If nothing arrives, the wait ends without a value
+    processed.borrow_mut().clear();
+    let value = util::wait_for(Duration::from_secs(60), process, || None::<u8>);
+    assert_eq!(value.unwrap(), None);
+    assert_eq!(processed.borrow().len(), 2);
+}
@@ -1,5 +1,7 @@
 use std::time::{Duration, Instant};
 
+use crate::Error;
+
 pub struct Timeout {
     start: Instant,
     timeout: Duration,
@@ -19,3 +21,58 @@ impl Timeout {
             .unwrap_or_else(|| Duration::from_millis(0))
     }
 }
+
+/// Process messages with `process` until `next` returns a value, returning
+/// `Ok(None)` if the timeout expires first. Messages that are already queued
+/// are processed before `next` is called, so values that arrived since the
+/// last call are returned immediately.
+pub fn wait_for<T>(
+    timeout: Duration,
+    mut process: impl FnMut(Duration) -> Result<bool, Error>,
+    mut next: impl FnMut() -> Option<T>,
+) -> Result<Option<T>, Error> {
+    let timeout = Timeout::start(timeout);
+    loop {
+        while process(Duration::from_millis(0))? {}
+        if let Some(value) = next() {
+            return Ok(Some(value));
+        }
+        if timeout.get() == Duration::from_millis(0) || !process(timeout.get())? {
+            return Ok(None);
+        }
+    }
+}
+
+/// Parse a Bluetooth address in the "XX:XX:XX:XX:XX:XX" format used by BlueZ.
+/// The bytes are returned in the same order as the string, which is most
+/// significant byte first.
+pub fn parse_address(address: &str) -> Result<[u8; 6], Error> {
+    let invalid = || Error::InvalidValue(format!("invalid Bluetooth address: {}", address));
+    let mut bytes = [0; 6];
+    let mut parts = address.split(':');
+    for b in bytes.iter_mut() {
+        *b = parts
+            .next()
+            .filter(|p| p.len() == 2)
+            .and_then(|p| u8::from_str_radix(p, 16).ok())
+            .ok_or_else(invalid)?;
+    }
+    if parts.next().is_some() {
+        return Err(invalid());
+    }
+    Ok(bytes)
+}
+
+/// Parse a 128-bit key from 32 hexadecimal digits, most significant byte
+/// first. `what` names the key in the error message.
+pub fn parse_key(hex: &str, what: &str) -> Result<[u8; 16], Error> {
+    let invalid = || Error::InvalidValue(format!("invalid {}: {}", what, hex));
+    if hex.len() != 32 || !hex.is_ascii() {
+        return Err(invalid());
+    }
+    let mut key = [0; 16];
+    for (i, b) in key.iter_mut().enumerate() {
+        *b = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).map_err(|_| invalid())?;
+    }
+    Ok(key)
+}
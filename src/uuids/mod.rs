@@ -0,0 +1,102 @@
+use std::convert::TryFrom;
+use std::fmt;
+
+use uuid::Uuid;
+
+mod assigned;
+#[cfg(test)]
+mod test;
+
+/// The Bluetooth base UUID, which 16 and 32-bit assigned numbers are relative
+/// to.
+pub const BLUETOOTH_BASE_UUID: Uuid = Uuid::from_u128(0x00000000_0000_1000_8000_00805F9B34FB);
+
+/// Mask of the bits of a UUID that must match the base UUID for it to be
+/// representable as a 16 or 32-bit assigned number.
+const BASE_MASK: u128 = (1 << 96) - 1;
+
+/// Create a UUID from a 16-bit assigned number.
+pub const fn uuid16(short: u16) -> Uuid {
+    uuid32(short as u32)
+}
+
+/// Create a UUID from a 32-bit assigned number.
+pub const fn uuid32(short: u32) -> Uuid {
+    Uuid::from_u128(BLUETOOTH_BASE_UUID.as_u128() | ((short as u128) << 96))
+}
+
+fn lookup(table: &'static [(u16, &'static str)], short: u16) -> Option<&'static str> {
+    table
+        .binary_search_by_key(&short, |(u, _)| *u)
+        .ok()
+        .map(|i| table[i].1)
+}
+
+/// Extension methods for converting between full UUIDs and Bluetooth assigned
+/// numbers.
+pub trait BluetoothUuidExt {
+    /// Get the 32-bit assigned number of this UUID, if it is derived from the
+    /// Bluetooth base UUID.
+    fn as_bluetooth_short(&self) -> Option<u32>;
+
+    /// Get the 16-bit assigned number of this UUID, if it is derived from the
+    /// Bluetooth base UUID and fits in 16 bits.
+    fn as_bluetooth_u16(&self) -> Option<u16>;
+
+    /// Get the name of a service, characteristic, descriptor or member UUID
+    /// assigned by the Bluetooth SIG.
+    fn assigned_name(&self) -> Option<&'static str>;
+
+    /// Get a displayable representation, which includes the assigned name if
+    /// there is one, e.g. "Battery Service (0x180F)".
+    fn display_name(&self) -> UuidName;
+}
+
+impl BluetoothUuidExt for Uuid {
+    fn as_bluetooth_short(&self) -> Option<u32> {
+        let value = self.as_u128();
+        if value & BASE_MASK == BLUETOOTH_BASE_UUID.as_u128() {
+            Some((value >> 96) as u32)
+        } else {
+            None
+        }
+    }
+
+    fn as_bluetooth_u16(&self) -> Option<u16> {
+        self.as_bluetooth_short()
+            .and_then(|short| u16::try_from(short).ok())
+    }
+
+    fn assigned_name(&self) -> Option<&'static str> {
+        let short = self.as_bluetooth_u16()?;
+        lookup(assigned::SERVICES, short)
+            .or_else(|| lookup(assigned::CHARACTERISTICS, short))
+            .or_else(|| lookup(assigned::DESCRIPTORS, short))
+            .or_else(|| lookup(assigned::MEMBERS, short))
+    }
+
+    fn display_name(&self) -> UuidName {
+        UuidName(*self)
+    }
+}
+
+/// Wrapper that formats a UUID with its assigned name, if it has one.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct UuidName(pub Uuid);
+
+impl fmt::Display for UuidName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self.0.assigned_name(), self.0.as_bluetooth_short()) {
+            (Some(name), Some(short)) => write!(f, "{} (0x{:04X})", name, short),
+            (None, Some(short)) if short <= 0xFFFF => write!(f, "0x{:04X}", short),
+            (None, Some(short)) => write!(f, "0x{:08X}", short),
+            _ => write!(f, "{}", self.0),
+        }
+    }
+}
+
+impl fmt::Debug for UuidName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, f)
+    }
+}
@@ -0,0 +1,51 @@
+use super::*;
+
+#[test]
+fn uuid16_base() {
+    assert_eq!(
+        uuid16(0x180F),
+        Uuid::parse_str("0000180f-0000-1000-8000-00805f9b34fb").unwrap()
+    );
+}
+
+#[test]
+fn short_round_trip() {
+    assert_eq!(uuid16(0x2A37).as_bluetooth_u16(), Some(0x2A37));
+    assert_eq!(uuid32(0x12345678).as_bluetooth_short(), Some(0x12345678));
+    assert_eq!(uuid32(0x12345678).as_bluetooth_u16(), None);
+}
+
+#[test]
+fn short_not_base() {
+    let uuid = Uuid::parse_str("6e400001-b5a3-f393-e0a9-e50e24dcca9e").unwrap();
+    assert_eq!(uuid.as_bluetooth_short(), None);
+}
+
+#[test]
+fn tables_sorted() {
+    for table in [
+        assigned::SERVICES,
+        assigned::CHARACTERISTICS,
+        assigned::DESCRIPTORS,
+        assigned::MEMBERS,
+    ] {
+        assert!(table.windows(2).all(|w| w[0].0 < w[1].0));
+    }
+}
+
+#[test]
+fn display_name() {
+    assert_eq!(
+        uuid16(0x180F).display_name().to_string(),
+        "Battery Service (0x180F)"
+    );
+    assert_eq!(
+        uuid16(0x2902).display_name().to_string(),
+        "Client Characteristic Configuration (0x2902)"
+    );
+    assert_eq!(uuid16(0xFFF0).display_name().to_string(), "0xFFF0");
+    assert_eq!(
+        format!("{:?}", uuid16(0xFEAA).display_name()),
+        "Google LLC (0xFEAA)"
+    );
+}